@@ -0,0 +1,355 @@
+use crate::{CyberGrindPattern, Rect};
+
+/// Where light comes from, for
+/// `CyberGrindPattern::occlusion_from()`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Light {
+    /// Light from far away, like the sun, coming from the
+    /// direction `(x, y, z)`. `x` and `y` are in tiles, and
+    /// `z` is in units of height, so `z` being twice the
+    /// length of `(x, y)` means the light rises 2 units of
+    /// height for every tile.
+    Direction { x: f32, y: f32, z: f32 },
+    /// Light, or an observer, `height` above a tile
+    Point { tile: (usize, usize), height: f32 },
+}
+
+impl CyberGrindPattern {
+    /// Gets the height at a point between tiles, by
+    /// bilinear interpolation of the four tiles around
+    /// it. Tile `(x, y)` is centered on the point
+    /// `(x, y)`, and points outside of the grid are
+    /// moved to its nearest edge.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(1, 0)].set_height(10);
+    /// assert_eq!(pat.sample_height(0.5, 0.0), 5.0);
+    /// assert_eq!(pat.sample_height(1.0, 0.5), 5.0);
+    /// assert_eq!(pat.sample_height(-3.0, 0.0), 0.0);
+    /// ```
+    pub fn sample_height(&self, x: f32, y: f32) -> f32 {
+        let x = x.clamp(0.0, 15.0);
+        let y = y.clamp(0.0, 15.0);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(15), (y0 + 1).min(15));
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+        let height = |x, y| self[(x, y)].height() as f32;
+        let top = height(x0, y0) * (1.0 - tx) + height(x1, y0) * tx;
+        let bottom = height(x0, y1) * (1.0 - tx) + height(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+
+    /// Returns whether someone standing on tile `a` can see
+    /// someone standing on tile `b`, with both of their eyes
+    /// `eye_height` above their tile. Tiles are treated as
+    /// solid columns, and the ray is blocked if it passes
+    /// through a tile taller than it at that point.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(5, 0)].set_height(3);
+    /// assert!(!pat.line_of_sight((0, 0), (10, 0), 2.0));
+    /// assert!(pat.line_of_sight((0, 0), (10, 0), 4.0));
+    /// assert!(pat.line_of_sight((0, 0), (10, 3), 2.0));
+    /// ```
+    pub fn line_of_sight(&self, a: (usize, usize), b: (usize, usize), eye_height: f32) -> bool {
+        let start = (a.0 as f32, a.1 as f32, self[a].height() as f32 + eye_height);
+        let end = (b.0 as f32, b.1 as f32, self[b].height() as f32 + eye_height);
+        self.ray_is_clear(start, end, [a, b])
+    }
+
+    // Walks a ray between two points, returning whether no
+    // tile other than the ones in `skip` is taller than it.
+    fn ray_is_clear(
+        &self,
+        start: (f32, f32, f32),
+        end: (f32, f32, f32),
+        skip: [(usize, usize); 2],
+    ) -> bool {
+        let distance = (end.0 - start.0).abs().max((end.1 - start.1).abs());
+        let steps = (distance * 8.0) as usize;
+
+        for step in 1..steps {
+            let t = step as f32 / steps as f32;
+            let x = start.0 + (end.0 - start.0) * t;
+            let y = start.1 + (end.1 - start.1) * t;
+            let ray = start.2 + (end.2 - start.2) * t;
+            let tile = (x.round() as usize, y.round() as usize);
+            if !skip.contains(&tile) && self[tile].height() as f32 > ray {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Finds which tiles are in shadow when lit by `light`.
+    /// Returns a map with one entry per tile, in the same
+    /// order as the pattern, which is true if the top of
+    /// the tile can't see the light.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Light};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(8, 8)].set_height(10);
+    ///
+    /// // Low sun in the west, casting a shadow to the east
+    /// let sun = Light::Direction { x: -1.0, y: 0.0, z: 0.5 };
+    /// let shadows = pat.occlusion_from(sun);
+    /// assert!(shadows[8 * 16 + 9]);
+    /// assert!(!shadows[8 * 16 + 7]);
+    /// ```
+    pub fn occlusion_from(&self, light: Light) -> [bool; 256] {
+        let mut shadows = [false; 256];
+        for (x, y) in Rect::FULL.positions() {
+            let surface = (x as f32, y as f32, self[(x, y)].height() as f32);
+            let lit = match light {
+                Light::Direction {
+                    x: dx,
+                    y: dy,
+                    z: dz,
+                } => {
+                    let horizontal = (dx * dx + dy * dy).sqrt();
+                    if horizontal == 0.0 {
+                        dz > 0.0
+                    } else {
+                        // Far enough to leave the grid from any tile
+                        let length = 24.0 / horizontal;
+                        let end = (
+                            surface.0 + dx * length,
+                            surface.1 + dy * length,
+                            surface.2 + dz * length,
+                        );
+                        let end = clip_ray(surface, end);
+                        self.ray_is_clear(surface, end, [(x, y); 2])
+                    }
+                }
+                Light::Point { tile, height } => {
+                    let source = (
+                        tile.0 as f32,
+                        tile.1 as f32,
+                        self[tile].height() as f32 + height,
+                    );
+                    self.ray_is_clear(surface, source, [(x, y), tile])
+                }
+            };
+            shadows[y * 16 + x] = !lit;
+        }
+        shadows
+    }
+
+    /// Finds which tiles are hidden from someone standing on
+    /// tile `from`, by checking `line_of_sight()` to every
+    /// tile with the same `eye_height`. Returns a map with one
+    /// entry per tile, in the same order as the pattern, which
+    /// is true if the tile is in cover.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(10, 7)] = Tile::with_height(5);
+    ///
+    /// // How much of the arena is hidden from the center
+    /// let cover = pat.cover_map((7, 7), 2.0);
+    /// let covered = cover.iter().filter(|&&hidden| hidden).count();
+    /// assert!(cover[7 * 16 + 11]);
+    /// assert!(!cover[7 * 16 + 3]);
+    /// assert!(covered < 256 / 4);
+    /// ```
+    pub fn cover_map(&self, from: (usize, usize), eye_height: f32) -> [bool; 256] {
+        let mut cover = [false; 256];
+        for (x, y) in Rect::FULL.positions() {
+            cover[y * 16 + x] = !self.line_of_sight(from, (x, y), eye_height);
+        }
+        cover
+    }
+
+    /// Gets the height of every tile walked over when
+    /// following `path` from point to point in straight
+    /// lines. Shared points are only counted once.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(2, 0)].set_height(4);
+    /// pat[(2, 2)].set_height(-3);
+    ///
+    /// let profile = pat.profile(&[(0, 0), (2, 0), (2, 2)]);
+    /// assert_eq!(profile, [0, 0, 4, 0, -3]);
+    /// ```
+    pub fn profile(&self, path: &[(usize, usize)]) -> Vec<i8> {
+        let mut heights: Vec<i8> = path
+            .first()
+            .map(|&pos| self[pos].height())
+            .into_iter()
+            .collect();
+        for segment in path.windows(2) {
+            let (start, end) = (segment[0], segment[1]);
+            let dx = end.0 as f32 - start.0 as f32;
+            let dy = end.1 as f32 - start.1 as f32;
+            let steps = dx.abs().max(dy.abs()) as usize;
+            for step in 1..=steps {
+                let t = step as f32 / steps as f32;
+                let x = (start.0 as f32 + dx * t).round() as usize;
+                let y = (start.1 as f32 + dy * t).round() as usize;
+                heights.push(self[(x, y)].height());
+            }
+        }
+        heights
+    }
+
+    /// Samples `CyberGrindPattern::sample_height()` every
+    /// `step` tiles along `path`, including both ends,
+    /// for a smooth cross-section of the terrain.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(1, 0)].set_height(4);
+    ///
+    /// let profile = pat.profile_sampled(&[(0.0, 0.0), (2.0, 0.0)], 0.5);
+    /// assert_eq!(profile, [0.0, 2.0, 4.0, 2.0, 0.0]);
+    /// ```
+    pub fn profile_sampled(&self, path: &[(f32, f32)], step: f32) -> Vec<f32> {
+        let mut heights: Vec<f32> = path
+            .first()
+            .map(|&(x, y)| self.sample_height(x, y))
+            .into_iter()
+            .collect();
+        if step <= 0.0 {
+            return heights;
+        }
+        let mut carried = 0.0;
+        for segment in path.windows(2) {
+            let (start, end) = (segment[0], segment[1]);
+            let length = ((end.0 - start.0).powi(2) + (end.1 - start.1).powi(2)).sqrt();
+            let mut distance = step - carried;
+            while distance <= length + 1e-4 {
+                let t = if length > 0.0 { distance / length } else { 1.0 };
+                heights.push(self.sample_height(
+                    start.0 + (end.0 - start.0) * t,
+                    start.1 + (end.1 - start.1) * t,
+                ));
+                distance += step;
+            }
+            carried = (length - (distance - step)).max(0.0);
+        }
+        heights
+    }
+
+    /// Works out how enclosed each tile is by taller tiles
+    /// around it, from 0.0 for open ground to 1.0 for the
+    /// bottom of a deep, narrow pit. For every tile, eight
+    /// directions are searched up to 4 tiles away for the
+    /// steepest rise, treating a unit of height as being
+    /// as long as a tile is wide. Returns one value per
+    /// tile, in the same order as the pattern.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::splat(Tile::with_height(10));
+    /// pat[(8, 8)].set_height(0);
+    ///
+    /// let ao = pat.ambient_occlusion();
+    /// assert!(ao[8 * 16 + 8] > 0.9);
+    /// assert_eq!(ao[0], 0.0);
+    /// ```
+    pub fn ambient_occlusion(&self) -> [f32; 256] {
+        const DIRECTIONS: [(i32, i32); 8] = [
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+        ];
+        let mut ao = [0.0; 256];
+        for (x, y) in Rect::FULL.positions() {
+            let height = self[(x, y)].height() as f32;
+            let mut total = 0.0;
+            for (dx, dy) in DIRECTIONS {
+                let mut steepest: f32 = 0.0;
+                for distance in 1..=4 {
+                    let (nx, ny) = (x as i32 + dx * distance, y as i32 + dy * distance);
+                    if !(0..16).contains(&nx) || !(0..16).contains(&ny) {
+                        break;
+                    }
+                    let rise = self[(nx as usize, ny as usize)].height() as f32 - height;
+                    let run = distance as f32 * ((dx * dx + dy * dy) as f32).sqrt();
+                    steepest = steepest.max(rise / run);
+                }
+                // Sine of the horizon angle
+                total += steepest / (1.0 + steepest * steepest).sqrt();
+            }
+            ao[y * 16 + x] = total / DIRECTIONS.len() as f32;
+        }
+        ao
+    }
+
+    /// Gets how fast the height changes along x and y at
+    /// every tile, in units of height per tile. Uses the
+    /// tiles on both sides, or the tile itself on the
+    /// edges of the grid. Returns one `(dx, dy)` pair per
+    /// tile, in the same order as the pattern.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// for x in 0..16 {
+    ///     pat.copy_tile_to_column((x as i8 * 2).into(), x);
+    /// }
+    /// assert_eq!(pat.gradients()[5 * 16 + 5], (2.0, 0.0));
+    /// ```
+    pub fn gradients(&self) -> [(f32, f32); 256] {
+        let mut gradients = [(0.0, 0.0); 256];
+        let height = |x: usize, y: usize| self[(x, y)].height() as f32;
+        for (x, y) in Rect::FULL.positions() {
+            let (left, right) = (x.saturating_sub(1), (x + 1).min(15));
+            let (up, down) = (y.saturating_sub(1), (y + 1).min(15));
+            let dx = (height(right, y) - height(left, y)) / (right - left) as f32;
+            let dy = (height(x, down) - height(x, up)) / (down - up) as f32;
+            gradients[y * 16 + x] = (dx, dy);
+        }
+        gradients
+    }
+
+    /// Gets the length of the steepest gradient
+    /// found by `CyberGrindPattern::gradients()`.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// assert_eq!(pat.max_slope(), 0.0);
+    ///
+    /// pat[(4, 4)].set_height(6);
+    /// assert_eq!(pat.max_slope(), 3.0);
+    /// ```
+    pub fn max_slope(&self) -> f32 {
+        self.gradients()
+            .iter()
+            .map(|(dx, dy)| (dx * dx + dy * dy).sqrt())
+            .fold(0.0, f32::max)
+    }
+}
+
+// Cuts a ray short where it leaves the grid.
+fn clip_ray(start: (f32, f32, f32), end: (f32, f32, f32)) -> (f32, f32, f32) {
+    let mut t: f32 = 1.0;
+    for (from, to) in [(start.0, end.0), (start.1, end.1)] {
+        if to > 15.49 {
+            t = t.min((15.49 - from) / (to - from));
+        } else if to < -0.49 {
+            t = t.min((-0.49 - from) / (to - from));
+        }
+    }
+    (
+        start.0 + (end.0 - start.0) * t,
+        start.1 + (end.1 - start.1) * t,
+        start.2 + (end.2 - start.2) * t,
+    )
+}
@@ -0,0 +1,139 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+use crate::{error::BinaryError, CyberGrindPattern, Prefab, Tile};
+
+/// Bytes every `.cgpb` file starts with.
+pub const BINARY_MAGIC: &[u8; 4] = b"CGPB";
+/// Version of the `.cgpb` format written by
+/// `CyberGrindPattern::to_binary()`.
+pub const BINARY_VERSION: u8 = 1;
+/// Size of a `.cgpb` file without any unknown prefabs.
+pub const BINARY_SIZE: usize = 4 + 1 + 256 + 128;
+
+// Nibble used for `Prefab::Unknown`, whose
+// bytes are stored after the packed prefabs
+const UNKNOWN_NIBBLE: u8 = 0xf;
+
+fn nibble(prefab: Prefab) -> u8 {
+    match prefab {
+        Prefab::None => 0,
+        Prefab::Melee => 1,
+        Prefab::Projectile => 2,
+        Prefab::HideousMass => 3,
+        Prefab::JumpPad => 4,
+        Prefab::Stairs => 5,
+        Prefab::Unknown(_) => UNKNOWN_NIBBLE,
+    }
+}
+
+fn from_nibble(nibble: u8) -> Option<Prefab> {
+    match nibble {
+        0 => Some(Prefab::None),
+        1 => Some(Prefab::Melee),
+        2 => Some(Prefab::Projectile),
+        3 => Some(Prefab::HideousMass),
+        4 => Some(Prefab::JumpPad),
+        5 => Some(Prefab::Stairs),
+        _ => None,
+    }
+}
+
+impl CyberGrindPattern {
+    /// Serializes the pattern into the compact `.cgpb` format,
+    /// which is always `BINARY_SIZE` bytes long unless the
+    /// pattern has unknown prefabs. The layout is:
+    /// - `BINARY_MAGIC` and `BINARY_VERSION`
+    /// - 256 heights, one signed byte per tile
+    /// - 128 bytes of prefabs, two per byte with the first
+    ///   tile in the low nibble. 0 to 5 are none, melee,
+    ///   projectile, hideous mass, jump pad and stairs, and
+    ///   15 is an unknown prefab
+    /// - the character of every unknown prefab, in tile order
+    ///
+    /// Tiles are in the same order as indexing
+    /// the pattern with numbers.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, BINARY_SIZE};
+    ///
+    /// let bytes = CyberGrindPattern::new().to_binary();
+    /// assert_eq!(bytes.len(), BINARY_SIZE);
+    /// assert!(bytes.starts_with(b"CGPB"));
+    /// ```
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(BINARY_SIZE);
+        bytes.extend_from_slice(BINARY_MAGIC);
+        bytes.push(BINARY_VERSION);
+        bytes.extend(self.tiles.iter().map(|tile| tile.height() as u8));
+        bytes.extend(
+            self.tiles
+                .chunks(2)
+                .map(|pair| nibble(pair[0].prefab()) | nibble(pair[1].prefab()) << 4),
+        );
+        for tile in &self.tiles {
+            if let Prefab::Unknown(char) = tile.prefab() {
+                bytes.push(char.byte());
+            }
+        }
+        bytes
+    }
+
+    #[cfg(feature = "std")]
+    /// Writes the pattern to `writer` in the `.cgpb` format.
+    /// See `CyberGrindPattern::to_binary()`.
+    pub fn write_binary<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), io::Error> {
+        writer.write_all(&self.to_binary())
+    }
+
+    /// Reads a pattern in the `.cgpb` format, written
+    /// by `CyberGrindPattern::to_binary()`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Prefab};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(5, 2)].set_height(-31);
+    /// pat[(5, 2)].set_prefab(Prefab::HideousMass);
+    ///
+    /// let read = CyberGrindPattern::parse_binary(&pat.to_binary()).unwrap();
+    /// assert_eq!(read[(5, 2)], pat[(5, 2)]);
+    /// ```
+    pub fn parse_binary(bytes: &[u8]) -> Result<CyberGrindPattern, BinaryError> {
+        if bytes.len() < BINARY_SIZE {
+            return Err(BinaryError::TooShort { len: bytes.len() });
+        }
+        if !bytes.starts_with(BINARY_MAGIC) {
+            return Err(BinaryError::NotBinary);
+        }
+        if bytes[4] != BINARY_VERSION {
+            return Err(BinaryError::UnsupportedVersion(bytes[4]));
+        }
+
+        let heights = &bytes[5..5 + 256];
+        let prefabs = &bytes[5 + 256..BINARY_SIZE];
+        let mut unknown = bytes[BINARY_SIZE..].iter();
+        let mut pattern = CyberGrindPattern::new();
+        for index in 0..256 {
+            let height = heights[index] as i8;
+            if !Tile::is_valid_height(height) {
+                return Err(BinaryError::Height { index });
+            }
+            let nibble = (prefabs[index / 2] >> (index % 2 * 4)) & 0xf;
+            let prefab = match nibble {
+                UNKNOWN_NIBBLE => match unknown.next() {
+                    // Known prefabs always have their own nibble
+                    Some(&byte) => Prefab::unknown(byte),
+                    None => return Err(BinaryError::TooShort { len: bytes.len() }),
+                },
+                _ => from_nibble(nibble),
+            }
+            .ok_or(BinaryError::Prefab { index })?;
+            pattern[index] = Tile::new(height, prefab);
+        }
+
+        if unknown.len() > 0 {
+            return Err(BinaryError::TrailingData);
+        }
+        Ok(pattern)
+    }
+}
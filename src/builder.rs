@@ -0,0 +1,96 @@
+use crate::{CyberGrindPattern, Tile};
+
+/// Builder for a `CyberGrindPattern`, created with
+/// `CyberGrindPattern::builder()`. Operations are applied
+/// in order, so later ones overwrite earlier ones.
+/// ```
+/// use tinycbg::{CyberGrindPattern, Prefab, Tile};
+///
+/// let pat = CyberGrindPattern::builder()
+///     .fill(Tile::with_height(2))
+///     .border(Tile::with_height(20))
+///     .row(8, Tile::with_height(-5))
+///     .at(8, 8, Tile::new(0, Prefab::JumpPad))
+///     .build();
+///
+/// assert_eq!(pat[(0, 7)].height(), 20);
+/// assert_eq!(pat[(0, 8)].height(), -5);
+/// assert_eq!(pat[(3, 8)].height(), -5);
+/// assert_eq!(pat[(8, 8)].prefab(), Prefab::JumpPad);
+/// assert_eq!(pat[(3, 3)].height(), 2);
+/// ```
+#[derive(Default, Clone, Copy, Debug)]
+pub struct PatternBuilder {
+    pattern: CyberGrindPattern,
+}
+
+impl PatternBuilder {
+    /// Sets every tile to `tile`.
+    pub const fn fill(mut self, tile: Tile) -> Self {
+        self.pattern = CyberGrindPattern::splat(tile);
+        self
+    }
+
+    /// Sets the outermost ring of tiles to `tile`.
+    pub const fn border(self, tile: Tile) -> Self {
+        self.row(0, tile)
+            .row(15, tile)
+            .column(0, tile)
+            .column(15, tile)
+    }
+
+    /// Sets every tile in row `row` to `tile`.
+    /// Panics if `row` is greater than 15.
+    pub const fn row(mut self, row: usize, tile: Tile) -> Self {
+        assert!(row < 16, "Row must be less than 16");
+        let mut x = 0;
+        while x < 16 {
+            self.pattern.tiles[row * 16 + x] = tile;
+            x += 1;
+        }
+        self
+    }
+
+    /// Sets every tile in column `column` to `tile`.
+    /// Panics if `column` is greater than 15.
+    pub const fn column(mut self, column: usize, tile: Tile) -> Self {
+        assert!(column < 16, "Column must be less than 16");
+        let mut y = 0;
+        while y < 16 {
+            self.pattern.tiles[y * 16 + column] = tile;
+            y += 1;
+        }
+        self
+    }
+
+    /// Sets the tile at `(x, y)` to `tile`.
+    /// Panics if `x` or `y` is greater than 15.
+    pub const fn at(mut self, x: usize, y: usize, tile: Tile) -> Self {
+        assert!(x < 16 && y < 16, "Coordinates must be less than 16");
+        self.pattern.tiles[y * 16 + x] = tile;
+        self
+    }
+
+    /// Builds the pattern.
+    pub const fn build(self) -> CyberGrindPattern {
+        self.pattern
+    }
+}
+
+impl CyberGrindPattern {
+    /// Creates a builder for a pattern,
+    /// starting from an empty one.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// const ARENA: CyberGrindPattern = CyberGrindPattern::builder()
+    ///     .border(Tile::with_height(10))
+    ///     .build();
+    /// assert_eq!(ARENA[(15, 4)].height(), 10);
+    /// ```
+    pub const fn builder() -> PatternBuilder {
+        PatternBuilder {
+            pattern: CyberGrindPattern::new(),
+        }
+    }
+}
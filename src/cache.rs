@@ -0,0 +1,87 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{error::IoError, CyberGrindPattern};
+
+struct CacheEntry {
+    len: u64,
+    modified: Option<SystemTime>,
+    pattern: CyberGrindPattern,
+}
+
+/// Memoizes parsed patterns by file path.
+/// A file is only parsed again if its size
+/// or modification time has changed since
+/// it was last loaded.
+/// ```no_run
+/// use tinycbg::PatternCache;
+///
+/// let mut cache = PatternCache::new();
+/// let first = cache.load("arena.cgp")?;
+/// let second = cache.load("arena.cgp")?; // Not parsed again
+/// # Ok::<(), tinycbg::error::IoError>(())
+/// ```
+#[derive(Default)]
+pub struct PatternCache {
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+impl PatternCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        PatternCache::default()
+    }
+
+    /// Returns the pattern at `path`, parsing
+    /// it only if it isn't cached or the file
+    /// has changed.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<CyberGrindPattern, IoError> {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path)?;
+        let len = metadata.len();
+        let modified = metadata.modified().ok();
+
+        if let Some(entry) = self.entries.get(path)
+            && entry.len == len
+            && entry.modified == modified
+            && modified.is_some()
+        {
+            return Ok(entry.pattern);
+        }
+
+        let pattern = CyberGrindPattern::parse_path(path)?;
+        self.entries.insert(
+            path.to_path_buf(),
+            CacheEntry {
+                len,
+                modified,
+                pattern,
+            },
+        );
+        Ok(pattern)
+    }
+
+    /// Removes the pattern at `path` from the cache.
+    pub fn invalidate<P: AsRef<Path>>(&mut self, path: P) {
+        self.entries.remove(path.as_ref());
+    }
+
+    /// Removes every pattern from the cache.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Gets the number of cached patterns.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,295 @@
+//! Charts of pattern statistics, drawn with `plotters` as SVG or
+//! PNG images so reports on a collection of patterns can include visuals.
+//!
+//! ```
+//! use tinycbg::chart::{Chart, Stat};
+//! use tinycbg::{CyberGrindPattern, Prefab, Tile};
+//!
+//! let high = CyberGrindPattern::splat(Tile::new(10, Prefab::None));
+//! let pack = [("flat", CyberGrindPattern::new()), ("high", high)];
+//! let chart = Chart::pack_stats(pack.iter().map(|(name, pat)| (*name, pat)), Stat::AverageHeight);
+//! assert_eq!(chart.values, [0.0, 10.0]);
+//! assert!(chart.to_svg().starts_with("<svg"));
+//! ```
+
+use plotters::{
+    coord::{ranged1d::SegmentValue, Shift},
+    prelude::*,
+};
+
+use crate::{render::encode_png, CyberGrindPattern, Prefab};
+
+/// Width of a rendered chart in pixels.
+pub const CHART_WIDTH: usize = 640;
+/// Height of a rendered chart in pixels.
+pub const CHART_HEIGHT: usize = 360;
+
+// Space around the plotted area, for the title and axis labels
+const MARGIN: u32 = 16;
+const TITLE: u32 = 32;
+const X_LABELS: u32 = 24;
+const Y_LABELS: u32 = 40;
+
+const DATA: RGBColor = RGBColor(60, 120, 230);
+
+/// A number measured from each pattern in a pack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stat {
+    /// The mean height of every tile
+    AverageHeight,
+    /// The difference between the highest and lowest tile
+    HeightRange,
+    /// `CyberGrindPattern::max_slope()`
+    MaxSlope,
+    /// The number of prefabs which spawn enemies
+    EnemySpawns,
+    /// The number of jump pads
+    JumpPads,
+}
+
+impl Stat {
+    /// Every stat, in the order they're declared.
+    pub const ALL: [Stat; 5] = [
+        Stat::AverageHeight,
+        Stat::HeightRange,
+        Stat::MaxSlope,
+        Stat::EnemySpawns,
+        Stat::JumpPads,
+    ];
+
+    /// Gets the name of the stat, as used in chart titles.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stat::AverageHeight => "Average height",
+            Stat::HeightRange => "Height range",
+            Stat::MaxSlope => "Max slope",
+            Stat::EnemySpawns => "Enemy spawns",
+            Stat::JumpPads => "Jump pads",
+        }
+    }
+
+    /// Measures the stat for `pattern`.
+    pub fn measure(&self, pattern: &CyberGrindPattern) -> f32 {
+        let heights = pattern[..].iter().map(|tile| tile.height());
+        match self {
+            Stat::AverageHeight => heights.map(|h| h as f32).sum::<f32>() / 256.0,
+            Stat::HeightRange => {
+                let (min, max) =
+                    heights.fold((i8::MAX, i8::MIN), |(min, max), h| (min.min(h), max.max(h)));
+                (max - min) as f32
+            }
+            Stat::MaxSlope => pattern.max_slope(),
+            Stat::EnemySpawns => pattern[..]
+                .iter()
+                .filter(|tile| tile.prefab().spawns_enemy())
+                .count() as f32,
+            Stat::JumpPads => pattern[..]
+                .iter()
+                .filter(|tile| tile.prefab() == Prefab::JumpPad)
+                .count() as f32,
+        }
+    }
+}
+
+/// How a chart's values are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChartKind {
+    /// A bar for each value
+    Bars,
+    /// A line through every value
+    Line,
+}
+
+/// A chart of labelled values, which can be rendered with
+/// `Chart::to_svg()` or `Chart::to_png()`. Only the SVG
+/// includes the title and labels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chart {
+    pub title: String,
+    pub kind: ChartKind,
+    /// The label under each value
+    pub labels: Vec<String>,
+    pub values: Vec<f32>,
+}
+
+impl Chart {
+    /// Counts how many tiles of `patterns` are at each height,
+    /// from the lowest to the highest height used.
+    /// ```
+    /// use tinycbg::chart::Chart;
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[0].set_height(2);
+    /// let chart = Chart::height_histogram(&[pat]);
+    /// assert_eq!(chart.labels, ["0", "1", "2"]);
+    /// assert_eq!(chart.values, [255.0, 0.0, 1.0]);
+    /// ```
+    pub fn height_histogram(patterns: &[CyberGrindPattern]) -> Chart {
+        let mut counts = [0usize; 101];
+        for tile in patterns.iter().flat_map(|pattern| pattern[..].iter()) {
+            counts[(tile.height() + 50) as usize] += 1;
+        }
+        let first = counts.iter().position(|&count| count > 0).unwrap_or(50);
+        let last = counts.iter().rposition(|&count| count > 0).unwrap_or(50);
+
+        Chart {
+            title: String::from("Height histogram"),
+            kind: ChartKind::Bars,
+            labels: (first..=last)
+                .map(|i| (i as i32 - 50).to_string())
+                .collect(),
+            values: counts[first..=last].iter().map(|&c| c as f32).collect(),
+        }
+    }
+
+    /// Charts the heights along `path`, using
+    /// `CyberGrindPattern::profile()`.
+    /// ```
+    /// use tinycbg::chart::{Chart, ChartKind};
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let chart = Chart::profile(&CyberGrindPattern::new(), &[(0, 0), (3, 0)]);
+    /// assert_eq!(chart.kind, ChartKind::Line);
+    /// assert_eq!(chart.values.len(), 4);
+    /// ```
+    pub fn profile(pattern: &CyberGrindPattern, path: &[(usize, usize)]) -> Chart {
+        let values: Vec<f32> = pattern.profile(path).iter().map(|&h| h as f32).collect();
+        Chart {
+            title: String::from("Elevation profile"),
+            kind: ChartKind::Line,
+            labels: (0..values.len()).map(|i| i.to_string()).collect(),
+            values,
+        }
+    }
+
+    /// Charts `stat` for each named pattern in a pack.
+    pub fn pack_stats<'a, I>(pack: I, stat: Stat) -> Chart
+    where
+        I: IntoIterator<Item = (&'a str, &'a CyberGrindPattern)>,
+    {
+        let (labels, values) = pack
+            .into_iter()
+            .map(|(name, pattern)| (name.to_string(), stat.measure(pattern)))
+            .unzip();
+        Chart {
+            title: stat.name().to_string(),
+            kind: ChartKind::Bars,
+            labels,
+            values,
+        }
+    }
+
+    // The values at the bottom and top of the plotted
+    // area, always including 0 and never equal.
+    fn range(&self) -> (f32, f32) {
+        let low = self.values.iter().copied().fold(0.0, f32::min);
+        let high = self.values.iter().copied().fold(0.0, f32::max);
+        if high > low {
+            (low, high)
+        } else {
+            (low, low + 1.0)
+        }
+    }
+
+    // Draws the chart onto `root`. Text is only drawn
+    // if `text` is set, since only the SVG backend
+    // can draw it without loading a font.
+    fn draw<DB: DrawingBackend>(
+        &self,
+        root: &DrawingArea<DB, Shift>,
+        text: bool,
+    ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+        root.fill(&WHITE)?;
+        let (low, high) = self.range();
+
+        let mut builder = ChartBuilder::on(root);
+        builder
+            .margin(MARGIN)
+            .x_label_area_size(X_LABELS)
+            .y_label_area_size(Y_LABELS);
+        if text {
+            builder.caption(&self.title, ("sans-serif", 16));
+        } else {
+            builder.margin_top(MARGIN + TITLE);
+        }
+        // Discrete ranges include their end, so this
+        // has a segment for each value
+        let columns = 0..self.values.len().saturating_sub(1);
+        let mut chart = builder.build_cartesian_2d(columns.into_segmented(), low..high)?;
+
+        let label = |value: &SegmentValue<usize>| match value {
+            SegmentValue::CenterOf(i) => self.labels.get(*i).cloned().unwrap_or_default(),
+            _ => String::new(),
+        };
+        if text {
+            chart
+                .configure_mesh()
+                .disable_mesh()
+                .x_labels(self.labels.len().clamp(1, 32))
+                .y_labels(2)
+                .x_label_formatter(&label)
+                .draw()?;
+        } else {
+            // The mesh always draws its labels, so
+            // only its axes are drawn here
+            let left = || SegmentValue::Exact(0);
+            chart.draw_series([
+                PathElement::new([(left(), high), (left(), low)], BLACK),
+                PathElement::new([(left(), low), (SegmentValue::Last, low)], BLACK),
+            ])?;
+        }
+
+        match self.kind {
+            ChartKind::Bars => {
+                chart.draw_series(self.values.iter().enumerate().map(|(i, &value)| {
+                    let mut bar = Rectangle::new(
+                        [
+                            (SegmentValue::Exact(i), 0.0),
+                            (SegmentValue::Exact(i + 1), value),
+                        ],
+                        DATA.filled(),
+                    );
+                    bar.set_margin(0, 0, 2, 2);
+                    bar
+                }))?;
+            }
+            ChartKind::Line => {
+                chart.draw_series(LineSeries::new(
+                    (self.values.iter().enumerate())
+                        .map(|(i, &value)| (SegmentValue::CenterOf(i), value)),
+                    DATA.stroke_width(2),
+                ))?;
+            }
+        }
+        root.present()
+    }
+
+    /// Renders the chart as an SVG image with its title, the
+    /// range of values on the left and labels along the bottom.
+    /// Labels are skipped if there are too many to fit.
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        {
+            let size = (CHART_WIDTH as u32, CHART_HEIGHT as u32);
+            let root = SVGBackend::with_string(&mut svg, size).into_drawing_area();
+            self.draw(&root, true)
+                .expect("Drawing to a String can't fail");
+        }
+        svg
+    }
+
+    /// Renders the chart as a PNG image, `CHART_WIDTH` by
+    /// `CHART_HEIGHT` pixels, with the same layout as
+    /// `Chart::to_svg()` but without any text.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut rgb = vec![0; CHART_WIDTH * CHART_HEIGHT * 3];
+        {
+            let size = (CHART_WIDTH as u32, CHART_HEIGHT as u32);
+            let root = BitMapBackend::with_buffer(&mut rgb, size).into_drawing_area();
+            self.draw(&root, false)
+                .expect("Drawing to a buffer of the right size can't fail");
+        }
+        encode_png(CHART_WIDTH as u32, CHART_HEIGHT as u32, &rgb)
+    }
+}
@@ -0,0 +1,86 @@
+use core::ops::{Index, IndexMut};
+
+use crate::{error::OutOfBounds, CyberGrindPattern, Tile};
+
+/// Coordinates of a tile, which are
+/// always between 0 and 15.
+/// ```
+/// use tinycbg::{Coord, CyberGrindPattern};
+///
+/// let mut pat = CyberGrindPattern::new();
+/// let coord = Coord::new(3, 12).unwrap();
+/// pat[coord].set_height(10);
+/// assert_eq!(pat[(3, 12)].height(), 10);
+///
+/// assert!(Coord::new(16, 0).is_none());
+/// ```
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Coord {
+    x: u8,
+    y: u8,
+}
+
+impl Coord {
+    /// Creates coordinates, or returns `None`
+    /// if `x` or `y` is greater than 15.
+    pub const fn new(x: usize, y: usize) -> Option<Coord> {
+        if x < 16 && y < 16 {
+            Some(Coord {
+                x: x as u8,
+                y: y as u8,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the coordinates of the tile at `index`, or
+    /// returns `None` if `index` is greater than 255.
+    pub const fn from_index(index: usize) -> Option<Coord> {
+        if index < 256 {
+            Coord::new(index % 16, index / 16)
+        } else {
+            None
+        }
+    }
+
+    pub const fn x(self) -> usize {
+        self.x as usize
+    }
+
+    pub const fn y(self) -> usize {
+        self.y as usize
+    }
+
+    /// Gets the index of the tile, in the same
+    /// order as indexing a pattern with numbers.
+    pub const fn index(self) -> usize {
+        self.y() * 16 + self.x()
+    }
+}
+
+impl TryFrom<(usize, usize)> for Coord {
+    type Error = OutOfBounds;
+    fn try_from((x, y): (usize, usize)) -> Result<Self, OutOfBounds> {
+        Coord::new(x, y).ok_or(OutOfBounds { x, y })
+    }
+}
+
+impl From<Coord> for (usize, usize) {
+    fn from(coord: Coord) -> Self {
+        (coord.x(), coord.y())
+    }
+}
+
+impl Index<Coord> for CyberGrindPattern {
+    type Output = Tile;
+    fn index(&self, coord: Coord) -> &Self::Output {
+        &self.tiles[coord.index()]
+    }
+}
+
+impl IndexMut<Coord> for CyberGrindPattern {
+    fn index_mut(&mut self, coord: Coord) -> &mut Tile {
+        &mut self.tiles[coord.index()]
+    }
+}
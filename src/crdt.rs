@@ -0,0 +1,172 @@
+//! Conflict-free collaborative editing.
+//!
+//! Every tile is a last-writer-wins register tagged
+//! with a Lamport timestamp. Two replicas can edit
+//! the same pattern at the same time, exchange their
+//! `Update`s in any order, and still end up with
+//! identical patterns.
+//! ```
+//! use tinycbg::crdt::CrdtPattern;
+//! use tinycbg::Tile;
+//!
+//! let mut alice = CrdtPattern::new(1);
+//! let mut bob = CrdtPattern::new(2);
+//!
+//! let a = alice.set(0, Tile::with_height(10));
+//! let b = bob.set(0, Tile::with_height(-10));
+//!
+//! alice.apply(&b);
+//! bob.apply(&a);
+//! assert_eq!(alice.pattern()[0], bob.pattern()[0]);
+//! ```
+
+use crate::{CyberGrindPattern, Prefab, Tile, UnknownPrefab};
+
+/// A Lamport timestamp. Ties between
+/// replicas are broken by replica id.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    pub counter: u64,
+    pub replica: u32,
+}
+
+/// A single tile edit, which can be sent
+/// to other replicas and applied there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Update {
+    pub index: u8,
+    pub tile: Tile,
+    pub stamp: Timestamp,
+}
+
+impl Update {
+    /// Size of an update encoded with `Update::to_bytes()`
+    pub const SIZE: usize = 15;
+
+    /// Encodes the update as a fixed-size
+    /// little-endian message.
+    pub fn to_bytes(&self) -> [u8; Update::SIZE] {
+        let mut bytes = [0; Update::SIZE];
+        bytes[0] = self.index;
+        bytes[1] = self.tile.height() as u8;
+        bytes[2] = self.tile.prefab().byte();
+        bytes[3..11].copy_from_slice(&self.stamp.counter.to_le_bytes());
+        bytes[11..15].copy_from_slice(&self.stamp.replica.to_le_bytes());
+        bytes
+    }
+
+    /// Decodes an update written by `Update::to_bytes()`.
+    /// Returns `None` if the message is malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Update> {
+        if bytes.len() != Update::SIZE {
+            return None;
+        }
+        let height = bytes[1] as i8;
+        if !Tile::is_valid_height(height) {
+            return None;
+        }
+        let prefab = Prefab::try_from(bytes[2])
+            .ok()
+            .or(UnknownPrefab::Keep.resolve(bytes[2]))?;
+        Some(Update {
+            index: bytes[0],
+            tile: Tile::new(height, prefab),
+            stamp: Timestamp {
+                counter: u64::from_le_bytes(bytes[3..11].try_into().ok()?),
+                replica: u32::from_le_bytes(bytes[11..15].try_into().ok()?),
+            },
+        })
+    }
+}
+
+/// A pattern replica which can be
+/// edited concurrently with others.
+#[derive(Clone, Debug)]
+pub struct CrdtPattern {
+    replica: u32,
+    clock: u64,
+    pattern: CyberGrindPattern,
+    stamps: [Timestamp; 256],
+}
+
+impl CrdtPattern {
+    /// Creates an empty replica with id `replica`.
+    /// Every replica editing the same pattern
+    /// should have a different id.
+    pub fn new(replica: u32) -> Self {
+        Self::from_pattern(replica, CyberGrindPattern::new())
+    }
+
+    /// Creates a replica starting from `pattern`.
+    /// All replicas should start from the same pattern.
+    pub fn from_pattern(replica: u32, pattern: CyberGrindPattern) -> Self {
+        CrdtPattern {
+            replica,
+            clock: 0,
+            pattern,
+            stamps: [Timestamp::default(); 256],
+        }
+    }
+
+    /// Sets the tile at `index` and returns
+    /// the update to send to other replicas.
+    pub fn set(&mut self, index: u8, tile: Tile) -> Update {
+        self.clock += 1;
+        let update = Update {
+            index,
+            tile,
+            stamp: Timestamp {
+                counter: self.clock,
+                replica: self.replica,
+            },
+        };
+        self.apply(&update);
+        update
+    }
+
+    /// Applies an update from any replica.
+    /// Returns whether the update changed
+    /// the tile, or lost to a newer edit.
+    pub fn apply(&mut self, update: &Update) -> bool {
+        self.clock = self.clock.max(update.stamp.counter);
+        let index = update.index as usize;
+        if update.stamp <= self.stamps[index] {
+            return false;
+        }
+        self.stamps[index] = update.stamp;
+        self.pattern[index] = update.tile;
+        true
+    }
+
+    /// Merges the full state of another replica into this one.
+    pub fn merge(&mut self, other: &CrdtPattern) {
+        for update in other.updates() {
+            self.apply(&update);
+        }
+    }
+
+    /// Returns an update for every tile which
+    /// has been edited, which is enough to bring
+    /// a new replica up to date.
+    pub fn updates(&self) -> impl Iterator<Item = Update> + '_ {
+        self.stamps
+            .iter()
+            .enumerate()
+            .filter(|(_, stamp)| stamp.counter != 0)
+            .map(|(index, &stamp)| Update {
+                index: index as u8,
+                tile: self.pattern[index],
+                stamp,
+            })
+    }
+
+    /// Gets the id of this replica.
+    pub fn replica(&self) -> u32 {
+        self.replica
+    }
+
+    /// Gets the current state of the pattern.
+    pub fn pattern(&self) -> &CyberGrindPattern {
+        &self.pattern
+    }
+}
@@ -1,15 +1,22 @@
-use std::{
+use alloc::string::String;
+use core::{
     error::Error,
     fmt::{Debug, Display},
-    io,
 };
+#[cfg(feature = "std")]
+use std::io;
 
 /// Error type which is used
 /// in parsing methods.
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub enum IoError {
     Io(io::Error),
     Parse(ParseError),
+    /// The input was bigger than `limit` bytes
+    TooLarge {
+        limit: usize,
+    },
 }
 
 /// Error type which is returned when
@@ -22,7 +29,117 @@ pub struct ParseError {
     pub char: u8,
 }
 
+/// Error type which is returned when a
+/// pattern is built from a collection of
+/// tiles that doesn't hold exactly 256 of them.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct WrongLength {
+    pub expected: usize,
+    pub found: usize,
+}
+
+/// Error type which is returned when a tile
+/// is given a height greater than 50 or less
+/// than -50.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct HeightError {
+    pub height: i8,
+}
+
+/// Error type which is returned when
+/// coordinates are greater than 15.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct OutOfBounds {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Error type which is returned when a
+/// buffer is too small to hold a pattern.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct BufferTooSmall {
+    pub needed: usize,
+    pub len: usize,
+}
+
+/// Error type which is returned when
+/// building a pattern from a slice of
+/// raw values.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SliceError {
+    /// The slice didn't hold exactly 256 values
+    Length(WrongLength),
+    /// The value at `index` is not a
+    /// valid height between -50 and 50
+    Height { index: usize, height: i8 },
+}
+
+/// Error type which is returned when
+/// parsing an invalid op log.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct OpParseError {
+    pub line: u32,
+}
+
+/// Error type which is returned when
+/// parsing an unknown archetype name.
+#[derive(PartialEq, Clone, Debug)]
+pub struct UnknownArchetype {
+    pub name: String,
+}
+
+/// Error type which is returned when
+/// parsing invalid pattern JSON.
+#[derive(PartialEq, Clone, Debug)]
+pub enum JsonError {
+    /// The input isn't valid JSON. `offset` is
+    /// the byte the problem was found at
+    Syntax { offset: usize },
+    /// The top level value isn't an object,
+    /// or is missing the field `field`
+    MissingField { field: &'static str },
+    /// `field` isn't an array of 16 rows of 16 values
+    WrongShape { field: &'static str },
+    /// The height at `(x, y)` isn't a
+    /// whole number between -50 and 50
+    Height { x: usize, y: usize },
+    /// The prefab at `(x, y)` isn't a known
+    /// prefab name or a single character
+    Prefab { x: usize, y: usize },
+}
+
+/// Error type which is returned when
+/// parsing an invalid `.cgpb` file.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum BinaryError {
+    /// The input doesn't start with `BINARY_MAGIC`
+    NotBinary,
+    /// The input was written by a newer
+    /// version of the format
+    UnsupportedVersion(u8),
+    /// The input ended early, after `len` bytes
+    TooShort { len: usize },
+    /// The height of the tile at `index`
+    /// isn't between -50 and 50
+    Height { index: usize },
+    /// The prefab of the tile at `index` isn't valid
+    Prefab { index: usize },
+    /// There is data after the end of the pattern
+    TrailingData,
+}
+
+/// Error type which is returned when
+/// decoding an invalid share code.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ShareCodeError {
+    /// The character at byte `index` isn't base64
+    InvalidChar { index: usize },
+    /// The code decoded into an invalid `.cgpb` file
+    Binary(BinaryError),
+}
+
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum ParseErrorType {
     /// Returns when a newline was expected,
     /// but got a different character.
@@ -42,30 +159,62 @@ pub enum ParseErrorType {
     /// Returns when an invalid prefab byte
     /// is found while parsing prefabs
     InvalidPrefab,
+    /// Returns when there is data after the
+    /// end of the pattern, and it isn't allowed
+    TrailingData,
+    /// Returns when the input ends before the
+    /// pattern does. `ParseError::char` is 0.
+    UnexpectedEof,
 }
 
+#[cfg(feature = "std")]
 impl From<io::Error> for IoError {
     fn from(value: io::Error) -> Self {
         Self::Io(value)
     }
 }
+#[cfg(feature = "std")]
 impl From<ParseError> for IoError {
     fn from(value: ParseError) -> Self {
         Self::Parse(value)
     }
 }
 
+impl From<BinaryError> for ShareCodeError {
+    fn from(value: BinaryError) -> Self {
+        Self::Binary(value)
+    }
+}
+
+impl From<WrongLength> for SliceError {
+    fn from(value: WrongLength) -> Self {
+        Self::Length(value)
+    }
+}
+
+#[cfg(feature = "std")]
 impl Error for IoError {}
 impl Error for ParseError {}
 impl Error for ParseErrorType {}
+impl Error for WrongLength {}
+impl Error for BufferTooSmall {}
+impl Error for OutOfBounds {}
+impl Error for HeightError {}
+impl Error for SliceError {}
+impl Error for OpParseError {}
+impl Error for UnknownArchetype {}
+impl Error for JsonError {}
+impl Error for BinaryError {}
+impl Error for ShareCodeError {}
 
+#[cfg(feature = "std")]
 impl Display for IoError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{self:?}")
     }
 }
 impl Display for ParseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let kind_str = match self.kind {
             ParseErrorType::ExpectedNewline => "Expected newline but got",
             ParseErrorType::InvalidHeightChar => "Invalid height char",
@@ -73,6 +222,14 @@ impl Display for ParseError {
             ParseErrorType::LeadingZero => "Leading zero in parentheses",
             ParseErrorType::InvalidPrefab => "Invalid prefab character",
             ParseErrorType::DuplicateNegative => "Duplicate negative symbol",
+            ParseErrorType::TrailingData => "Unexpected data after pattern",
+            ParseErrorType::UnexpectedEof => {
+                return write!(
+                    f,
+                    "Error parsing line {}, column {}: Unexpected end of input",
+                    self.line, self.column
+                );
+            }
         };
 
         match self.char {
@@ -92,14 +249,101 @@ impl Display for ParseError {
         }
     }
 }
+impl Display for HeightError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Height {} is not between -50 and 50", self.height)
+    }
+}
+impl Display for OutOfBounds {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Coordinates ({}, {}) are out of bounds", self.x, self.y)
+    }
+}
+impl Display for BufferTooSmall {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Buffer of {} bytes is too small, {} are needed",
+            self.len, self.needed
+        )
+    }
+}
+impl Display for WrongLength {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Expected {} elements, but got {}",
+            self.expected, self.found
+        )
+    }
+}
+impl Display for SliceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SliceError::Length(err) => write!(f, "{err}"),
+            SliceError::Height { index, height } => {
+                write!(f, "Extreme height value {height} at index {index}")
+            }
+        }
+    }
+}
+impl Display for OpParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Invalid op on line {}", self.line)
+    }
+}
+impl Display for UnknownArchetype {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Unknown archetype \"{}\"", self.name)
+    }
+}
+impl Display for JsonError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            JsonError::Syntax { offset } => write!(f, "Invalid JSON at byte {offset}"),
+            JsonError::MissingField { field } => write!(f, "Missing field \"{field}\""),
+            JsonError::WrongShape { field } => {
+                write!(f, "Expected \"{field}\" to be 16 rows of 16 values")
+            }
+            JsonError::Height { x, y } => write!(f, "Invalid height at ({x}, {y})"),
+            JsonError::Prefab { x, y } => write!(f, "Invalid prefab at ({x}, {y})"),
+        }
+    }
+}
+impl Display for BinaryError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BinaryError::NotBinary => write!(f, "Not a .cgpb file"),
+            BinaryError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported .cgpb version {version}")
+            }
+            BinaryError::TooShort { len } => write!(f, "Unexpected end of input after {len} bytes"),
+            BinaryError::Height { index } => write!(f, "Invalid height at index {index}"),
+            BinaryError::Prefab { index } => write!(f, "Invalid prefab at index {index}"),
+            BinaryError::TrailingData => write!(f, "Unexpected data after pattern"),
+        }
+    }
+}
+impl Display for ShareCodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ShareCodeError::InvalidChar { index } => {
+                write!(f, "Invalid share code character at byte {index}")
+            }
+            ShareCodeError::Binary(err) => write!(f, "Invalid share code: {err}"),
+        }
+    }
+}
 impl Display for ParseErrorType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{self:?}")
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use alloc::format;
+
     use crate::error::ParseError;
 
     #[test]
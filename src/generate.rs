@@ -0,0 +1,486 @@
+use std::str::FromStr;
+
+use crate::{error::UnknownArchetype, CyberGrindPattern, Prefab, Rect, Tile};
+
+/// A small, seedable random number generator used
+/// by the pattern generators. The same seed always
+/// gives the same numbers, so generated patterns
+/// can be reproduced from their seed.
+/// ```
+/// use tinycbg::Rng;
+///
+/// let mut a = Rng::new(7);
+/// let mut b = Rng::new(7);
+/// assert_eq!(a.next_u64(), b.next_u64());
+/// assert!((-5..5).contains(&a.range(-5, 5)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Gets the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        // SplitMix64
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Gets a random number in `low..high`.
+    /// Panics if `low >= high`.
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        assert!(low < high, "Empty range");
+        let span = (high - low) as u64;
+        low + (self.next_u64() % span) as i64
+    }
+
+    /// Gets a random number in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Puts the items of `slice` in a random order.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.range(0, i as i64 + 1) as usize;
+            slice.swap(i, j);
+        }
+    }
+}
+
+/// Settings for `CyberGrindPattern::scatter_pillars()`.
+/// Heights past 50 or -50 are clamped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PillarConfig {
+    /// Lowest height a pillar can have
+    pub min_height: i8,
+    /// Highest height a pillar can have
+    pub max_height: i8,
+    /// Width and length of a pillar, in tiles
+    pub footprint: usize,
+    /// Minimum number of tiles between two pillars
+    pub min_spacing: usize,
+}
+
+impl Default for PillarConfig {
+    fn default() -> Self {
+        PillarConfig {
+            min_height: 5,
+            max_height: 15,
+            footprint: 1,
+            min_spacing: 1,
+        }
+    }
+}
+
+/// Settings for `CyberGrindPattern::generate_voronoi()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoronoiConfig {
+    /// Number of regions
+    pub sites: usize,
+    /// Lowest height a region can have
+    pub min_height: i8,
+    /// Highest height a region can have
+    pub max_height: i8,
+    /// Number of times the borders
+    /// between regions are smoothed
+    pub smoothing: usize,
+}
+
+impl Default for VoronoiConfig {
+    fn default() -> Self {
+        VoronoiConfig {
+            sites: 8,
+            min_height: 0,
+            max_height: 10,
+            smoothing: 0,
+        }
+    }
+}
+
+/// A ready to play style of arena, built
+/// out of the lower level generators.
+/// ```
+/// use tinycbg::{Archetype, CyberGrindPattern, Rng};
+///
+/// let archetype: Archetype = "colosseum".parse().unwrap();
+/// assert_eq!(archetype, Archetype::Colosseum { tiers: 3 });
+///
+/// let pat = CyberGrindPattern::generate_archetype(&mut Rng::new(0), archetype);
+/// assert_eq!(pat[(0, 0)].height(), 18);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Archetype {
+    /// Flat ground split by a winding
+    /// trench running from top to bottom
+    Canyon { depth: u8, width: usize },
+    /// Rolling hills, smoother for
+    /// lower `roughness` values
+    Hills { roughness: f32 },
+    /// Raised platforms over a pit
+    Islands { count: usize },
+    /// A flat floor ringed by stepped walls
+    Colosseum { tiers: usize },
+}
+
+impl Archetype {
+    /// Every archetype with its default settings.
+    pub const ALL: [Archetype; 4] = [
+        Archetype::Canyon { depth: 8, width: 4 },
+        Archetype::Hills { roughness: 0.5 },
+        Archetype::Islands { count: 6 },
+        Archetype::Colosseum { tiers: 3 },
+    ];
+
+    /// Gets the name of the archetype,
+    /// which `FromStr` accepts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Archetype::Canyon { .. } => "canyon",
+            Archetype::Hills { .. } => "hills",
+            Archetype::Islands { .. } => "islands",
+            Archetype::Colosseum { .. } => "colosseum",
+        }
+    }
+}
+
+impl FromStr for Archetype {
+    type Err = UnknownArchetype;
+    /// Gets an archetype with its default
+    /// settings by name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Archetype::ALL
+            .into_iter()
+            .find(|archetype| archetype.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| UnknownArchetype {
+                name: s.to_string(),
+            })
+    }
+}
+
+impl CyberGrindPattern {
+    /// Places up to `count` square pillars at random spots,
+    /// never closer to each other than `config.min_spacing`.
+    /// Pillars replace the tiles under them, prefabs included.
+    /// Returns where each pillar was placed, which may be less
+    /// than `count` if the arena runs out of room.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, PillarConfig, Rng};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// let pillars = pat.scatter_pillars(&mut Rng::new(1), 6, PillarConfig::default());
+    /// assert_eq!(pillars.len(), 6);
+    /// assert!(pat[pillars[0].x + pillars[0].y * 16].height() >= 5);
+    /// ```
+    pub fn scatter_pillars(
+        &mut self,
+        rng: &mut Rng,
+        count: usize,
+        config: PillarConfig,
+    ) -> Vec<Rect> {
+        let mut pillars: Vec<Rect> = Vec::new();
+        let footprint = config.footprint.clamp(1, 16);
+        let (low, high) = (
+            config.min_height.min(config.max_height).clamp(-50, 50),
+            config.min_height.max(config.max_height).clamp(-50, 50),
+        );
+
+        let mut attempts = 0;
+        while pillars.len() < count && attempts < count * 100 {
+            attempts += 1;
+            let x = rng.range(0, (17 - footprint) as i64) as usize;
+            let y = rng.range(0, (17 - footprint) as i64) as usize;
+            let rect = Rect::new(x, y, footprint, footprint);
+
+            let gap = config.min_spacing;
+            let too_close = pillars.iter().any(|other| {
+                rect.x < other.x + other.width + gap
+                    && other.x < rect.x + rect.width + gap
+                    && rect.y < other.y + other.height + gap
+                    && other.y < rect.y + rect.height + gap
+            });
+            if too_close {
+                continue;
+            }
+
+            let tile = Tile::with_height(rng.range(low as i64, high as i64 + 1) as i8);
+            for pos in rect.positions() {
+                self[pos] = tile;
+            }
+            pillars.push(rect);
+        }
+        pillars
+    }
+
+    /// Places up to `count` of `prefab` using Poisson-disk
+    /// sampling, so no two are closer than `min_spacing`
+    /// tiles to each other, or to prefabs already in the
+    /// pattern. Only tiles for which `eligible` returns
+    /// true are used. Returns where each prefab was placed.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Prefab, Rng};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// // Keep spawns off the outer ring
+    /// let spawns = pat.scatter_prefabs(&mut Rng::new(5), Prefab::Melee, 8, 3.0, |(x, y)| {
+    ///     (1..15).contains(&x) && (1..15).contains(&y)
+    /// });
+    /// assert_eq!(spawns.len(), 8);
+    /// ```
+    pub fn scatter_prefabs<F: Fn((usize, usize)) -> bool>(
+        &mut self,
+        rng: &mut Rng,
+        prefab: Prefab,
+        count: usize,
+        min_spacing: f32,
+        eligible: F,
+    ) -> Vec<(usize, usize)> {
+        let mut taken: Vec<(usize, usize)> = Rect::FULL
+            .positions()
+            .filter(|&pos| self[pos].prefab() != Prefab::None)
+            .collect();
+        let mut candidates: Vec<(usize, usize)> = Rect::FULL
+            .positions()
+            .filter(|&pos| self[pos].prefab() == Prefab::None && eligible(pos))
+            .collect();
+        rng.shuffle(&mut candidates);
+
+        let mut placed = Vec::new();
+        for (x, y) in candidates {
+            if placed.len() == count {
+                break;
+            }
+            let far_enough = taken.iter().all(|&(other_x, other_y)| {
+                let dx = x as f32 - other_x as f32;
+                let dy = y as f32 - other_y as f32;
+                dx * dx + dy * dy >= min_spacing * min_spacing
+            });
+            if far_enough {
+                self[(x, y)].set_prefab(prefab);
+                taken.push((x, y));
+                placed.push((x, y));
+            }
+        }
+        placed
+    }
+
+    /// Adds a random amount between `-amplitude` and
+    /// `amplitude` to the height of every tile for which
+    /// `mask` returns true. Heights are clamped to stay
+    /// between -50 and 50.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Rng};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.jitter_heights(&mut Rng::new(8), 2, |(_, y)| y < 8);
+    /// assert!(pat[0..128].iter().all(|tile| (-2..=2).contains(&tile.height())));
+    /// assert!(pat[128..256].iter().all(|tile| tile.height() == 0));
+    /// ```
+    pub fn jitter_heights<F: Fn((usize, usize)) -> bool>(
+        &mut self,
+        rng: &mut Rng,
+        amplitude: u8,
+        mask: F,
+    ) {
+        let amplitude = amplitude.min(100) as i64;
+        for pos in Rect::FULL.positions() {
+            if !mask(pos) {
+                continue;
+            }
+            let offset = rng.range(-amplitude, amplitude + 1);
+            let height = (self[pos].height() as i64 + offset).clamp(-50, 50);
+            self[pos].set_height(height as i8);
+        }
+    }
+
+    /// Generates plateaus by scattering `config.sites`
+    /// random points, giving each tile the height of
+    /// the region of its nearest point. Each region
+    /// gets a random height between `config.min_height`
+    /// and `config.max_height`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Rng, VoronoiConfig};
+    ///
+    /// let config = VoronoiConfig { sites: 4, ..Default::default() };
+    /// let pat = CyberGrindPattern::generate_voronoi(&mut Rng::new(3), config);
+    /// assert!(pat[..].iter().all(|tile| (0..=10).contains(&tile.height())));
+    /// ```
+    pub fn generate_voronoi(rng: &mut Rng, config: VoronoiConfig) -> Self {
+        let (low, high) = (
+            config.min_height.min(config.max_height).clamp(-50, 50),
+            config.min_height.max(config.max_height).clamp(-50, 50),
+        );
+        let sites: Vec<(i64, i64, i8)> = (0..config.sites.max(1))
+            .map(|_| {
+                let x = rng.range(0, 16);
+                let y = rng.range(0, 16);
+                (x, y, rng.range(low as i64, high as i64 + 1) as i8)
+            })
+            .collect();
+
+        let mut pattern = CyberGrindPattern::new();
+        let mut region = [0; 256];
+        for (x, y) in Rect::FULL.positions() {
+            let (nearest, &(_, _, height)) = sites
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (site_x, site_y, _))| {
+                    (x as i64 - site_x).pow(2) + (y as i64 - site_y).pow(2)
+                })
+                .expect("There is always at least one site");
+            region[y * 16 + x] = nearest;
+            pattern[(x, y)].set_height(height);
+        }
+
+        for _ in 0..config.smoothing {
+            let before = pattern;
+            for (x, y) in Rect::FULL.positions() {
+                let neighbors: Vec<(usize, usize)> = Rect::around((x, y), 1).positions().collect();
+                let on_border = neighbors
+                    .iter()
+                    .any(|&(nx, ny)| region[ny * 16 + nx] != region[y * 16 + x]);
+                if on_border {
+                    let sum: i32 = neighbors
+                        .iter()
+                        .map(|&pos| before[pos].height() as i32)
+                        .sum();
+                    let average = (sum as f32 / neighbors.len() as f32).round();
+                    pattern[(x, y)].set_height(average as i8);
+                }
+            }
+        }
+        pattern
+    }
+
+    /// Generates rough terrain with the diamond-square
+    /// algorithm, run on a 17x17 grid with the last row
+    /// and column dropped. `roughness` is how much the
+    /// noise is kept at each finer level, with values
+    /// around 0.5 giving rolling hills and values closer
+    /// to 1.0 giving jagged terrain. Heights are scaled
+    /// to fill `-amplitude..=amplitude`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Rng};
+    ///
+    /// let pat = CyberGrindPattern::generate_diamond_square(&mut Rng::new(1), 0.5, 10);
+    /// assert!(pat[..].iter().all(|tile| (-10..=10).contains(&tile.height())));
+    /// ```
+    pub fn generate_diamond_square(rng: &mut Rng, roughness: f32, amplitude: u8) -> Self {
+        const SIZE: usize = 17;
+        let mut grid = [[0.0f32; SIZE]; SIZE];
+        for (x, y) in [(0, 0), (16, 0), (0, 16), (16, 16)] {
+            grid[y][x] = rng.next_f32() * 2.0 - 1.0;
+        }
+
+        let mut step = SIZE - 1;
+        let mut scale = 1.0;
+        while step > 1 {
+            let half = step / 2;
+
+            // Diamond step: the center of every square
+            for y in (half..SIZE).step_by(step) {
+                for x in (half..SIZE).step_by(step) {
+                    let average = (grid[y - half][x - half]
+                        + grid[y - half][x + half]
+                        + grid[y + half][x - half]
+                        + grid[y + half][x + half])
+                        / 4.0;
+                    grid[y][x] = average + (rng.next_f32() * 2.0 - 1.0) * scale;
+                }
+            }
+
+            // Square step: the middle of every edge
+            for y in (0..SIZE).step_by(half) {
+                let start = if (y / half).is_multiple_of(2) {
+                    half
+                } else {
+                    0
+                };
+                for x in (start..SIZE).step_by(step) {
+                    let neighbors = [
+                        (x.checked_sub(half), Some(y)),
+                        (Some(x + half), Some(y)),
+                        (Some(x), y.checked_sub(half)),
+                        (Some(x), Some(y + half)),
+                    ];
+                    let values: Vec<f32> = neighbors
+                        .iter()
+                        .filter_map(|&(nx, ny)| grid.get(ny?)?.get(nx?))
+                        .copied()
+                        .collect();
+                    let average = values.iter().sum::<f32>() / values.len() as f32;
+                    grid[y][x] = average + (rng.next_f32() * 2.0 - 1.0) * scale;
+                }
+            }
+
+            scale *= roughness;
+            step = half;
+        }
+
+        let values = grid[..16].iter().flat_map(|row| &row[..16]);
+        let min = values.clone().copied().fold(f32::INFINITY, f32::min);
+        let max = values.copied().fold(f32::NEG_INFINITY, f32::max);
+        let amplitude = amplitude.min(50) as f32;
+
+        let mut pattern = CyberGrindPattern::new();
+        for (x, y) in Rect::FULL.positions() {
+            let t = if max > min {
+                (grid[y][x] - min) / (max - min)
+            } else {
+                0.5
+            };
+            let height = (t * 2.0 - 1.0) * amplitude;
+            pattern[(x, y)].set_height(height.round() as i8);
+        }
+        pattern
+    }
+
+    /// Generates an arena in the style of `archetype`.
+    pub fn generate_archetype(rng: &mut Rng, archetype: Archetype) -> Self {
+        match archetype {
+            Archetype::Canyon { depth, width } => {
+                let mut pattern = Self::generate_diamond_square(rng, 0.5, 2);
+                let depth = depth.min(50) as i8;
+                let mut center = rng.range(4, 12);
+                for y in 0..16 {
+                    let left = center - width as i64 / 2;
+                    for x in left.max(0)..(left + width as i64).min(16) {
+                        let height = pattern[(x as usize, y)].height();
+                        pattern[(x as usize, y)].set_height((height - depth).max(-50));
+                    }
+                    center = (center + rng.range(-1, 2)).clamp(2, 13);
+                }
+                pattern
+            }
+            Archetype::Hills { roughness } => {
+                Self::generate_diamond_square(rng, roughness.clamp(0.0, 1.0), 12)
+            }
+            Archetype::Islands { count } => {
+                let mut pattern = Self::splat(Tile::with_height(-10));
+                let config = PillarConfig {
+                    min_height: 0,
+                    max_height: 6,
+                    footprint: 3,
+                    min_spacing: 1,
+                };
+                pattern.scatter_pillars(rng, count, config);
+                pattern
+            }
+            Archetype::Colosseum { tiers } => {
+                let tiers = tiers.min(7);
+                let profile: Vec<i8> = (0..tiers).rev().map(|tier| (tier as i8 + 1) * 6).collect();
+                let mut pattern = Self::new();
+                pattern.build_border_profile(&profile);
+                pattern
+            }
+        }
+    }
+}
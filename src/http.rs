@@ -0,0 +1,345 @@
+//! A tiny HTTP server for browsing a directory of patterns.
+//!
+//! ```no_run
+//! // Browse to http://127.0.0.1:8080 to see every pattern in "patterns/"
+//! tinycbg::http::serve("patterns/", "127.0.0.1:8080")?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! The server has the following routes:
+//! - `/` lists every pattern with its preview
+//! - `/patterns.json` lists the metadata of every pattern
+//! - `/patterns/<name>.json` is the metadata of a single pattern
+//! - `/patterns/<name>.svg` and `/patterns/<name>.png` are rendered previews
+//! - `/patterns/<name>.cgp` is the pattern file itself
+//!
+//! Metadata looks like this:
+//! ```json
+//! {"name": "arena", "min_height": -2, "max_height": 20,
+//!  "prefabs": {"projectile": 2, "melee": 4, "hideous mass": 0,
+//!              "jump pad": 1, "stairs": 0, "none": 249}}
+//! ```
+//! Patterns which fail to parse have an `"error"` field instead.
+
+use std::{
+    fmt::Write as FmtWrite,
+    fs,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::{CyberGrindPattern, Prefab};
+
+/// How long a `PreviewServer` waits to read a request
+/// or write a response before giving up, by default.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves every `.cgp` file in `dir` at `addr`.
+/// Never returns unless the server fails to start.
+pub fn serve<P: Into<PathBuf>, A: ToSocketAddrs>(dir: P, addr: A) -> Result<(), io::Error> {
+    PreviewServer::bind(dir, addr)?.run()
+}
+
+/// An HTTP server previewing the patterns in a directory.
+/// The directory is read again on every request, so
+/// patterns can be added or edited while it's running.
+/// Connections are answered one at a time, so a client
+/// which stops sending or receiving is dropped after
+/// the timeout instead of blocking everyone else.
+pub struct PreviewServer {
+    listener: TcpListener,
+    dir: PathBuf,
+    timeout: Duration,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Response {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn not_found() -> Self {
+        Response {
+            status: "404 Not Found",
+            content_type: "text/plain",
+            body: b"Not found".to_vec(),
+        }
+    }
+}
+
+impl PreviewServer {
+    /// Starts listening at `addr`, serving the patterns in `dir`.
+    pub fn bind<P: Into<PathBuf>, A: ToSocketAddrs>(dir: P, addr: A) -> Result<Self, io::Error> {
+        Ok(PreviewServer {
+            listener: TcpListener::bind(addr)?,
+            dir: dir.into(),
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Sets how long to wait to read a request or write
+    /// a response. Defaults to `DEFAULT_TIMEOUT`.
+    /// Returns an error if `timeout` is zero.
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<(), io::Error> {
+        if timeout.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Timeout can't be zero",
+            ));
+        }
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    /// Gets the address the server is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
+
+    /// Answers requests forever. Errors from
+    /// individual connections are ignored.
+    pub fn run(&self) -> Result<(), io::Error> {
+        loop {
+            let _ = self.handle_next();
+        }
+    }
+
+    /// Waits for the next connection
+    /// and answers a single request.
+    pub fn handle_next(&self) -> Result<(), io::Error> {
+        let (mut stream, _) = self.listener.accept()?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        let path = read_request_path(&mut stream)?;
+        let response = match path {
+            Some(path) => self.route(&path),
+            None => Response {
+                status: "400 Bad Request",
+                content_type: "text/plain",
+                body: b"Bad request".to_vec(),
+            },
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            response.status,
+            response.content_type,
+            response.body.len()
+        )?;
+        stream.write_all(&response.body)?;
+        stream.flush()
+    }
+
+    fn route(&self, path: &str) -> Response {
+        if path == "/" {
+            return Response::ok("text/html", self.index().into_bytes());
+        }
+        if path == "/patterns.json" {
+            let entries: Vec<String> = self
+                .pattern_names()
+                .iter()
+                .map(|name| self.metadata(name))
+                .collect();
+            return Response::ok(
+                "application/json",
+                format!("[{}]", entries.join(",")).into_bytes(),
+            );
+        }
+
+        let Some(file) = path.strip_prefix("/patterns/") else {
+            return Response::not_found();
+        };
+        let Some((name, ext)) = file.rsplit_once('.') else {
+            return Response::not_found();
+        };
+        let name = percent_decode(name);
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Response::not_found();
+        }
+        let file_path = self.dir.join(format!("{name}.cgp"));
+        if !file_path.is_file() {
+            return Response::not_found();
+        }
+
+        if ext == "json" {
+            return Response::ok("application/json", self.metadata(&name).into_bytes());
+        }
+        if ext == "cgp" {
+            return match fs::read(&file_path) {
+                Ok(bytes) => Response::ok("text/plain", bytes),
+                Err(_) => Response::not_found(),
+            };
+        }
+        let Ok(pattern) = CyberGrindPattern::parse_path(&file_path) else {
+            return Response::not_found();
+        };
+        match ext {
+            "svg" => Response::ok("image/svg+xml", pattern.to_svg().into_bytes()),
+            "png" => Response::ok("image/png", pattern.to_png()),
+            _ => Response::not_found(),
+        }
+    }
+
+    fn pattern_names(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "cgp" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn metadata(&self, name: &str) -> String {
+        let path = self.dir.join(format!("{name}.cgp"));
+        let pattern = match CyberGrindPattern::parse_path(path) {
+            Ok(pattern) => pattern,
+            Err(err) => {
+                return format!(
+                    "{{\"name\":\"{}\",\"error\":\"{}\"}}",
+                    json_escape(name),
+                    json_escape(&err.to_string())
+                );
+            }
+        };
+
+        let heights = pattern[..].iter().map(|tile| tile.height());
+        let min = heights.clone().min().unwrap_or_default();
+        let max = heights.max().unwrap_or_default();
+        let prefabs: Vec<String> = Prefab::ALL
+            .iter()
+            .map(|&prefab| {
+                let count = pattern[..]
+                    .iter()
+                    .filter(|tile| tile.prefab() == prefab)
+                    .count();
+                format!("\"{}\":{count}", prefab.name())
+            })
+            .collect();
+
+        format!(
+            "{{\"name\":\"{}\",\"min_height\":{min},\"max_height\":{max},\"prefabs\":{{{}}}}}",
+            json_escape(name),
+            prefabs.join(",")
+        )
+    }
+
+    fn index(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Patterns</title></head>\
+             <body style=\"font-family:sans-serif\">",
+        );
+        for name in self.pattern_names() {
+            let url = percent_encode(&name);
+            let name = html_escape(&name);
+            write!(
+                html,
+                "<figure style=\"display:inline-block\"><a href=\"/patterns/{url}.cgp\">\
+                 <img src=\"/patterns/{url}.svg\" width=\"256\" height=\"256\" alt=\"{name}\">\
+                 </a><figcaption>{name}</figcaption></figure>"
+            )
+            .expect("Writing to a String can't fail");
+        }
+        html.push_str("</body></html>");
+        html
+    }
+}
+
+// Reads the request head and returns
+// the path of a GET request.
+fn read_request_path(stream: &mut TcpStream) -> Result<Option<String>, io::Error> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < 8192 {
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut parts = head.lines().next().unwrap_or_default().split(' ');
+    if parts.next() != Some("GET") {
+        return Ok(None);
+    }
+    Ok(parts
+        .next()
+        .map(|path| path.split('?').next().unwrap_or(path).to_string()))
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::new();
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.') {
+            out.push(byte as char);
+        } else {
+            write!(out, "%{byte:02X}").expect("Writing to a String can't fail");
+        }
+    }
+    out
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).expect("Writing to a String can't fail")
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,4 +1,4 @@
-use std::{
+use core::{
     fmt::Debug,
     marker::PhantomData,
     ops::{Deref, DerefMut},
@@ -163,13 +163,13 @@ impl<'a> DerefMut for RowMut<'a> {
 }
 
 impl<'a> Debug for Row<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_list().entries(self.iter()).finish()
     }
 }
 
 impl<'a> Debug for RowMut<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_list().entries(self.iter()).finish()
     }
 }
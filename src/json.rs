@@ -0,0 +1,351 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use crate::{error::JsonError, CyberGrindPattern, Prefab, Tile, UnknownPrefab};
+
+// A parsed JSON value. Numbers are kept as
+// text, since only small integers are needed.
+enum Value {
+    Null,
+    Bool,
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+// How deeply arrays and objects can be nested. Patterns only
+// need 3 levels, this leaves room for fields which are ignored
+// without letting deeply nested input overflow the stack.
+const MAX_DEPTH: usize = 32;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Reader<'_> {
+    fn error<T>(&self) -> Result<T, JsonError> {
+        Err(JsonError::Syntax { offset: self.pos })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) != Some(&byte) {
+            return self.error();
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // Checks for `byte`, moving past it if it's there.
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.bytes.get(self.pos) == Some(&byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn keyword(&mut self, word: &[u8], value: Value) -> Result<Value, JsonError> {
+        if !self.bytes[self.pos..].starts_with(word) {
+            return self.error();
+        }
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    // Moves into an array or object.
+    fn enter(&mut self) -> Result<(), JsonError> {
+        if self.depth == MAX_DEPTH {
+            return self.error();
+        }
+        self.depth += 1;
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Value, JsonError> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => {
+                self.enter()?;
+                let mut fields = Vec::new();
+                if self.eat(b'}') {
+                    self.depth -= 1;
+                    return Ok(Value::Object(fields));
+                }
+                loop {
+                    self.expect(b'"')?;
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    if !self.eat(b',') {
+                        self.expect(b'}')?;
+                        self.depth -= 1;
+                        return Ok(Value::Object(fields));
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.enter()?;
+                let mut values = Vec::new();
+                if self.eat(b']') {
+                    self.depth -= 1;
+                    return Ok(Value::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    if !self.eat(b',') {
+                        self.expect(b']')?;
+                        self.depth -= 1;
+                        return Ok(Value::Array(values));
+                    }
+                }
+            }
+            Some(b'"') => {
+                self.pos += 1;
+                Ok(Value::String(self.string()?))
+            }
+            Some(b't') => self.keyword(b"true", Value::Bool),
+            Some(b'f') => self.keyword(b"false", Value::Bool),
+            Some(b'n') => self.keyword(b"null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') =
+                    self.bytes.get(self.pos)
+                {
+                    self.pos += 1;
+                }
+                let text =
+                    core::str::from_utf8(&self.bytes[start..self.pos]).expect("Numbers are ASCII");
+                if text.parse::<f64>().is_err() {
+                    self.pos = start;
+                    return self.error();
+                }
+                Ok(Value::Number(text.to_string()))
+            }
+            _ => self.error(),
+        }
+    }
+
+    // Reads the rest of a string, after the opening quote.
+    fn string(&mut self) -> Result<String, JsonError> {
+        let mut string = Vec::new();
+        loop {
+            let Some(&byte) = self.bytes.get(self.pos) else {
+                return self.error();
+            };
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&kind) = self.bytes.get(self.pos) else {
+                        return self.error();
+                    };
+                    self.pos += 1;
+                    let escaped = match kind {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\x08',
+                        b'f' => '\x0c',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => {
+                            self.pos -= 1;
+                            return self.error();
+                        }
+                    };
+                    string.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                0..0x20 => {
+                    self.pos -= 1;
+                    return self.error();
+                }
+                _ => string.push(byte),
+            }
+        }
+        String::from_utf8(string).or_else(|_| self.error())
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self.bytes.get(self.pos..self.pos + 4);
+        let code = digits
+            .and_then(|digits| core::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok());
+        match code {
+            Some(code) => {
+                self.pos += 4;
+                Ok(code)
+            }
+            None => self.error(),
+        }
+    }
+
+    // Reads the 4 hex digits after `\u`, along
+    // with a second escape for surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let mut code = self.hex4()?;
+        if (0xd800..0xdc00).contains(&code) && self.bytes[self.pos..].starts_with(b"\\u") {
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return self.error();
+            }
+            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+        }
+        char::from_u32(code).map_or_else(|| self.error(), Ok)
+    }
+}
+
+// Gets the 16 rows of 16 values in `field`.
+fn grid<'a>(
+    fields: &'a [(String, Value)],
+    field: &'static str,
+) -> Result<Vec<&'a [Value]>, JsonError> {
+    let (_, value) = fields
+        .iter()
+        .find(|(key, _)| key == field)
+        .ok_or(JsonError::MissingField { field })?;
+    let rows = match value {
+        Value::Array(rows) if rows.len() == 16 => rows,
+        _ => return Err(JsonError::WrongShape { field }),
+    };
+    rows.iter()
+        .map(|row| match row {
+            Value::Array(row) if row.len() == 16 => Ok(&row[..]),
+            _ => Err(JsonError::WrongShape { field }),
+        })
+        .collect()
+}
+
+impl CyberGrindPattern {
+    /// Writes the pattern as JSON, an alternative to the `.cgp`
+    /// format for web-based tools. The layout is an object with
+    /// two fields, each holding 16 rows of 16 values, indexed
+    /// by `y` and then `x`:
+    /// - `"heights"` holds numbers from -50 to 50
+    /// - `"prefabs"` holds prefab names, as given by `Prefab::name()`,
+    ///   or the character of `Prefab::Unknown` prefabs
+    ///
+    /// Each row is written on its own line.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(1, 0)].set_height(-4);
+    /// let json = pat.to_json();
+    /// assert!(json.contains("[0, -4, 0,"));
+    /// assert!(json.contains("[\"none\", \"none\","));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n  \"heights\": [\n");
+        for y in 0..16 {
+            let row: Vec<String> = (0..16).map(|x| self[(x, y)].height().to_string()).collect();
+            let comma = if y < 15 { "," } else { "" };
+            writeln!(json, "    [{}]{comma}", row.join(", "))
+                .expect("Writing to a String can't fail");
+        }
+        json.push_str("  ],\n  \"prefabs\": [\n");
+        for y in 0..16 {
+            let row: Vec<String> = (0..16)
+                .map(|x| match self[(x, y)].prefab() {
+                    Prefab::Unknown(char) => json_string(&char.char().to_string()),
+                    prefab => json_string(prefab.name()),
+                })
+                .collect();
+            let comma = if y < 15 { "," } else { "" };
+            writeln!(json, "    [{}]{comma}", row.join(", "))
+                .expect("Writing to a String can't fail");
+        }
+        json.push_str("  ]\n}\n");
+        json
+    }
+
+    /// Reads a pattern written by `CyberGrindPattern::to_json()`.
+    /// Whitespace doesn't matter, other fields are ignored and
+    /// prefabs can be written as anything `Prefab::from_str()`
+    /// accepts. Other single characters are read as
+    /// `Prefab::Unknown`. Arrays and objects nested more
+    /// than 32 deep fail with `JsonError::Syntax`.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(3, 5)].set_height(12);
+    /// let read = CyberGrindPattern::from_json(&pat.to_json()).unwrap();
+    /// assert_eq!(read[(3, 5)].height(), 12);
+    /// ```
+    pub fn from_json(json: &str) -> Result<CyberGrindPattern, JsonError> {
+        let mut reader = Reader {
+            bytes: json.as_bytes(),
+            pos: 0,
+            depth: 0,
+        };
+        let value = reader.value()?;
+        reader.skip_whitespace();
+        if reader.pos < json.len() {
+            return reader.error();
+        }
+
+        let Value::Object(fields) = value else {
+            return Err(JsonError::MissingField { field: "heights" });
+        };
+        let heights = grid(&fields, "heights")?;
+        let prefabs = grid(&fields, "prefabs")?;
+
+        let mut pattern = CyberGrindPattern::new();
+        for y in 0..16 {
+            for x in 0..16 {
+                let height = match &heights[y][x] {
+                    Value::Number(text) => text.parse::<i8>().ok(),
+                    _ => None,
+                }
+                .filter(|&height| Tile::is_valid_height(height))
+                .ok_or(JsonError::Height { x, y })?;
+
+                let prefab = match &prefabs[y][x] {
+                    Value::String(name) => name.parse().ok().or_else(|| match name.as_bytes() {
+                        &[byte] => UnknownPrefab::Keep.resolve(byte),
+                        _ => None,
+                    }),
+                    _ => None,
+                }
+                .ok_or(JsonError::Prefab { x, y })?;
+
+                pattern[(x, y)] = Tile::new(height, prefab);
+            }
+        }
+        Ok(pattern)
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for char in text.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            _ if char.is_control() => {
+                write!(json, "\\u{:04x}", char as u32).expect("Writing to a String can't fail")
+            }
+            _ => json.push(char),
+        }
+    }
+    json.push('"');
+    json
+}
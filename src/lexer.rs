@@ -0,0 +1,284 @@
+use crate::{
+    error::{ParseError, ParseErrorType},
+    CyberGrindPattern, ParseOptions, Prefab,
+};
+
+pub(crate) const BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Where a token was found in the input.
+/// `start` and `end` are byte offsets, and
+/// `line` and `column` are where `start` is,
+/// both starting at 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    /// A single digit height, like `7`
+    Height(i8),
+    /// A height in parentheses, like `(-12)`
+    ParenHeight(i8),
+    /// A prefab character, like `n`
+    Prefab(Prefab),
+    /// `\n`, or `\r\n` if CRLF is allowed
+    Newline,
+}
+
+/// A single token of a `.cgp` file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// Splits a `.cgp` file into tokens. This is the
+/// lexer `CyberGrindPattern::parse()` is built on.
+///
+/// Everything is lexed as heights until the first
+/// empty line, and as prefabs after it. The lexer
+/// doesn't check that rows have the right length,
+/// which is left up to the parser. A UTF-8 byte
+/// order mark at the start is skipped. Lexing stops
+/// after the first error.
+/// ```
+/// use tinycbg::{Lexer, TokenKind};
+///
+/// let kinds: Vec<_> = Lexer::new(b"1(-20)\n\nn")
+///     .map(|token| token.unwrap().kind)
+///     .collect();
+/// assert_eq!(kinds.len(), 5);
+/// assert_eq!(kinds[1], TokenKind::ParenHeight(-20));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Lexer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    line: u32,
+    column: u32,
+    options: ParseOptions,
+    in_prefabs: bool,
+}
+
+impl<'a> Lexer<'a> {
+    /// Creates a lexer using `ParseOptions::default()`.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_options(bytes, ParseOptions::default())
+    }
+
+    /// Creates a lexer following `options`. Only the options
+    /// about CRLF, leading zeros, clamping and unknown
+    /// prefabs affect lexing.
+    pub fn with_options(bytes: &'a [u8], options: ParseOptions) -> Self {
+        Lexer {
+            bytes,
+            // A UTF-8 byte order mark is skipped
+            pos: if bytes.starts_with(BOM) { BOM.len() } else { 0 },
+            line: 1,
+            column: 1,
+            options,
+            in_prefabs: false,
+        }
+    }
+
+    // Creates a lexer for a single line of a larger
+    // input, which is line `line` and starts at `start`.
+    pub(crate) fn line(
+        bytes: &'a [u8],
+        start: usize,
+        options: ParseOptions,
+        line: u32,
+        in_prefabs: bool,
+    ) -> Self {
+        Lexer {
+            bytes,
+            pos: start,
+            line,
+            column: 1,
+            options,
+            in_prefabs,
+        }
+    }
+
+    /// Gets the offset of the next byte to be lexed.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Gets the line and column of the next byte to be lexed.
+    pub fn line_column(&self) -> (u32, u32) {
+        (self.line, self.column)
+    }
+
+    /// Returns whether the lexer has moved
+    /// past the empty line, into the prefabs.
+    pub fn in_prefabs(&self) -> bool {
+        self.in_prefabs
+    }
+
+    /// Gets the next byte to be lexed.
+    pub fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn error(&mut self, line: u32, column: u32, char: u8, kind: ParseErrorType) -> ParseError {
+        // Stop lexing after an error
+        self.pos = self.bytes.len();
+        ParseError {
+            line,
+            column,
+            char,
+            kind,
+        }
+    }
+
+    // Gets the byte at the current position, failing
+    // if the input ends in the middle of a token.
+    fn byte(&mut self) -> Result<u8, ParseError> {
+        match self.peek() {
+            Some(char) => Ok(char),
+            None => Err(self.error(self.line, self.column, 0, ParseErrorType::UnexpectedEof)),
+        }
+    }
+
+    fn parentheses(&mut self) -> Result<i8, ParseError> {
+        let mut is_negative = false;
+        let mut height: i32 = 0;
+
+        self.pos += 1;
+        self.column += 1;
+        let mut char = self.byte()?;
+
+        while char != b')' {
+            if char == b'-' {
+                if is_negative {
+                    return Err(self.error(
+                        self.line,
+                        self.column,
+                        char,
+                        ParseErrorType::DuplicateNegative,
+                    ));
+                } else {
+                    is_negative = true;
+                }
+            } else {
+                if !(48..=57).contains(&char) {
+                    return Err(self.error(
+                        self.line,
+                        self.column,
+                        char,
+                        ParseErrorType::InvalidHeightChar,
+                    ));
+                }
+
+                if char == 48 && height == 0 && !self.options.allow_leading_zeros {
+                    return Err(self.error(
+                        self.line,
+                        self.column,
+                        char,
+                        ParseErrorType::LeadingZero,
+                    ));
+                }
+
+                height = height.saturating_mul(10).saturating_add(char as i32 - 48);
+            }
+
+            self.pos += 1;
+            self.column += 1;
+            char = self.byte()?;
+        }
+
+        if is_negative {
+            height *= -1;
+        }
+
+        if !(-50..=50).contains(&height) {
+            if !self.options.clamp_heights {
+                return Err(self.error(
+                    self.line,
+                    self.column,
+                    char,
+                    ParseErrorType::InvalidHeightValue,
+                ));
+            }
+            height = height.clamp(-50, 50);
+        }
+
+        Ok(height as i8)
+    }
+
+    fn next_token(&mut self) -> Result<Token, ParseError> {
+        let start = self.pos;
+        let (line, column) = (self.line, self.column);
+        let char = self.bytes[self.pos];
+
+        let is_newline = char == b'\n'
+            || (char == b'\r'
+                && self.options.allow_crlf
+                && self.bytes.get(self.pos + 1) == Some(&b'\n'));
+        let kind = if is_newline {
+            // An empty line separates the heights from the prefabs
+            if column == 1 {
+                self.in_prefabs = true;
+            }
+            self.pos += if char == b'\r' { 2 } else { 1 };
+            self.line += 1;
+            self.column = 1;
+            TokenKind::Newline
+        } else if self.in_prefabs {
+            let prefab = match Prefab::try_from(char) {
+                Ok(prefab) => prefab,
+                Err(kind) => match self.options.unknown_prefab.resolve(char) {
+                    Some(prefab) => prefab,
+                    None => return Err(self.error(line, column, char, kind)),
+                },
+            };
+            self.pos += 1;
+            self.column += 1;
+            TokenKind::Prefab(prefab)
+        } else if char == b'(' {
+            let height = self.parentheses()?;
+            self.pos += 1;
+            self.column += 1;
+            TokenKind::ParenHeight(height)
+        } else if char.is_ascii_digit() {
+            self.pos += 1;
+            self.column += 1;
+            TokenKind::Height(char as i8 - 48)
+        } else {
+            return Err(self.error(line, column, char, ParseErrorType::InvalidHeightChar));
+        };
+
+        Ok(Token {
+            kind,
+            span: Span {
+                start,
+                end: self.pos,
+                line,
+                column,
+            },
+        })
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, ParseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.bytes.len() {
+            return None;
+        }
+        Some(self.next_token())
+    }
+}
+
+impl CyberGrindPattern {
+    /// Splits a series of bytes into tokens.
+    /// Same as `Lexer::new(bytes)`
+    pub fn tokenize(bytes: &[u8]) -> Lexer<'_> {
+        Lexer::new(bytes)
+    }
+}
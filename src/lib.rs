@@ -1,51 +1,183 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod analysis;
+mod binary;
+mod builder;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "charts")]
+pub mod chart;
+mod coord;
+#[cfg(feature = "crdt")]
+pub mod crdt;
 pub mod error;
+#[cfg(feature = "std")]
+mod generate;
+#[cfg(feature = "http")]
+pub mod http;
 mod iter;
+mod json;
+mod lexer;
+mod lint;
+mod macros;
+#[cfg(feature = "net")]
+pub mod net;
 mod normal_fmt;
+mod op;
+mod region;
+#[cfg(feature = "std")]
+mod render;
+mod sanitize;
+#[cfg(feature = "std")]
+mod scale;
+#[cfg(feature = "schemars")]
+mod schema;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod shape;
+mod share;
+mod source_map;
+mod symmetry;
 mod tile;
+mod transform;
+#[cfg(feature = "std")]
+pub use analysis::Light;
+pub use binary::BINARY_MAGIC;
+pub use binary::BINARY_SIZE;
+pub use binary::BINARY_VERSION;
+pub use builder::PatternBuilder;
+#[cfg(feature = "std")]
+pub use cache::PatternCache;
+pub use coord::Coord;
+#[cfg(feature = "std")]
+pub use generate::Archetype;
+#[cfg(feature = "std")]
+pub use generate::PillarConfig;
+#[cfg(feature = "std")]
+pub use generate::Rng;
+#[cfg(feature = "std")]
+pub use generate::VoronoiConfig;
+pub use lexer::Lexer;
+pub use lexer::Span;
+pub use lexer::Token;
+pub use lexer::TokenKind;
+pub use lint::Diagnostic;
+pub use lint::DiagnosticKind;
+pub use lint::Fix;
+pub use lint::Severity;
+#[doc(hidden)]
+pub use macros::__cgp;
+#[doc(hidden)]
+pub use macros::__include_cgp;
+pub use normal_fmt::LineEnding;
+pub use normal_fmt::ParseOptions;
+pub use normal_fmt::ParseStatus;
+pub use normal_fmt::Parser;
+pub use normal_fmt::UnknownPrefab;
+pub use normal_fmt::WriteOptions;
+#[cfg(feature = "std")]
+pub use normal_fmt::WritePolicy;
+pub use normal_fmt::DEFAULT_MAX_FILE_SIZE;
+pub use op::Op;
+pub use op::Recorder;
+pub use region::Rect;
+pub use region::SubPattern;
+#[cfg(feature = "std")]
+pub use render::PNG_TILE_SIZE;
+pub use sanitize::Correction;
+pub use sanitize::OutOfRange;
+pub use sanitize::RawPattern;
+pub use sanitize::SanitizeConfig;
+#[cfg(feature = "std")]
+pub use scale::WorldScale;
+pub use shape::Selection;
+pub use source_map::Layer;
+pub use source_map::SourceMap;
+pub use symmetry::BalanceReport;
+pub use symmetry::Quadrant;
+pub use symmetry::Symmetry;
+pub use symmetry::SymmetryOrder;
 pub use tile::Prefab;
 pub use tile::Tile;
+pub use tile::TileBuilder;
+pub use tile::UnknownChar;
 
-use std::fmt::Debug;
-use std::ops::Index;
-use std::ops::IndexMut;
-use std::ops::Range;
-use std::ops::RangeFull;
+use alloc::{format, vec::Vec};
+use core::fmt::Debug;
+use core::ops::Add;
+use core::ops::Index;
+use core::ops::IndexMut;
+use core::ops::Range;
+use core::ops::RangeFull;
+use core::ops::Sub;
+use core::ops::{Bound, RangeBounds};
+use error::{SliceError, WrongLength};
 
+/// Size of the largest file `CyberGrindPattern::write()`
+/// can produce. Files being parsed can be bigger,
+/// up to `ParseOptions::max_file_size`.
 pub const MAX_FILE_SIZE: usize = 1569;
 pub mod prelude {
+    pub use crate::error::BinaryError;
+    pub use crate::error::BufferTooSmall;
+    pub use crate::error::HeightError;
+    #[cfg(feature = "std")]
     pub use crate::error::IoError;
+    pub use crate::error::JsonError;
+    pub use crate::error::OpParseError;
+    pub use crate::error::OutOfBounds;
     pub use crate::error::ParseError;
     pub use crate::error::ParseErrorType;
+    pub use crate::error::ShareCodeError;
+    pub use crate::error::SliceError;
+    pub use crate::error::UnknownArchetype;
+    pub use crate::error::WrongLength;
     pub use crate::CyberGrindPattern;
 }
 
+/// Which of two patterns to take prefabs from when
+/// combining them, such as with `CyberGrindPattern::combine_max()`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PrefabSide {
+    /// The pattern the method is called on
+    #[default]
+    Left,
+    /// The pattern passed to the method
+    Right,
+}
+
 impl Default for CyberGrindPattern {
     fn default() -> Self {
-        CyberGrindPattern {
-            tiles: [Tile::default(); 256],
-        }
+        CyberGrindPattern::splat(Tile::default())
     }
 }
 
-impl From<Vec<Tile>> for CyberGrindPattern {
-    fn from(values: Vec<Tile>) -> Self {
-        let mut new = Self::new();
-        for (i, value) in values.iter().enumerate() {
-            if i > 255 {
-                break;
-            }
-            new[i] = *value;
-        }
-        new
+impl TryFrom<&[Tile]> for CyberGrindPattern {
+    type Error = WrongLength;
+    fn try_from(values: &[Tile]) -> Result<Self, WrongLength> {
+        let tiles: [Tile; 256] = values.try_into().map_err(|_| WrongLength {
+            expected: 256,
+            found: values.len(),
+        })?;
+        Ok(CyberGrindPattern { tiles })
+    }
+}
+impl TryFrom<Vec<Tile>> for CyberGrindPattern {
+    type Error = WrongLength;
+    fn try_from(values: Vec<Tile>) -> Result<Self, WrongLength> {
+        Self::try_from(values.as_slice())
     }
 }
 impl From<[Tile; 256]> for CyberGrindPattern {
     fn from(tiles: [Tile; 256]) -> Self {
-        CyberGrindPattern { tiles }
+        CyberGrindPattern::from_array(tiles)
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 pub struct CyberGrindPattern {
     tiles: [Tile; 256],
 }
@@ -101,7 +233,7 @@ fn set_header(arr: &mut [u8]) {
 }
 
 impl Debug for CyberGrindPattern {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         const ARR_SIZE: usize = DBG_ROW_SIZE * 33 - 1;
         let mut res: [u8; ARR_SIZE] = [b'0'; ARR_SIZE];
         set_header(&mut res);
@@ -153,22 +285,183 @@ impl Debug for CyberGrindPattern {
 /// // coordinates or numbers 0-255
 /// pat[(7, 7)].set_prefab(Prefab::Melee);
 ///
+/// # #[cfg(feature = "std")]
 /// pat.write_to_path("test.cgp"); // Cybergrind patterns are stored in '.cgp' files
 /// ```
 impl CyberGrindPattern {
     /// Creates a new Cybergrind pattern
     /// with all tiles initialized to have
     /// a height of zero and no prefab.
-    /// Same as `CyberGrindPattern::default()`
+    /// Same as `CyberGrindPattern::default()`,
+    /// but usable in `const` contexts.
     /// ```
     /// use tinycbg::{CyberGrindPattern, Prefab};
     ///
-    /// let pat = CyberGrindPattern::new();
-    /// assert_eq!(pat[0].height(), 0);
-    /// assert_eq!(pat[0].prefab(), Prefab::None);
+    /// static EMPTY: CyberGrindPattern = CyberGrindPattern::new();
+    /// assert_eq!(EMPTY[0].height(), 0);
+    /// assert_eq!(EMPTY[0].prefab(), Prefab::None);
+    /// ```
+    pub const fn new() -> Self {
+        CyberGrindPattern::splat(Tile::with_height(0))
+    }
+
+    /// Creates a new Cybergrind pattern
+    /// with every tile set to `tile`.
     /// ```
-    pub fn new() -> Self {
-        CyberGrindPattern::default()
+    /// use tinycbg::{CyberGrindPattern, Prefab, Tile};
+    ///
+    /// let pat = CyberGrindPattern::splat(Tile::new(-10, Prefab::Stairs));
+    /// assert!(pat[..].iter().all(|tile| tile.height() == -10));
+    /// ```
+    pub const fn splat(tile: Tile) -> Self {
+        CyberGrindPattern { tiles: [tile; 256] }
+    }
+
+    /// Creates a new Cybergrind pattern by calling
+    /// `f` with the coordinates of every tile, going
+    /// through each row from `(0, 0)` to `(15, 15)`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// // A bowl, lowest in the middle
+    /// let pat = CyberGrindPattern::from_fn(|x, y| {
+    ///     let distance = x.abs_diff(8).max(y.abs_diff(8));
+    ///     Tile::with_height(distance as i8 * 3)
+    /// });
+    /// assert_eq!(pat[(8, 8)].height(), 0);
+    /// assert_eq!(pat[(0, 3)].height(), 24);
+    /// ```
+    pub fn from_fn<F: FnMut(usize, usize) -> Tile>(mut f: F) -> Self {
+        CyberGrindPattern {
+            tiles: core::array::from_fn(|index| f(index % 16, index / 16)),
+        }
+    }
+
+    /// Creates a new pattern by calling `f` with the
+    /// coordinates and tile of every tile in the pattern.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// // Lower the edges more than the center
+    /// let pat = CyberGrindPattern::splat(Tile::with_height(20));
+    /// let falloff = pat.map(|x, y, tile| {
+    ///     let distance = x.abs_diff(8).max(y.abs_diff(8));
+    ///     tile - distance as i8 * 2
+    /// });
+    /// assert_eq!(falloff[(8, 8)].height(), 20);
+    /// assert_eq!(falloff[(0, 0)].height(), 4);
+    /// ```
+    pub fn map<F: FnMut(usize, usize, Tile) -> Tile>(&self, mut f: F) -> Self {
+        CyberGrindPattern::from_fn(|x, y| f(x, y, self.tiles[y * 16 + x]))
+    }
+
+    /// Same as `CyberGrindPattern::map()`,
+    /// but changes the pattern in place.
+    pub fn map_in_place<F: FnMut(usize, usize, &mut Tile)>(&mut self, mut f: F) {
+        for (index, tile) in self.tiles.iter_mut().enumerate() {
+            f(index % 16, index / 16, tile);
+        }
+    }
+
+    /// Creates a new Cybergrind pattern from an array
+    /// of 256 tiles, in the same order as indexing the
+    /// pattern with numbers. Same as
+    /// `CyberGrindPattern::from(tiles)`, but usable in
+    /// `const` contexts.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Prefab, Tile};
+    ///
+    /// const RING: CyberGrindPattern = {
+    ///     let mut tiles = [Tile::with_height(10); 256];
+    ///     let mut i = 0;
+    ///     while i < 256 {
+    ///         if i % 16 > 0 && i % 16 < 15 && i > 16 && i < 240 {
+    ///             tiles[i] = Tile::with_prefab(Prefab::Melee);
+    ///         }
+    ///         i += 1;
+    ///     }
+    ///     CyberGrindPattern::from_array(tiles)
+    /// };
+    /// assert_eq!(RING[(0, 5)].height(), 10);
+    /// assert_eq!(RING[(5, 5)].prefab(), Prefab::Melee);
+    /// ```
+    pub const fn from_array(tiles: [Tile; 256]) -> Self {
+        CyberGrindPattern { tiles }
+    }
+
+    /// Creates a new Cybergrind pattern from
+    /// a slice of exactly 256 tiles.
+    /// Same as `CyberGrindPattern::try_from(tiles)`
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let tiles = [Tile::with_height(5); 256];
+    /// let pat = CyberGrindPattern::from_tiles(&tiles).unwrap();
+    /// assert_eq!(pat[255].height(), 5);
+    ///
+    /// assert!(CyberGrindPattern::from_tiles(&tiles[..100]).is_err());
+    /// ```
+    pub fn from_tiles(tiles: &[Tile]) -> Result<Self, WrongLength> {
+        Self::try_from(tiles)
+    }
+
+    /// Creates a new Cybergrind pattern from
+    /// a slice of exactly 256 heights, with
+    /// every tile's prefab set to `Prefab::None`.
+    /// Fails if the slice has the wrong length
+    /// or a height is not between -50 and 50.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    /// use tinycbg::error::SliceError;
+    ///
+    /// let mut heights = [0; 256];
+    /// heights[17] = -20;
+    /// let pat = CyberGrindPattern::from_heights_slice(&heights).unwrap();
+    /// assert_eq!(pat[(1, 1)].height(), -20);
+    ///
+    /// heights[3] = 51;
+    /// assert_eq!(
+    ///     CyberGrindPattern::from_heights_slice(&heights).unwrap_err(),
+    ///     SliceError::Height { index: 3, height: 51 }
+    /// );
+    /// ```
+    pub fn from_heights_slice(heights: &[i8]) -> Result<Self, SliceError> {
+        if heights.len() != 256 {
+            return Err(SliceError::Length(WrongLength {
+                expected: 256,
+                found: heights.len(),
+            }));
+        }
+
+        let mut new = Self::new();
+        for (index, &height) in heights.iter().enumerate() {
+            if !Tile::is_valid_height(height) {
+                return Err(SliceError::Height { index, height });
+            }
+            new[index].set_height(height);
+        }
+        Ok(new)
+    }
+
+    /// Creates a new Cybergrind pattern from
+    /// a list of tiles. Extra tiles past the
+    /// 256th are dropped, and missing ones are
+    /// left as `Tile::default()`.
+    /// Use `CyberGrindPattern::try_from` if
+    /// the length should be checked instead.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let pat = CyberGrindPattern::from_tiles_lossy(&[Tile::with_height(5); 10]);
+    /// assert_eq!(pat[9].height(), 5);
+    /// assert_eq!(pat[10].height(), 0);
+    /// ```
+    pub fn from_tiles_lossy(values: &[Tile]) -> Self {
+        let mut new = Self::new();
+        for (i, value) in values.iter().take(256).enumerate() {
+            new[i] = *value;
+        }
+        new
     }
 
     /// Copies the data from tile `Tile` to
@@ -192,6 +485,186 @@ impl CyberGrindPattern {
         }
     }
 
+    /// Sets every tile in the pattern to `tile`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.fill(Tile::with_height(4));
+    /// assert!(pat[..].iter().all(|&tile| tile == Tile::with_height(4)));
+    /// ```
+    pub fn fill(&mut self, tile: Tile) {
+        self.tiles = [tile; 256];
+    }
+
+    /// Sets the outermost ring of tiles to `tile`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.fill_border(Tile::with_height(20));
+    /// assert_eq!(pat[(0, 9)].height(), 20);
+    /// assert_eq!(pat[(9, 15)].height(), 20);
+    /// assert_eq!(pat[(1, 1)].height(), 0);
+    /// ```
+    pub fn fill_border(&mut self, tile: Tile) {
+        for i in [0, 15] {
+            self.copy_tile_to_row(tile, i);
+            self.copy_tile_to_column(tile, i);
+        }
+    }
+
+    /// Sets every tile in the rows in `rows` to `tile`.
+    /// Panics if `rows` goes past row 15.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.fill_rows(6..=9, Tile::with_height(-10));
+    /// assert_eq!(pat[(3, 6)].height(), -10);
+    /// assert_eq!(pat[(3, 9)].height(), -10);
+    /// assert_eq!(pat[(3, 10)].height(), 0);
+    /// ```
+    pub fn fill_rows<R: RangeBounds<usize>>(&mut self, rows: R, tile: Tile) {
+        let start = match rows.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match rows.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => 16,
+        };
+        assert!(end <= 16, "Rows must be less than 16");
+        if start < end {
+            self.tiles[start * 16..end * 16].fill(tile);
+        }
+    }
+
+    /// Flips the height of every tile, turning towers
+    /// into pits and pits into towers. Prefabs are kept.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.build_border(20, 1);
+    /// pat.invert_heights();
+    /// assert_eq!(pat[(0, 0)].height(), -20);
+    /// assert_eq!(pat[(5, 5)].height(), 0);
+    /// ```
+    pub fn invert_heights(&mut self) {
+        for tile in &mut self.tiles {
+            *tile = -*tile;
+        }
+    }
+
+    /// Creates a new pattern by calling `f` with the
+    /// tiles of `self` and `other` at each position.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let terrain = CyberGrindPattern::splat(Tile::with_height(10));
+    /// let detail = CyberGrindPattern::from_fn(|x, _| Tile::with_height(x as i8));
+    ///
+    /// let highest = terrain.combine_with(&detail, |a, b| {
+    ///     if a.height() > b.height() { a } else { b }
+    /// });
+    /// assert_eq!(highest[(3, 0)].height(), 10);
+    /// assert_eq!(highest[(13, 0)].height(), 13);
+    /// ```
+    pub fn combine_with<F: FnMut(Tile, Tile) -> Tile>(
+        &self,
+        other: &CyberGrindPattern,
+        mut f: F,
+    ) -> CyberGrindPattern {
+        CyberGrindPattern {
+            tiles: core::array::from_fn(|index| f(self.tiles[index], other.tiles[index])),
+        }
+    }
+
+    /// Creates a new pattern with the highest height of
+    /// `self` and `other` at each tile, taking prefabs
+    /// from `prefabs`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, PrefabSide, Tile};
+    ///
+    /// let mut walls = CyberGrindPattern::new();
+    /// walls.build_border(20, 1);
+    /// let mut pillars = CyberGrindPattern::new();
+    /// pillars[(0, 0)].set_height(35);
+    /// pillars[(8, 8)].set_height(35);
+    ///
+    /// let arena = walls.combine_max(&pillars, PrefabSide::Left);
+    /// assert_eq!(arena[(0, 0)].height(), 35);
+    /// assert_eq!(arena[(0, 5)].height(), 20);
+    /// assert_eq!(arena[(8, 8)].height(), 35);
+    /// ```
+    pub fn combine_max(&self, other: &CyberGrindPattern, prefabs: PrefabSide) -> CyberGrindPattern {
+        self.combine_heights(other, prefabs, i8::max)
+    }
+
+    /// Creates a new pattern with the lowest height of
+    /// `self` and `other` at each tile, taking prefabs
+    /// from `prefabs`.
+    pub fn combine_min(&self, other: &CyberGrindPattern, prefabs: PrefabSide) -> CyberGrindPattern {
+        self.combine_heights(other, prefabs, i8::min)
+    }
+
+    fn combine_heights(
+        &self,
+        other: &CyberGrindPattern,
+        prefabs: PrefabSide,
+        height: fn(i8, i8) -> i8,
+    ) -> CyberGrindPattern {
+        self.combine_with(other, |a, b| {
+            let prefab = match prefabs {
+                PrefabSide::Left => a.prefab(),
+                PrefabSide::Right => b.prefab(),
+            };
+            Tile::new(height(a.height(), b.height()), prefab)
+        })
+    }
+
+    /// Gets the tile at `(x, y)`, or `None`
+    /// if `x` or `y` is greater than 15.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let pat = CyberGrindPattern::new();
+    /// assert!(pat.get(15, 3).is_some());
+    /// assert!(pat.get(20, 3).is_none());
+    /// ```
+    pub fn get(&self, x: usize, y: usize) -> Option<&Tile> {
+        if x < 16 && y < 16 {
+            Some(&self.tiles[y * 16 + x])
+        } else {
+            None
+        }
+    }
+
+    /// Gets the tile at `(x, y)` mutably, or
+    /// `None` if `x` or `y` is greater than 15.
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Tile> {
+        if x < 16 && y < 16 {
+            Some(&mut self.tiles[y * 16 + x])
+        } else {
+            None
+        }
+    }
+
+    /// Gets the tile at `index`, or `None` if
+    /// `index` is greater than 255.
+    pub fn get_index(&self, index: usize) -> Option<&Tile> {
+        self.tiles.get(index)
+    }
+
+    /// Gets the tile at `index` mutably, or
+    /// `None` if `index` is greater than 255.
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut Tile> {
+        self.tiles.get_mut(index)
+    }
+
     /// Copies the data from tile `Tile` to
     /// column number `column`.
     /// ```
@@ -228,18 +701,19 @@ impl IndexMut<usize> for CyberGrindPattern {
     }
 }
 
+/// Gets the tile at `(x, y)`. Panics if
+/// `x` or `y` is greater than 15.
 impl Index<(usize, usize)> for CyberGrindPattern {
     type Output = Tile;
-    fn index(&self, coordinate: (usize, usize)) -> &Self::Output {
-        let index = coordinate.1 * 16 + coordinate.0;
-        &self.tiles[index]
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        self.get(x, y).expect("Coordinates must be less than 16")
     }
 }
 
 impl IndexMut<(usize, usize)> for CyberGrindPattern {
-    fn index_mut(&mut self, coordinate: (usize, usize)) -> &mut Tile {
-        let index = coordinate.1 * 16 + coordinate.0;
-        &mut self.tiles[index]
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Tile {
+        self.get_mut(x, y)
+            .expect("Coordinates must be less than 16")
     }
 }
 
@@ -268,3 +742,41 @@ impl IndexMut<RangeFull> for CyberGrindPattern {
         &mut self.tiles[range]
     }
 }
+
+/// Adds the heights of two patterns tile by tile,
+/// clamping them between -50 and 50. Prefabs are
+/// kept from the left side, unless it has none
+/// on a tile.
+/// ```
+/// use tinycbg::{CyberGrindPattern, Prefab, Tile};
+///
+/// let terrain = CyberGrindPattern::splat(Tile::with_height(30));
+/// let mut detail = CyberGrindPattern::new();
+/// detail[(4, 4)] = Tile::new(25, Prefab::Melee);
+///
+/// let combined = terrain + &detail;
+/// assert_eq!(combined[(4, 4)], Tile::new(50, Prefab::Melee));
+/// assert_eq!(combined[(5, 4)].height(), 30);
+/// ```
+impl Add<&CyberGrindPattern> for CyberGrindPattern {
+    type Output = CyberGrindPattern;
+    fn add(self, rhs: &CyberGrindPattern) -> Self::Output {
+        self.combine_with(rhs, |a, b| {
+            let mut tile = a.saturating_add(b.height());
+            if a.prefab() == Prefab::None {
+                tile.set_prefab(b.prefab());
+            }
+            tile
+        })
+    }
+}
+
+/// Subtracts the heights of two patterns tile by
+/// tile, clamping them between -50 and 50.
+/// Prefabs are kept from the left side.
+impl Sub<&CyberGrindPattern> for CyberGrindPattern {
+    type Output = CyberGrindPattern;
+    fn sub(self, rhs: &CyberGrindPattern) -> Self::Output {
+        self.combine_with(rhs, |a, b| a.saturating_sub(b.height()))
+    }
+}
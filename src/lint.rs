@@ -0,0 +1,343 @@
+use alloc::{
+    collections::VecDeque,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{Display, Formatter, Result as FmtRes},
+    ops::Range,
+};
+
+use crate::{
+    error::{ParseError, ParseErrorType},
+    lexer::BOM,
+    normal_fmt::{line_end, ParseSink},
+    CyberGrindPattern, ParseOptions, Prefab, Span,
+};
+
+/// How serious a `Diagnostic` is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The input is still a valid pattern,
+    /// but is likely a mistake
+    Warning,
+    /// The input is not a valid pattern
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiagnosticKind {
+    /// The input failed to parse
+    Parse(ParseErrorType),
+    /// A height between 0 and 9 was
+    /// written in parentheses
+    UnneededParentheses,
+    /// Parentheses without any digits,
+    /// which are read as a height of 0
+    EmptyParentheses,
+    /// There are bytes left over after the pattern
+    TrailingData,
+    /// Every tile has a height of 0 and no prefab
+    AllZero,
+}
+
+/// A change which fixes the problem
+/// a `Diagnostic` points out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fix {
+    /// Byte offsets of the text to replace
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl Fix {
+    /// Applies the fix to the bytes it was made for.
+    pub fn apply(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut fixed = bytes[..self.range.start].to_vec();
+        fixed.extend_from_slice(self.replacement.as_bytes());
+        fixed.extend_from_slice(&bytes[self.range.end..]);
+        fixed
+    }
+}
+
+/// A problem found by `CyberGrindPattern::lint()`.
+/// Lines and columns start at 1, and the end
+/// of the problem is exclusive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// Byte offsets of the problem
+    pub range: Range<usize>,
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    pub fix: Option<Fix>,
+}
+
+impl Diagnostic {
+    fn new(
+        bytes: &[u8],
+        range: Range<usize>,
+        severity: Severity,
+        kind: DiagnosticKind,
+        fix: Option<Fix>,
+    ) -> Self {
+        let (line, column) = position(bytes, range.start);
+        let (end_line, end_column) = position(bytes, range.end);
+        Diagnostic {
+            line,
+            column,
+            end_line,
+            end_column,
+            range,
+            severity,
+            kind,
+            fix,
+        }
+    }
+
+    /// Gets a human readable description of the problem.
+    pub fn message(&self) -> String {
+        match &self.kind {
+            DiagnosticKind::Parse(kind) => kind.to_string(),
+            DiagnosticKind::UnneededParentheses => "Unneeded parentheses".to_string(),
+            DiagnosticKind::EmptyParentheses => "Empty parentheses".to_string(),
+            DiagnosticKind::TrailingData => "Trailing data after pattern".to_string(),
+            DiagnosticKind::AllZero => "Pattern is completely empty".to_string(),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtRes {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(
+            f,
+            "{severity} at line {}, column {}: {}",
+            self.line,
+            self.column,
+            self.message()
+        )
+    }
+}
+
+// Gets the offset of the first byte of line 1,
+// skipping a byte order mark like the lexer does.
+fn first_line_start(bytes: &[u8]) -> usize {
+    if bytes.starts_with(BOM) {
+        BOM.len()
+    } else {
+        0
+    }
+}
+
+// Gets the line and column of the byte at `offset`.
+fn position(bytes: &[u8], offset: usize) -> (u32, u32) {
+    let before = &bytes[..offset.min(bytes.len())];
+    let line = before.iter().filter(|&&byte| byte == b'\n').count() as u32 + 1;
+    let line_start = before
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(first_line_start(bytes), |i| i + 1);
+    (line, offset.saturating_sub(line_start) as u32 + 1)
+}
+
+// Gets the offset of the byte at `line` and `column`.
+fn offset(bytes: &[u8], line: u32, column: u32) -> usize {
+    let line_start = if line == 1 {
+        first_line_start(bytes)
+    } else {
+        bytes
+            .iter()
+            .enumerate()
+            .filter(|&(_, &byte)| byte == b'\n')
+            .nth(line as usize - 2)
+            .map_or(bytes.len(), |(i, _)| i + 1)
+    };
+    line_start + column as usize - 1
+}
+
+// Lints the input a line at a time, so
+// nothing is parsed until it's needed.
+struct Linter<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    // Next row of the pattern to lint,
+    // counting the empty line
+    row: usize,
+    found: VecDeque<Diagnostic>,
+    all_zero: bool,
+    done: bool,
+}
+
+impl Linter<'_> {
+    fn warn(&mut self, span: Span, kind: DiagnosticKind, replacement: &str) {
+        let range = span.start..span.end;
+        let fix = Fix {
+            range: range.clone(),
+            replacement: replacement.to_string(),
+        };
+        self.found.push_back(Diagnostic::new(
+            self.bytes,
+            range,
+            Severity::Warning,
+            kind,
+            Some(fix),
+        ));
+    }
+
+    fn lint_line(&mut self) {
+        let bytes = self.bytes;
+        if self.row == 33 {
+            self.lint_end();
+            return;
+        }
+
+        let end = line_end(bytes, self.pos);
+        let options = ParseOptions::default();
+        let res = CyberGrindPattern::parse_line(&bytes[..end], self.pos, self.row, self, &options);
+        self.pos = end;
+        self.row += 1;
+        if let Err(err) = res {
+            self.error(err);
+        }
+    }
+
+    // Checks the whole pattern once every line is parsed.
+    fn lint_end(&mut self) {
+        let bytes = self.bytes;
+        if self.all_zero {
+            self.found.push_back(Diagnostic::new(
+                bytes,
+                0..self.pos,
+                Severity::Warning,
+                DiagnosticKind::AllZero,
+                None,
+            ));
+        }
+        if self.pos < bytes.len() {
+            let range = self.pos..bytes.len();
+            let fix = Fix {
+                range: range.clone(),
+                replacement: String::new(),
+            };
+            self.found.push_back(Diagnostic::new(
+                bytes,
+                range,
+                Severity::Warning,
+                DiagnosticKind::TrailingData,
+                Some(fix),
+            ));
+        }
+        self.done = true;
+    }
+
+    fn error(&mut self, err: ParseError) {
+        let bytes = self.bytes;
+        let start = offset(bytes, err.line, err.column).min(bytes.len());
+        let range = start..(start + 1).min(bytes.len());
+        // Extra characters can simply be removed
+        let fix = matches!(
+            err.kind,
+            ParseErrorType::LeadingZero | ParseErrorType::DuplicateNegative
+        )
+        .then(|| Fix {
+            range: range.clone(),
+            replacement: String::new(),
+        });
+        self.found.push_back(Diagnostic::new(
+            bytes,
+            range,
+            Severity::Error,
+            DiagnosticKind::Parse(err.kind),
+            fix,
+        ));
+        // Parsing stops at the first error
+        self.done = true;
+    }
+}
+
+impl Iterator for Linter<'_> {
+    type Item = Diagnostic;
+    fn next(&mut self) -> Option<Diagnostic> {
+        while self.found.is_empty() && !self.done {
+            self.lint_line();
+        }
+        self.found.pop_front()
+    }
+}
+
+impl ParseSink for Linter<'_> {
+    fn height(&mut self, _index: usize, height: i8, span: Span) {
+        self.all_zero &= height == 0;
+        let text = &self.bytes[span.start..span.end];
+        if text == b"()" || text == b"(-)" {
+            self.warn(span, DiagnosticKind::EmptyParentheses, "0");
+        } else if text[0] == b'(' && (0..10).contains(&height) {
+            self.warn(
+                span,
+                DiagnosticKind::UnneededParentheses,
+                &height.to_string(),
+            );
+        }
+    }
+
+    fn prefab(&mut self, _index: usize, prefab: Prefab, _span: Span) {
+        self.all_zero &= prefab == Prefab::None;
+    }
+}
+
+impl CyberGrindPattern {
+    /// Checks a series of bytes for errors, like
+    /// `CyberGrindPattern::validate_bytes()`, as well as
+    /// things which parse fine but are likely mistakes.
+    /// The input is parsed a line at a time as the
+    /// iterator is advanced, and diagnostics are handed
+    /// back in the order they appear. Diagnostics about
+    /// the whole pattern, like `DiagnosticKind::AllZero`
+    /// and `DiagnosticKind::TrailingData`, come last.
+    /// Parsing stops at the first error, so at most
+    /// one error is ever reported.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, DiagnosticKind};
+    ///
+    /// let mut bytes = std::fs::read("tests/patterns/valid/max_len.cgp").unwrap();
+    /// bytes.extend_from_slice(b"garbage");
+    ///
+    /// let kinds: Vec<_> = CyberGrindPattern::lint(&bytes).map(|d| d.kind).collect();
+    /// assert_eq!(kinds, [DiagnosticKind::TrailingData]);
+    /// ```
+    pub fn lint(bytes: &[u8]) -> impl Iterator<Item = Diagnostic> {
+        Linter {
+            bytes,
+            pos: first_line_start(bytes),
+            row: 0,
+            found: VecDeque::new(),
+            all_zero: true,
+            done: false,
+        }
+    }
+
+    /// Same as `CyberGrindPattern::lint()`, collected into a `Vec`.
+    /// Every diagnostic has a byte range and a range of lines and
+    /// columns, and most warnings come with a `Fix`, which makes
+    /// this a good fit for editor integrations.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let text = std::fs::read_to_string("tests/patterns/valid/max_len.cgp").unwrap();
+    /// let text = text.replacen("(-50)", "(1)", 1);
+    ///
+    /// let diagnostics = CyberGrindPattern::diagnostics(text.as_bytes());
+    /// let fixed = diagnostics[0].fix.as_ref().unwrap().apply(text.as_bytes());
+    /// assert!(CyberGrindPattern::diagnostics(&fixed).is_empty());
+    /// ```
+    pub fn diagnostics(bytes: &[u8]) -> Vec<Diagnostic> {
+        Self::lint(bytes).collect()
+    }
+}
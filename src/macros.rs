@@ -0,0 +1,239 @@
+use crate::{lexer::BOM, CyberGrindPattern, Prefab, Tile};
+
+/// Writes a pattern inline as a 16x16 grid, which is
+/// checked at compile time. Each row is written in
+/// brackets, with heights from -50 to 50 separated by
+/// spaces. Prefabs can optionally follow a `;` as 16 more
+/// rows, using the same characters as `.cgp` files.
+/// Rows go from `y = 0` to `y = 15`.
+/// ```
+/// use tinycbg::{cgp, Prefab};
+///
+/// let pattern = cgp! {
+///     [5 5 5 5 5 5 5 5 5 5 5 5 5 5 5 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 -10 -10 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 -10 -10 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 0 0 0 0 0 0 0 0 0 0 0 0 0 0 5]
+///     [5 5 5 5 5 5 5 5 5 5 5 5 5 5 5 5]
+///     ;
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 n 0 0 0 0 0 0 0 0 0 0 0 0 p 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 J 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+///     [0 H 0 0 0 0 0 0 0 0 0 0 0 0 s 0]
+///     [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+/// };
+/// assert_eq!(pattern[(7, 7)].height(), -10);
+/// assert_eq!(pattern[(7, 7)].prefab(), Prefab::JumpPad);
+/// assert_eq!(pattern[(1, 14)].prefab(), Prefab::HideousMass);
+/// ```
+/// Rows of the wrong length don't compile:
+/// ```compile_fail
+/// let pattern = tinycbg::cgp! {
+///     [0 0 0]
+/// };
+/// ```
+#[macro_export]
+macro_rules! cgp {
+    ($([$($height:literal)*])* $(; $([$($prefab:tt)*])*)?) => {{
+        const PATTERN: $crate::CyberGrindPattern = $crate::__cgp(
+            &[$(&[$($height),*]),*],
+            &[$($(&[$(stringify!($prefab)),*]),*)?],
+        );
+        PATTERN
+    }};
+}
+
+// Builds the pattern for `cgp!`, panicking
+// (and so failing to compile) on bad input.
+#[doc(hidden)]
+pub const fn __cgp(heights: &[&[i8]], prefabs: &[&[&str]]) -> CyberGrindPattern {
+    assert!(heights.len() == 16, "cgp! needs 16 rows of heights");
+    assert!(
+        prefabs.is_empty() || prefabs.len() == 16,
+        "cgp! needs 16 rows of prefabs"
+    );
+    let mut tiles = [Tile::with_height(0); 256];
+    let mut y = 0;
+    while y < 16 {
+        assert!(heights[y].len() == 16, "Every row needs 16 heights");
+        assert!(
+            prefabs.is_empty() || prefabs[y].len() == 16,
+            "Every row needs 16 prefabs"
+        );
+        let mut x = 0;
+        while x < 16 {
+            let prefab = if prefabs.is_empty() {
+                Prefab::None
+            } else {
+                prefab(prefabs[y][x])
+            };
+            tiles[y * 16 + x] = Tile::new(heights[y][x], prefab);
+            x += 1;
+        }
+        y += 1;
+    }
+    CyberGrindPattern::from_array(tiles)
+}
+
+const fn prefab(char: &str) -> Prefab {
+    let bytes = char.as_bytes();
+    assert!(bytes.len() == 1, "Prefabs are single characters");
+    match Prefab::known(bytes[0]) {
+        Some(prefab) => prefab,
+        None => panic!("Unknown prefab character"),
+    }
+}
+
+/// Embeds a `.cgp` file in the binary as a `CyberGrindPattern`,
+/// failing to compile if the file doesn't parse. The path is
+/// relative to the current file, like `include_bytes!()`, and the
+/// file is read the same way as by `CyberGrindPattern::parse()`.
+/// ```
+/// use tinycbg::{include_cgp, CyberGrindPattern};
+///
+/// static RANGE: CyberGrindPattern = include_cgp!("../tests/patterns/valid/range.cgp");
+/// assert_eq!(RANGE[0].height(), -50);
+/// ```
+/// Invalid files don't compile:
+/// ```compile_fail
+/// let pattern = tinycbg::include_cgp!("../tests/patterns/invalid/duplicate_minus.cgp");
+/// ```
+#[macro_export]
+macro_rules! include_cgp {
+    ($path:expr $(,)?) => {{
+        const PATTERN: $crate::CyberGrindPattern = $crate::__include_cgp(include_bytes!($path));
+        PATTERN
+    }};
+}
+
+// Parses the file for `include_cgp!`, panicking
+// (and so failing to compile) if it's invalid.
+#[doc(hidden)]
+pub const fn __include_cgp(bytes: &[u8]) -> CyberGrindPattern {
+    match parse(bytes) {
+        Ok(pattern) => pattern,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+// A `const` version of `CyberGrindPattern::parse()`,
+// with less detailed errors.
+const fn parse(bytes: &[u8]) -> Result<CyberGrindPattern, &'static str> {
+    const EOF: &str = "Unexpected end of input";
+    let mut tiles = [Tile::with_height(0); 256];
+    let bom = BOM.len();
+    let mut pos = 0;
+    if bytes.len() >= bom && bytes[0] == BOM[0] && bytes[1] == BOM[1] && bytes[2] == BOM[2] {
+        pos = bom;
+    }
+
+    let mut index = 0;
+    while index < 256 {
+        if pos >= bytes.len() {
+            return Err(EOF);
+        }
+        let height = match bytes[pos] {
+            char @ b'0'..=b'9' => (char - b'0') as i32,
+            b'(' => {
+                let mut negative = false;
+                let mut height: i32 = 0;
+                pos += 1;
+                loop {
+                    if pos >= bytes.len() {
+                        return Err(EOF);
+                    }
+                    match bytes[pos] {
+                        b')' => break,
+                        b'-' if negative => return Err("Duplicate negative sign"),
+                        b'-' => negative = true,
+                        b'0' if height == 0 => return Err("Leading zero in parentheses"),
+                        char @ b'0'..=b'9' => {
+                            height = height
+                                .saturating_mul(10)
+                                .saturating_add((char - b'0') as i32)
+                        }
+                        _ => return Err("Invalid height character"),
+                    }
+                    pos += 1;
+                }
+                if negative {
+                    height = -height;
+                }
+                if height < -50 || height > 50 {
+                    return Err("Height must be between -50 and 50");
+                }
+                height
+            }
+            _ => return Err("Invalid height character"),
+        };
+        tiles[index].set_height(height as i8);
+        pos += 1;
+        index += 1;
+        if index % 16 == 0 {
+            pos = match newline(bytes, pos) {
+                Ok(pos) => pos,
+                Err(err) => return Err(err),
+            };
+        }
+    }
+
+    pos = match newline(bytes, pos) {
+        Ok(pos) => pos,
+        Err(err) => return Err(err),
+    };
+
+    index = 0;
+    while index < 256 {
+        if pos >= bytes.len() {
+            return Err(EOF);
+        }
+        match Prefab::known(bytes[pos]) {
+            Some(prefab) => tiles[index].set_prefab(prefab),
+            None => return Err("Invalid prefab character"),
+        }
+        pos += 1;
+        index += 1;
+        if index % 16 == 0 {
+            pos = match newline(bytes, pos) {
+                Ok(pos) => pos,
+                Err(err) => return Err(err),
+            };
+        }
+    }
+    Ok(CyberGrindPattern::from_array(tiles))
+}
+
+// Checks for a `\n` or `\r\n` at `pos`,
+// returning the position after it.
+const fn newline(bytes: &[u8], pos: usize) -> Result<usize, &'static str> {
+    if pos >= bytes.len() {
+        return Err("Unexpected end of input");
+    }
+    match bytes[pos] {
+        b'\n' => Ok(pos + 1),
+        b'\r' if pos + 1 < bytes.len() && bytes[pos + 1] == b'\n' => Ok(pos + 2),
+        _ => Err("Expected newline"),
+    }
+}
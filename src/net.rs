@@ -0,0 +1,254 @@
+//! Sharing pattern updates over TCP.
+//!
+//! Only plain TCP is supported, so browsers can't connect
+//! directly; a WebSocket bridge has to sit in between.
+//!
+//! A `PatternServer` broadcasts `Message`s to every
+//! connected `PatternClient`, so a live editor can push
+//! arena changes to any number of preview clients.
+//! ```no_run
+//! use tinycbg::net::{Message, PatternClient, PatternServer};
+//! use tinycbg::{CyberGrindPattern, Op, Tile};
+//!
+//! let mut server = PatternServer::bind("127.0.0.1:7878")?;
+//! let mut client = PatternClient::connect("127.0.0.1:7878")?;
+//!
+//! server.broadcast(&Message::Ops(vec![Op::Fill(Tile::with_height(4))]))?;
+//! client.recv()?; // The current pattern, sent when connecting
+//! client.recv()?; // The ops
+//! assert_eq!(client.pattern()[0].height(), 4);
+//! # Ok::<(), tinycbg::error::IoError>(())
+//! ```
+//!
+//! Every message is a tag byte, followed by the
+//! length of the payload as a little-endian `u16`,
+//! followed by the payload itself. Full patterns are
+//! sent as `.cgp` text, and ops as an op log.
+//! Ops which refer to tiles outside of the pattern
+//! are rejected instead of being applied.
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{error::IoError, CyberGrindPattern, Op, MAX_FILE_SIZE};
+
+const PATTERN_TAG: u8 = 0;
+const OPS_TAG: u8 = 1;
+
+/// How long a `PatternServer` waits to write to
+/// a client before disconnecting it, by default.
+pub const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An update sent from a server to its clients.
+#[derive(Clone, Debug)]
+pub enum Message {
+    /// Replaces the whole pattern
+    Pattern(Box<CyberGrindPattern>),
+    /// Ops to apply to the current pattern
+    Ops(Vec<Op>),
+}
+
+impl Message {
+    /// Returns whether the message can be applied,
+    /// which is false if any of its ops are invalid.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Message::Pattern(_) => true,
+            Message::Ops(ops) => ops.iter().all(Op::is_valid),
+        }
+    }
+
+    /// Applies the message to `pattern`. Panics if
+    /// the message isn't valid.
+    pub fn apply(&self, pattern: &mut CyberGrindPattern) {
+        match self {
+            Message::Pattern(new) => *pattern = **new,
+            Message::Ops(ops) => pattern.replay(ops),
+        }
+    }
+
+    /// Writes the message to `writer`.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), io::Error> {
+        let mut buf = [0; MAX_FILE_SIZE];
+        let (tag, payload) = match self {
+            Message::Pattern(pattern) => {
+                let len = pattern.write_into(&mut buf);
+                (PATTERN_TAG, &buf[..len])
+            }
+            Message::Ops(ops) => (OPS_TAG, &Op::write_log(ops).into_bytes()[..]),
+        };
+        let len = u16::try_from(payload.len())
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "Message is too long"))?;
+
+        writer.write_all(&[tag])?;
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(payload)?;
+        writer.flush()
+    }
+
+    /// Reads a message written by `Message::write_to()`.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Message, IoError> {
+        let mut header = [0; 3];
+        reader.read_exact(&mut header)?;
+        let mut payload = vec![0; u16::from_le_bytes([header[1], header[2]]) as usize];
+        reader.read_exact(&mut payload)?;
+
+        match header[0] {
+            PATTERN_TAG => Ok(Message::Pattern(Box::new(CyberGrindPattern::parse(
+                &payload,
+            )?))),
+            OPS_TAG => {
+                let log = String::from_utf8(payload)
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+                // `Op::parse_log()` rejects ops which can't be applied
+                let ops = Op::parse_log(&log)
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+                Ok(Message::Ops(ops))
+            }
+            tag => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Unknown message tag {tag}"),
+            )
+            .into()),
+        }
+    }
+}
+
+/// Broadcasts pattern updates to every
+/// connected `PatternClient`. Newly connected
+/// clients are sent the current pattern first.
+/// Clients which take longer than the write
+/// timeout to receive a message are disconnected,
+/// so one stalled client can't hold up the rest.
+pub struct PatternServer {
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+    pattern: CyberGrindPattern,
+    write_timeout: Duration,
+}
+
+impl PatternServer {
+    /// Starts listening for clients at `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, io::Error> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(PatternServer {
+            listener,
+            clients: Vec::new(),
+            pattern: CyberGrindPattern::new(),
+            write_timeout: DEFAULT_WRITE_TIMEOUT,
+        })
+    }
+
+    /// Sets how long to wait to write to a client before
+    /// disconnecting it. Defaults to `DEFAULT_WRITE_TIMEOUT`.
+    /// Returns an error if `timeout` is zero.
+    pub fn set_write_timeout(&mut self, timeout: Duration) -> Result<(), io::Error> {
+        if timeout.is_zero() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Timeout can't be zero",
+            ));
+        }
+        for client in &self.clients {
+            client.set_write_timeout(Some(timeout))?;
+        }
+        self.write_timeout = timeout;
+        Ok(())
+    }
+
+    /// Gets the address the server is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts every client waiting to connect and sends
+    /// them the current pattern. This is also done
+    /// before every broadcast. Returns the number of new clients.
+    pub fn accept_pending(&mut self) -> Result<usize, io::Error> {
+        let mut accepted = 0;
+        loop {
+            match self.listener.accept() {
+                Ok((mut stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    stream.set_nodelay(true)?;
+                    stream.set_write_timeout(Some(self.write_timeout))?;
+                    let current = Message::Pattern(Box::new(self.pattern));
+                    if current.write_to(&mut stream).is_ok() {
+                        self.clients.push(stream);
+                        accepted += 1;
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(accepted),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Applies `message` to the server's pattern and sends
+    /// it to every client. Clients which can't be written
+    /// to in time are disconnected. Returns an error without
+    /// sending anything if the message isn't valid.
+    pub fn broadcast(&mut self, message: &Message) -> Result<(), io::Error> {
+        if !message.is_valid() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Message refers to tiles outside of the pattern",
+            ));
+        }
+        self.accept_pending()?;
+        message.apply(&mut self.pattern);
+        self.clients
+            .retain_mut(|client| message.write_to(client).is_ok());
+        Ok(())
+    }
+
+    /// Gets the number of connected clients.
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Gets the pattern as the clients currently see it.
+    pub fn pattern(&self) -> &CyberGrindPattern {
+        &self.pattern
+    }
+}
+
+/// Receives pattern updates from a `PatternServer`
+/// and keeps a local copy of the pattern up to date.
+pub struct PatternClient {
+    stream: TcpStream,
+    pattern: CyberGrindPattern,
+}
+
+impl PatternClient {
+    /// Connects to a server at `addr`.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self, io::Error> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(PatternClient {
+            stream,
+            pattern: CyberGrindPattern::new(),
+        })
+    }
+
+    /// Waits for the next message from the server
+    /// and applies it to the local pattern. Messages
+    /// which can't be applied are returned as errors.
+    pub fn recv(&mut self) -> Result<Message, IoError> {
+        let message = Message::read_from(&mut self.stream)?;
+        if !message.is_valid() {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Invalid op").into());
+        }
+        message.apply(&mut self.pattern);
+        Ok(message)
+    }
+
+    /// Gets the local copy of the pattern.
+    pub fn pattern(&self) -> &CyberGrindPattern {
+        &self.pattern
+    }
+}
@@ -1,14 +1,313 @@
+#[cfg(feature = "std")]
+use crate::error::IoError;
 use crate::{
-    error::{IoError, ParseError, ParseErrorType},
+    error::{BufferTooSmall, ParseError, ParseErrorType},
+    lexer::{Lexer, Span, Token, TokenKind, BOM},
     CyberGrindPattern, Prefab, MAX_FILE_SIZE,
 };
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+#[cfg(feature = "std")]
 use std::{
-    fs::File,
-    io::{self, BufReader, BufWriter, Read, Write},
-    path::Path,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
+#[cfg(feature = "std")]
+/// Options for `CyberGrindPattern::write_to_path_with()`.
+/// ```
+/// use tinycbg::WritePolicy;
+///
+/// let policy = WritePolicy {
+///     backup: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WritePolicy {
+    /// Keep the previous file as `<path>.bak`
+    /// before overwriting it. Defaults to `false`
+    pub backup: bool,
+    /// How many backups to keep when `backup` is set.
+    /// Older backups are rotated to `<path>.bak.1`,
+    /// `<path>.bak.2` and so on. Defaults to 1
+    pub max_backups: usize,
+    /// Write the file with `CyberGrindPattern::write_to_path_atomic()`.
+    /// Defaults to `false`
+    pub atomic: bool,
+    /// Hold an exclusive advisory lock on `<path>.lock`
+    /// while backing up and writing, which readers using
+    /// `CyberGrindPattern::parse_path_locked()` wait for.
+    /// The lock file is left behind, since removing it
+    /// could let a waiting reader or writer lock a file
+    /// nobody else can see. Defaults to `false`
+    pub lock: bool,
+}
+
+#[cfg(feature = "std")]
+impl Default for WritePolicy {
+    fn default() -> Self {
+        WritePolicy {
+            backup: false,
+            max_backups: 1,
+            atomic: false,
+            lock: false,
+        }
+    }
+}
+
+/// Line endings used when writing a pattern.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// `\n`, which is what the game writes
+    #[default]
+    Lf,
+    /// `\r\n`, as used on Windows
+    CrLf,
+}
+
+impl LineEnding {
+    /// The line ending normally used on
+    /// the platform being compiled for.
+    #[cfg(windows)]
+    pub const NATIVE: LineEnding = LineEnding::CrLf;
+    /// The line ending normally used on
+    /// the platform being compiled for.
+    #[cfg(not(windows))]
+    pub const NATIVE: LineEnding = LineEnding::Lf;
+
+    /// Gets the bytes of the line ending.
+    pub fn as_bytes(self) -> &'static [u8] {
+        match self {
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+        }
+    }
+}
+
+/// Options for `CyberGrindPattern::write_with()`,
+/// controlling how the pattern is formatted.
+/// ```
+/// use tinycbg::{LineEnding, WriteOptions};
+///
+/// let options = WriteOptions {
+///     line_ending: LineEnding::CrLf,
+/// };
+/// ```
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Defaults to `LineEnding::Lf`
+    pub line_ending: LineEnding,
+}
+
+#[cfg(feature = "std")]
+// Opens `<path>.lock`, which readers and writers lock
+// in place of `path`, since atomic writes replace it.
+fn lock_file(path: &Path) -> Result<File, io::Error> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(PathBuf::from(name))
+}
+
+#[cfg(feature = "std")]
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    if n > 0 {
+        name.push(format!(".{n}"));
+    }
+    PathBuf::from(name)
+}
+
+/// What to do when parsing an unknown prefab character.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum UnknownPrefab {
+    /// Fail with `ParseErrorType::InvalidPrefab`
+    #[default]
+    Error,
+    /// Use the given prefab instead
+    Replace(Prefab),
+    /// Keep printable ASCII characters as `Prefab::Unknown`,
+    /// so they're written back out unchanged. Other
+    /// characters still fail with `ParseErrorType::InvalidPrefab`
+    Keep,
+}
+
+impl UnknownPrefab {
+    // Gets the prefab to use for the unknown
+    // character `byte`, if there is one.
+    pub(crate) fn resolve(self, byte: u8) -> Option<Prefab> {
+        match self {
+            UnknownPrefab::Error => None,
+            UnknownPrefab::Replace(prefab) => Some(prefab),
+            UnknownPrefab::Keep => Prefab::unknown(byte),
+        }
+    }
+}
+
+/// Options for `CyberGrindPattern::parse_with()`,
+/// controlling how strict parsing is.
+/// `ParseOptions::default()` parses exactly like
+/// `CyberGrindPattern::parse()`.
+/// ```
+/// use tinycbg::{ParseOptions, Prefab, UnknownPrefab};
+///
+/// let options = ParseOptions {
+///     unknown_prefab: UnknownPrefab::Replace(Prefab::None),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParseOptions {
+    /// Accept `\r\n` line endings as well as `\n`.
+    /// Defaults to `true`
+    pub allow_crlf: bool,
+    /// Accept heights in parentheses which start with
+    /// a zero, such as `(05)`. Defaults to `false`
+    pub allow_leading_zeros: bool,
+    /// What to do with unknown prefab characters.
+    /// Defaults to `UnknownPrefab::Error`
+    pub unknown_prefab: UnknownPrefab,
+    /// Ignore anything after the last line of prefabs,
+    /// instead of failing with `ParseErrorType::TrailingData`.
+    /// Defaults to `true`, but `CyberGrindPattern::parse_file()`,
+    /// `parse_reader()` and `parse_path()` read whole files,
+    /// so they always report trailing data
+    pub allow_trailing_data: bool,
+    /// Clamp heights outside of -50 to 50 into that range,
+    /// instead of failing with `ParseErrorType::InvalidHeightValue`.
+    /// Defaults to `false`
+    pub clamp_heights: bool,
+    /// Accept input which ends right after the last prefab,
+    /// without a newline. Defaults to `false`
+    pub allow_missing_final_newline: bool,
+    /// Largest file, in bytes, `CyberGrindPattern::parse_file_with()`
+    /// will read before failing with `IoError::TooLarge`.
+    /// Defaults to `DEFAULT_MAX_FILE_SIZE`
+    pub max_file_size: usize,
+}
+
+/// Default for `ParseOptions::max_file_size`, far
+/// bigger than any reasonable `.cgp` file.
+pub const DEFAULT_MAX_FILE_SIZE: usize = 64 * 1024;
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            allow_crlf: true,
+            allow_leading_zeros: false,
+            unknown_prefab: UnknownPrefab::Error,
+            allow_trailing_data: true,
+            clamp_heights: false,
+            allow_missing_final_newline: false,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+}
+
+impl ParseOptions {
+    // Used when reading whole files, where anything
+    // after the pattern is a mistake rather than the
+    // rest of a larger document.
+    #[cfg(feature = "std")]
+    fn whole_input() -> Self {
+        ParseOptions {
+            allow_trailing_data: false,
+            ..Default::default()
+        }
+    }
+
+    /// Options which reject anything
+    /// that isn't a canonical `.cgp` file.
+    pub fn strict() -> Self {
+        ParseOptions {
+            allow_crlf: false,
+            allow_trailing_data: false,
+            ..Default::default()
+        }
+    }
+
+    /// Options which accept as much as possible, for
+    /// opening patterns from anywhere. Heights are
+    /// clamped, unknown prefabs are replaced with
+    /// `Prefab::None` and the final newline is optional.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, ParseOptions, Prefab};
+    ///
+    /// let text = std::fs::read_to_string("tests/patterns/valid/range.cgp").unwrap();
+    /// let text = text.replacen("(-50)", "(-70)", 1).replacen('n', "?", 1);
+    /// let text = text.trim_end();
+    ///
+    /// assert!(CyberGrindPattern::parse_str(text).is_err());
+    /// let pattern = CyberGrindPattern::parse_with(text.as_bytes(), &ParseOptions::lenient()).unwrap();
+    /// assert_eq!(pattern[0].height(), -50);
+    /// assert_eq!(pattern[0].prefab(), Prefab::None);
+    /// ```
+    pub fn lenient() -> Self {
+        ParseOptions {
+            allow_crlf: true,
+            allow_leading_zeros: true,
+            unknown_prefab: UnknownPrefab::Replace(Prefab::None),
+            allow_trailing_data: true,
+            clamp_heights: true,
+            allow_missing_final_newline: true,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+}
+
+// Receives the tiles found while parsing.
+pub(crate) trait ParseSink {
+    fn height(&mut self, index: usize, height: i8, span: Span);
+    fn prefab(&mut self, index: usize, prefab: Prefab, span: Span);
+}
+
+impl ParseSink for CyberGrindPattern {
+    fn height(&mut self, index: usize, height: i8, _span: Span) {
+        self[index].set_height(height);
+    }
+    fn prefab(&mut self, index: usize, prefab: Prefab, _span: Span) {
+        self[index].set_prefab(prefab);
+    }
+}
+
+// Throws away every tile, for validation.
+struct Discard;
+
+impl ParseSink for Discard {
+    fn height(&mut self, _index: usize, _height: i8, _span: Span) {}
+    fn prefab(&mut self, _index: usize, _prefab: Prefab, _span: Span) {}
+}
+
+#[cfg(all(feature = "std", unix))]
+// Syncs the directory holding `path`, so a rename into it
+// is on disk before returning.
+fn sync_parent(path: &Path) -> Result<(), io::Error> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()
+}
+
+#[cfg(all(feature = "std", not(unix)))]
+// Directories can't be opened for syncing everywhere else.
+fn sync_parent(_path: &Path) -> Result<(), io::Error> {
+    Ok(())
+}
+
 impl CyberGrindPattern {
+    #[cfg(feature = "std")]
     /// Creates a new file at path `path`. If one already exists,
     /// it is truncated. Outputs a Cybergrind Pattern File to that
     /// path.
@@ -17,214 +316,509 @@ impl CyberGrindPattern {
         self.write(&mut file)
     }
 
-    /// Takes in a file and writes a Cybergrind Pattern to it.
-    pub fn write(&self, file: &mut File) -> Result<(), io::Error> {
-        let mut buf = Box::new([0; MAX_FILE_SIZE]);
-        let mut buf_idx = 0;
-        let mut tile_idx = 0;
-        for _row in 0..16 {
-            for _column in 0..16 {
-                let tile = self.tiles[tile_idx];
-                let height = tile.height();
-                // Actually sobbing right now
-                if (0..9).contains(&height) {
-                    buf[buf_idx] = (height + 48) as u8;
-                    buf_idx += 1;
-                } else {
-                    let dear_god_why = format!("({height})");
-                    for byte in dear_god_why.as_bytes() {
-                        buf[buf_idx] = *byte;
-                        buf_idx += 1;
-                    }
-                }
+    #[cfg(feature = "std")]
+    /// Writes a Cybergrind Pattern File to path `path`
+    /// without ever leaving a partially written file
+    /// behind. The pattern is written to a temporary file
+    /// in the same directory, which is then renamed over
+    /// `path`. On Unix, the directory is synced afterwards
+    /// so the rename survives a crash.
+    pub fn write_to_path_atomic<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?;
 
-                tile_idx += 1;
+        // Every write gets its own temporary file, so
+        // concurrent writes to `path` can't clobber
+        // each other's before renaming.
+        static NEXT_TMP: AtomicUsize = AtomicUsize::new(0);
+        let (tmp_path, mut file) = loop {
+            let mut tmp_name = OsString::from(".");
+            tmp_name.push(file_name);
+            tmp_name.push(format!(
+                ".{}.{}.tmp",
+                std::process::id(),
+                NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+            ));
+            let tmp_path = path.with_file_name(tmp_name);
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&tmp_path)
+            {
+                Ok(file) => break (tmp_path, file),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
             }
-            buf[buf_idx] = b'\n';
-            buf_idx += 1;
+        };
+
+        let res = self
+            .write(&mut file)
+            .and_then(|()| file.sync_all())
+            .and_then(|()| fs::rename(&tmp_path, path));
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp_path);
         }
+        res?;
+        sync_parent(path)
+    }
 
-        buf[buf_idx] = b'\n';
-        buf_idx += 1;
+    #[cfg(feature = "std")]
+    /// Writes a Cybergrind Pattern File to path `path`,
+    /// following `policy`. Backups are only made
+    /// if a file already exists at `path`.
+    pub fn write_to_path_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        policy: WritePolicy,
+    ) -> Result<(), io::Error> {
+        let path = path.as_ref();
+        // Locked before backing up, so another
+        // writer's partial file is never copied
+        let _lock = if policy.lock {
+            let file = lock_file(path)?;
+            file.lock()?;
+            Some(file)
+        } else {
+            None
+        };
 
-        tile_idx = 0;
-        for _row in 0..16 {
-            for _column in 0..16 {
-                let tile = self.tiles[tile_idx];
-                let prefab = tile.prefab();
+        if policy.backup && policy.max_backups > 0 && path.is_file() {
+            let oldest = backup_path(path, policy.max_backups - 1);
+            if oldest.exists() {
+                fs::remove_file(oldest)?;
+            }
+            for n in (0..policy.max_backups - 1).rev() {
+                let backup = backup_path(path, n);
+                if backup.exists() {
+                    fs::rename(backup, backup_path(path, n + 1))?;
+                }
+            }
+            fs::copy(path, backup_path(path, 0))?;
+        }
 
-                buf[buf_idx] = prefab.byte();
-                buf_idx += 1;
+        if policy.atomic {
+            self.write_to_path_atomic(path)
+        } else {
+            self.write_to_path(path)
+        }
+    }
 
-                tile_idx += 1;
+    #[cfg(feature = "std")]
+    /// Writes a Cybergrind Pattern to `writer`, which can be
+    /// a file, a socket, a `Vec<u8>` or anything else
+    /// implementing `Write`. The pattern is written with a
+    /// single call to `write_all()`, so there's no need to
+    /// wrap `writer` in a `BufWriter`.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut out = Vec::new();
+    /// CyberGrindPattern::new().write(&mut out).unwrap();
+    /// assert!(out.starts_with(b"0000000000000000\n"));
+    /// ```
+    pub fn write<W: Write + ?Sized>(&self, writer: &mut W) -> Result<(), io::Error> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    /// Serializes the pattern into the contents
+    /// of a Cybergrind Pattern File.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let bytes = CyberGrindPattern::new().to_bytes();
+    /// assert_eq!(bytes.len(), 16 * 17 + 1 + 16 * 17);
+    /// assert!(CyberGrindPattern::parse(&bytes).is_ok());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to_vec(&mut bytes);
+        bytes
+    }
+
+    /// Serializes the pattern onto the end of `out`,
+    /// leaving what was already in it untouched.
+    pub fn write_to_vec(&self, out: &mut Vec<u8>) {
+        self.write_to_vec_with(out, &WriteOptions::default());
+    }
+
+    #[cfg(feature = "std")]
+    /// Same as `CyberGrindPattern::write()`, following `options`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, LineEnding, WriteOptions};
+    ///
+    /// let options = WriteOptions {
+    ///     line_ending: LineEnding::CrLf,
+    /// };
+    /// let mut out = Vec::new();
+    /// CyberGrindPattern::new().write_with(&mut out, &options).unwrap();
+    /// assert!(out.starts_with(b"0000000000000000\r\n"));
+    /// assert!(CyberGrindPattern::parse(&out).is_ok());
+    /// ```
+    pub fn write_with<W: Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), io::Error> {
+        writer.write_all(&self.to_bytes_with(options))
+    }
+
+    /// Same as `CyberGrindPattern::to_bytes()`, following `options`.
+    pub fn to_bytes_with(&self, options: &WriteOptions) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to_vec_with(&mut bytes, options);
+        bytes
+    }
+
+    /// Same as `CyberGrindPattern::write_to_vec()`, following `options`.
+    pub fn write_to_vec_with(&self, out: &mut Vec<u8>, options: &WriteOptions) {
+        let mut buf = [0; MAX_FILE_SIZE];
+        let len = self.write_into(&mut buf);
+        match options.line_ending {
+            LineEnding::Lf => out.extend_from_slice(&buf[..len]),
+            LineEnding::CrLf => {
+                for line in buf[..len].split_inclusive(|&byte| byte == b'\n') {
+                    out.extend_from_slice(&line[..line.len() - 1]);
+                    out.extend_from_slice(b"\r\n");
+                }
             }
-            buf[buf_idx] = b'\n';
-            buf_idx += 1;
         }
+    }
 
-        let mut writer = BufWriter::new(file);
+    /// Gets the number of bytes the pattern takes up
+    /// as a Cybergrind Pattern File, which is at most
+    /// `MAX_FILE_SIZE`.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let pattern = CyberGrindPattern::new();
+    /// assert_eq!(pattern.serialized_len(), pattern.to_bytes().len());
+    /// ```
+    pub fn serialized_len(&self) -> usize {
+        let heights: usize = self
+            .tiles
+            .iter()
+            .map(|tile| match tile.height() {
+                0..10 => 1,
+                -9..0 | 10.. => 4,
+                _ => 5,
+            })
+            .sum();
+        heights + 16 + 1 + 16 * 17
+    }
 
-        writer.write_all(&buf[..buf_idx])
+    /// Serializes the pattern into the start of `buf` without
+    /// allocating, returning the number of bytes written.
+    /// Fails if `buf` is shorter than
+    /// `CyberGrindPattern::serialized_len()`, leaving it
+    /// untouched. A buffer of `MAX_FILE_SIZE` bytes always fits.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, MAX_FILE_SIZE};
+    ///
+    /// let mut buf = [0; MAX_FILE_SIZE];
+    /// let len = CyberGrindPattern::new().write_to_buf(&mut buf).unwrap();
+    /// assert!(buf[..len].starts_with(b"0000000000000000\n"));
+    /// assert!(CyberGrindPattern::new().write_to_buf(&mut buf[..10]).is_err());
+    /// ```
+    pub fn write_to_buf(&self, buf: &mut [u8]) -> Result<usize, BufferTooSmall> {
+        let needed = self.serialized_len();
+        if buf.len() < needed {
+            return Err(BufferTooSmall {
+                needed,
+                len: buf.len(),
+            });
+        }
+        Ok(self.write_into(buf))
     }
 
-    fn check_for_newline(line: u32, column: u32, byte: u8) -> Result<(), ParseError> {
-        if byte != b'\n' {
-            Err(ParseError {
-                line,
-                column,
-                char: byte,
-                kind: ParseErrorType::ExpectedNewline,
-            })
-        } else {
-            Ok(())
+    // Serializes the pattern into `buf`, which must be big
+    // enough, returning the number of bytes written.
+    pub(crate) fn write_into(&self, buf: &mut [u8]) -> usize {
+        let mut len = 0;
+        let mut push = |byte: u8| {
+            buf[len] = byte;
+            len += 1;
+        };
+        for row in self.tiles.chunks(16) {
+            for tile in row {
+                let height = tile.height();
+                // Actually sobbing right now
+                if (0..10).contains(&height) {
+                    push(b'0' + height as u8);
+                    continue;
+                }
+                push(b'(');
+                if height < 0 {
+                    push(b'-');
+                }
+                let abs = height.unsigned_abs();
+                if abs >= 10 {
+                    push(b'0' + abs / 10);
+                }
+                push(b'0' + abs % 10);
+                push(b')');
+            }
+            push(b'\n');
+        }
+        push(b'\n');
+        for row in self.tiles.chunks(16) {
+            for tile in row {
+                push(tile.prefab().byte());
+            }
+            push(b'\n');
         }
+        len
+    }
+
+    /// Takes in a series of bytes and tries
+    /// to turn them into a Cybergrind Pattern.
+    pub fn parse(bytes: &[u8]) -> Result<CyberGrindPattern, ParseError> {
+        Self::parse_with(bytes, &ParseOptions::default())
     }
 
-    // Returns:
-    // - height
-    // - new column idx
-    // - increased buf_idx
-    fn parse_parentheses(
+    /// Takes in a series of bytes and tries to turn
+    /// them into a Cybergrind Pattern, following `options`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, ParseOptions};
+    ///
+    /// let text = std::fs::read_to_string("tests/patterns/valid/range.cgp").unwrap();
+    /// let windows_text = text.replace('\n', "\r\n");
+    ///
+    /// assert!(CyberGrindPattern::parse_str(&windows_text).is_ok());
+    /// assert!(CyberGrindPattern::parse_with(windows_text.as_bytes(), &ParseOptions::strict()).is_err());
+    /// ```
+    pub fn parse_with(
         bytes: &[u8],
-        buf_idx: usize,
-        line: u32,
-        column: u32,
-    ) -> Result<(i8, u32, usize), ParseError> {
-        let mut is_negative = false;
-        let mut height: i8 = 0;
-        let mut buf_idx = buf_idx;
-
-        let mut column = column + 1;
-        buf_idx += 1;
-        let mut char = bytes[buf_idx];
-
-        while char != b')' {
-            if char == b'-' {
-                if is_negative {
-                    return Err(ParseError {
-                        line,
-                        column,
-                        char,
-                        kind: ParseErrorType::DuplicateNegative,
-                    });
-                } else {
-                    is_negative = true;
-                }
-            } else {
-                if !(48..=57).contains(&char) {
-                    return Err(ParseError {
-                        line,
-                        column,
-                        char,
-                        kind: ParseErrorType::InvalidHeightChar,
-                    });
+        options: &ParseOptions,
+    ) -> Result<CyberGrindPattern, ParseError> {
+        let mut pattern = CyberGrindPattern::new();
+        Self::parse_into(bytes, &mut pattern, options)?;
+        Ok(pattern)
+    }
+
+    /// Parses a Cybergrind Pattern from the start of a
+    /// series of bytes, returning it along with the
+    /// number of bytes it took up. Anything after the
+    /// pattern is ignored, so patterns embedded in
+    /// larger documents can be parsed in place.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    /// let len = bytes.len();
+    /// bytes.extend_from_slice(b"more stuff");
+    ///
+    /// let (pattern, consumed) = CyberGrindPattern::parse_prefix(&bytes).unwrap();
+    /// assert_eq!(consumed, len);
+    /// assert_eq!(&bytes[consumed..], b"more stuff");
+    /// ```
+    pub fn parse_prefix(bytes: &[u8]) -> Result<(CyberGrindPattern, usize), ParseError> {
+        Self::parse_prefix_with(bytes, &ParseOptions::default())
+    }
+
+    /// Same as `CyberGrindPattern::parse_prefix()`, following `options`.
+    /// `ParseOptions::allow_trailing_data` is ignored.
+    pub fn parse_prefix_with(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(CyberGrindPattern, usize), ParseError> {
+        let options = ParseOptions {
+            allow_trailing_data: true,
+            ..*options
+        };
+        let mut pattern = CyberGrindPattern::new();
+        let consumed = Self::parse_into(bytes, &mut pattern, &options)?;
+        Ok((pattern, consumed))
+    }
+
+    /// Checks whether a series of bytes is a valid
+    /// Cybergrind Pattern without building one, returning
+    /// the first error found. Accepts exactly the same
+    /// input as `CyberGrindPattern::parse()`.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    /// use tinycbg::error::ParseErrorType;
+    ///
+    /// let mut bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    /// assert!(CyberGrindPattern::validate_bytes(&bytes).is_ok());
+    ///
+    /// bytes[1] = b'x';
+    /// let err = CyberGrindPattern::validate_bytes(&bytes).unwrap_err();
+    /// assert_eq!(err.kind, ParseErrorType::InvalidHeightChar);
+    /// ```
+    pub fn validate_bytes(bytes: &[u8]) -> Result<(), ParseError> {
+        Self::parse_into(bytes, &mut Discard, &ParseOptions::default())?;
+        Ok(())
+    }
+
+    /// Parses a series of bytes like `CyberGrindPattern::parse()`,
+    /// but keeps going after an error to find every error in
+    /// the input. Parsing starts over at the next line after
+    /// each error, so there's at most one error per line.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    /// use tinycbg::error::ParseErrorType;
+    ///
+    /// let text = std::fs::read_to_string("tests/patterns/valid/range.cgp").unwrap();
+    /// let text = text.replacen("(-50)", "(-70)", 1).replacen('J', "?", 1);
+    ///
+    /// let errors = CyberGrindPattern::parse_all_errors(text.as_bytes()).unwrap_err();
+    /// assert_eq!(errors[0].kind, ParseErrorType::InvalidHeightValue);
+    /// assert_eq!(errors[1].kind, ParseErrorType::InvalidPrefab);
+    /// ```
+    pub fn parse_all_errors(bytes: &[u8]) -> Result<CyberGrindPattern, Vec<ParseError>> {
+        Self::parse_all_errors_with(bytes, &ParseOptions::default())
+    }
+
+    /// Same as `CyberGrindPattern::parse_all_errors()`,
+    /// following `options`.
+    pub fn parse_all_errors_with(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<CyberGrindPattern, Vec<ParseError>> {
+        let mut pattern = CyberGrindPattern::new();
+        let mut errors = Vec::new();
+        let mut pos = if bytes.starts_with(BOM) { BOM.len() } else { 0 };
+
+        // Heights, the empty line and prefabs
+        for row in 0..33 {
+            let end = line_end(bytes, pos);
+            let res = Self::parse_line(&bytes[..end], pos, row, &mut pattern, options);
+            pos = end;
+            if let Err(err) = res {
+                let eof = err.kind == ParseErrorType::UnexpectedEof;
+                errors.push(err);
+                if eof {
+                    return Err(errors);
                 }
+            }
+        }
+
+        if !options.allow_trailing_data && pos < bytes.len() {
+            errors.push(ParseError {
+                line: 34,
+                column: 1,
+                char: bytes[pos],
+                kind: ParseErrorType::TrailingData,
+            });
+        }
 
-                if char == 48 && height == 0 {
+        if errors.is_empty() {
+            Ok(pattern)
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Parses line `row` of a pattern, which starts at `start`
+    // and runs to the end of `bytes`, handing every value found
+    // to `sink`. Rows 0 to 15 are heights, row 16 is the empty
+    // line and the rest are prefabs. Every parser is built on
+    // this, so they all accept exactly the same input.
+    pub(crate) fn parse_line<S: ParseSink>(
+        bytes: &[u8],
+        start: usize,
+        row: usize,
+        sink: &mut S,
+        options: &ParseOptions,
+    ) -> Result<(), ParseError> {
+        let in_prefabs = row > 16;
+        let mut lexer = Lexer::line(bytes, start, *options, row as u32 + 1, in_prefabs);
+        if row == 16 {
+            return Self::expect_newline(&mut lexer);
+        }
+
+        let first = if in_prefabs {
+            (row - 17) * 16
+        } else {
+            row * 16
+        };
+        for index in first..first + 16 {
+            let token = Self::expect_token(&mut lexer)?;
+            match (token.kind, in_prefabs) {
+                (TokenKind::Height(height) | TokenKind::ParenHeight(height), false) => {
+                    sink.height(index, height, token.span)
+                }
+                (TokenKind::Prefab(prefab), true) => sink.prefab(index, prefab, token.span),
+                _ => {
                     return Err(ParseError {
-                        line,
-                        column,
-                        char,
-                        kind: ParseErrorType::LeadingZero,
+                        line: token.span.line,
+                        column: token.span.column,
+                        char: bytes[token.span.start],
+                        kind: if in_prefabs {
+                            ParseErrorType::InvalidPrefab
+                        } else {
+                            ParseErrorType::InvalidHeightChar
+                        },
                     });
                 }
-
-                height *= 10;
-                height += char as i8 - 48;
             }
-
-            column += 1;
-            buf_idx += 1;
-            char = bytes[buf_idx];
         }
 
-        if is_negative {
-            height *= -1;
+        if row == 32 && options.allow_missing_final_newline && lexer.peek().is_none() {
+            return Ok(());
         }
+        Self::expect_newline(&mut lexer)
+    }
 
-        if !(-50..=50).contains(&height) {
-            return Err(ParseError {
+    // Gets the next token, which the parser
+    // knows has to be there.
+    fn expect_token(lexer: &mut Lexer) -> Result<Token, ParseError> {
+        let (line, column) = lexer.line_column();
+        lexer.next().unwrap_or(Err(ParseError {
+            line,
+            column,
+            char: 0,
+            kind: ParseErrorType::UnexpectedEof,
+        }))
+    }
+
+    // Checks that the next token is a newline.
+    fn expect_newline(lexer: &mut Lexer) -> Result<(), ParseError> {
+        let (line, column) = lexer.line_column();
+        let char = lexer.peek();
+        match lexer.next() {
+            Some(Ok(Token {
+                kind: TokenKind::Newline,
+                ..
+            })) => Ok(()),
+            _ => Err(ParseError {
                 line,
                 column,
-                char,
-                kind: ParseErrorType::InvalidHeightValue,
-            });
+                char: char.unwrap_or(0),
+                kind: match char {
+                    Some(_) => ParseErrorType::ExpectedNewline,
+                    None => ParseErrorType::UnexpectedEof,
+                },
+            }),
         }
-
-        Ok((height, column, buf_idx))
     }
 
-    /// Takes in a series of bytes and tries
-    /// to turn them into a Cybergrind Pattern.
-    pub fn parse(bytes: &[u8]) -> Result<CyberGrindPattern, ParseError> {
-        let mut pattern = CyberGrindPattern::new();
-        let mut pat_idx = 0;
-        let mut buf_idx = 0;
-        let mut line = 1;
-        let mut char;
-
-        for _row in 0..16 {
-            let mut column = 1;
-            for _column in 0..16 {
-                char = bytes[buf_idx];
-                if char == b'(' {
-                    let height;
-                    (height, column, buf_idx) =
-                        Self::parse_parentheses(bytes, buf_idx, line, column)?;
-
-                    pattern[pat_idx].set_height(height);
-                } else {
-                    if !(48..=57).contains(&char) {
-                        return Err(ParseError {
-                            line,
-                            column,
-                            char,
-                            kind: ParseErrorType::InvalidHeightChar,
-                        });
-                    }
-                    pattern[pat_idx].set_height(char as i8 - 48);
-                }
-
-                column += 1;
-                pat_idx += 1;
-                buf_idx += 1;
-            }
+    // Parses a pattern, handing every value found to `sink`.
+    // Returns the number of bytes consumed.
+    pub(crate) fn parse_into<S: ParseSink>(
+        bytes: &[u8],
+        sink: &mut S,
+        options: &ParseOptions,
+    ) -> Result<usize, ParseError> {
+        let mut pos = if bytes.starts_with(BOM) { BOM.len() } else { 0 };
+        for row in 0..33 {
+            let end = line_end(bytes, pos);
+            Self::parse_line(&bytes[..end], pos, row, sink, options)?;
+            pos = end;
+        }
 
-            Self::check_for_newline(line, column, bytes[buf_idx])?;
-            buf_idx += 1;
-            line += 1;
-        }
-
-        Self::check_for_newline(line, 1, bytes[buf_idx])?;
-
-        buf_idx += 1;
-        line += 1;
-        pat_idx = 0;
-
-        for _row in 0..16 {
-            for column in 1..17 {
-                char = bytes[buf_idx];
-                let prefab = match Prefab::try_from(char) {
-                    Ok(prefab) => prefab,
-                    Err(kind) => {
-                        return Err(ParseError {
-                            line,
-                            column,
-                            char,
-                            kind,
-                        })
-                    }
-                };
-                pattern[pat_idx].set_prefab(prefab);
-                pat_idx += 1;
-                buf_idx += 1;
-            }
-            Self::check_for_newline(line, 17, bytes[buf_idx])?;
-            buf_idx += 1;
-            line += 1;
+        if !options.allow_trailing_data && pos < bytes.len() {
+            return Err(ParseError {
+                line: 34,
+                column: 1,
+                char: bytes[pos],
+                kind: ParseErrorType::TrailingData,
+            });
         }
 
-        Ok(pattern)
+        Ok(pos)
     }
 
     /// Takes in a string and tries to turn it into
@@ -233,19 +827,338 @@ impl CyberGrindPattern {
         Self::parse(string.as_bytes())
     }
 
+    #[cfg(feature = "std")]
     /// Takes in a string and tries to read
-    /// it as a Cybergrind Pattern.
+    /// it as a Cybergrind Pattern. Anything after
+    /// the pattern fails with `ParseErrorType::TrailingData`.
     pub fn parse_file(file: &mut File) -> Result<CyberGrindPattern, IoError> {
-        let mut buf = Box::new([0; MAX_FILE_SIZE]);
-        let mut reader = BufReader::new(file);
-        let bytes_read = reader.read(buf.as_mut())?;
-        Ok(Self::parse(&buf[..bytes_read])?)
+        Self::parse_file_with(file, &ParseOptions::whole_input())
     }
 
-    /// Tries to open a file at path `path` and reads
-    /// it as a Cybergrind Pattern.
+    #[cfg(feature = "std")]
+    /// Reads the whole of `file` and parses it as a Cybergrind
+    /// Pattern, following `options`. Fails with `IoError::TooLarge`
+    /// if the file is bigger than `ParseOptions::max_file_size`.
+    pub fn parse_file_with(
+        file: &mut File,
+        options: &ParseOptions,
+    ) -> Result<CyberGrindPattern, IoError> {
+        Self::parse_reader_with(&mut BufReader::new(file), options)
+    }
+
+    #[cfg(feature = "std")]
+    /// Reads `reader` to the end and parses what it
+    /// read as a Cybergrind Pattern. Works with sockets,
+    /// archive entries, in-memory cursors or anything
+    /// else implementing `Read`. Like `parse_file()`,
+    /// anything after the pattern is an error.
+    /// ```
+    /// use std::io::Cursor;
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let text = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    /// let pattern = CyberGrindPattern::parse_reader(&mut Cursor::new(text)).unwrap();
+    /// assert_eq!(pattern[(15, 0)].height(), -35);
+    /// ```
+    pub fn parse_reader<R: Read + ?Sized>(reader: &mut R) -> Result<CyberGrindPattern, IoError> {
+        Self::parse_reader_with(reader, &ParseOptions::whole_input())
+    }
+
+    #[cfg(feature = "std")]
+    /// Same as `CyberGrindPattern::parse_reader()`, following
+    /// `options`. Stops reading and fails with `IoError::TooLarge`
+    /// once more than `ParseOptions::max_file_size` bytes are read.
+    pub fn parse_reader_with<R: Read + ?Sized>(
+        reader: &mut R,
+        options: &ParseOptions,
+    ) -> Result<CyberGrindPattern, IoError> {
+        let limit = options.max_file_size;
+        let mut buf = Vec::new();
+        reader.take(limit as u64 + 1).read_to_end(&mut buf)?;
+        if buf.len() > limit {
+            return Err(IoError::TooLarge { limit });
+        }
+        Ok(Self::parse_with(&buf, options)?)
+    }
+
+    #[cfg(feature = "std")]
+    /// Tries to open a file at path `path` and reads it as
+    /// a Cybergrind Pattern, like `CyberGrindPattern::parse_file()`.
     pub fn parse_path<P: AsRef<Path>>(path: P) -> Result<CyberGrindPattern, IoError> {
         let mut file = File::open(path)?;
         Self::parse_file(&mut file)
     }
+
+    #[cfg(feature = "std")]
+    /// Tries to open a file at path `path` and reads it
+    /// as a Cybergrind Pattern, following `options`.
+    pub fn parse_path_with<P: AsRef<Path>>(
+        path: P,
+        options: &ParseOptions,
+    ) -> Result<CyberGrindPattern, IoError> {
+        let mut file = File::open(path)?;
+        Self::parse_file_with(&mut file, options)
+    }
+
+    #[cfg(feature = "std")]
+    /// Tries to open a file at path `path` and reads
+    /// it as a Cybergrind Pattern, while holding a shared
+    /// advisory lock on `<path>.lock`, which is created if
+    /// needed. Waits for writers using `write_to_path_with()`
+    /// with `WritePolicy::lock` set to finish first.
+    pub fn parse_path_locked<P: AsRef<Path>>(path: P) -> Result<CyberGrindPattern, IoError> {
+        let path = path.as_ref();
+        let lock = lock_file(path)?;
+        lock.lock_shared()?;
+        let mut file = File::open(path)?;
+        Self::parse_file(&mut file)
+    }
+
+    /// Tries to open a file at path `path` and reads it
+    /// as a Cybergrind Pattern by memory-mapping it,
+    /// avoiding a copy of the file's contents.
+    ///
+    /// The file must not be modified by another
+    /// process while it is being parsed.
+    #[cfg(feature = "memmap")]
+    pub fn parse_path_mmap<P: AsRef<Path>>(path: P) -> Result<CyberGrindPattern, IoError> {
+        let file = File::open(path)?;
+        // SAFETY: The mapping is only read from while parsing
+        // and is unmapped before returning. Changing the file
+        // mid-parse is documented as unsupported.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self::parse_with(&map, &ParseOptions::whole_input())?)
+    }
+}
+
+// Gets the end of the line starting at `pos`,
+// just past its newline.
+pub(crate) fn line_end(bytes: &[u8], pos: usize) -> usize {
+    bytes[pos..]
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map_or(bytes.len(), |i| pos + i + 1)
+}
+
+/// Formats the pattern as the contents of a Cybergrind
+/// Pattern File, exactly like `CyberGrindPattern::write()`.
+/// ```
+/// use tinycbg::CyberGrindPattern;
+///
+/// let pattern = CyberGrindPattern::new();
+/// assert_eq!(format!("{pattern}").into_bytes(), pattern.to_bytes());
+/// ```
+impl Display for CyberGrindPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buf = [0; MAX_FILE_SIZE];
+        let len = self.write_into(&mut buf);
+        let text = core::str::from_utf8(&buf[..len]).expect("Patterns are written as ASCII");
+        f.write_str(text)
+    }
+}
+
+/// Same as `CyberGrindPattern::parse_str()`.
+/// ```
+/// use tinycbg::CyberGrindPattern;
+///
+/// let text = std::fs::read_to_string("tests/patterns/valid/range.cgp").unwrap();
+/// let pattern: CyberGrindPattern = text.parse().unwrap();
+/// assert_eq!(pattern[0].height(), -50);
+/// ```
+impl FromStr for CyberGrindPattern {
+    type Err = ParseError;
+    fn from_str(string: &str) -> Result<Self, ParseError> {
+        Self::parse_str(string)
+    }
+}
+
+impl TryFrom<&str> for CyberGrindPattern {
+    type Error = ParseError;
+    fn try_from(string: &str) -> Result<Self, ParseError> {
+        Self::parse_str(string)
+    }
+}
+
+/// Result of feeding bytes to a `Parser`.
+#[derive(Clone, Debug)]
+pub enum ParseStatus {
+    /// The pattern isn't finished yet,
+    /// and more bytes are needed
+    Incomplete,
+    /// The pattern is finished. Only the first
+    /// `consumed` bytes of the last chunk were used.
+    Complete {
+        pattern: Box<CyberGrindPattern>,
+        consumed: usize,
+    },
+}
+
+/// An incremental parser, which is fed bytes in chunks
+/// of any size and produces a pattern once enough bytes
+/// have been fed. Accepts exactly the same input as
+/// `CyberGrindPattern::parse_prefix_with()`, without
+/// needing the whole input at once. Bytes are kept
+/// until their line is finished, then parsed by the
+/// same code as `CyberGrindPattern::parse()`.
+/// ```
+/// use tinycbg::{ParseStatus, Parser};
+///
+/// let bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+/// let mut parser = Parser::new();
+///
+/// for chunk in bytes.chunks(100) {
+///     if let ParseStatus::Complete { pattern, .. } = parser.feed(chunk).unwrap() {
+///         assert_eq!(pattern[0].height(), -50);
+///     }
+/// }
+/// ```
+/// After a pattern is completed the parser starts
+/// over, so a stream of patterns can be parsed by
+/// feeding it the unconsumed bytes.
+#[derive(Clone, Debug)]
+pub struct Parser {
+    options: ParseOptions,
+    pattern: CyberGrindPattern,
+    // Row of the pattern being parsed,
+    // counting the empty line
+    row: usize,
+    // Bytes fed so far of the row being parsed
+    line: Vec<u8>,
+    error: Option<ParseError>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser::with_options(ParseOptions::default())
+    }
+}
+
+impl Parser {
+    /// Creates a parser using `ParseOptions::default()`.
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    /// Creates a parser following `options`.
+    pub fn with_options(options: ParseOptions) -> Self {
+        Parser {
+            options,
+            pattern: CyberGrindPattern::new(),
+            row: 0,
+            line: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Throws away everything fed so far,
+    /// including any error.
+    pub fn reset(&mut self) {
+        *self = Parser::with_options(self.options);
+    }
+
+    /// Returns whether bytes have been fed since
+    /// the last pattern was completed.
+    pub fn in_progress(&self) -> bool {
+        self.row != 0 || !self.line.is_empty()
+    }
+
+    /// Feeds the next chunk of bytes to the parser.
+    /// Once an error is returned, every later call
+    /// returns the same error until `Parser::reset()`.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<ParseStatus, ParseError> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+
+        let mut consumed = 0;
+        while consumed < bytes.len() {
+            let end = line_end(bytes, consumed);
+            self.line.extend_from_slice(&bytes[consumed..end]);
+            consumed = end;
+
+            if self.line.last() != Some(&b'\n') {
+                // Errors are reported as soon as they're fed,
+                // rather than once the line is finished
+                self.check_partial_line()?;
+                break;
+            }
+
+            // The final newline can only be missing at the end of the input
+            let options = ParseOptions {
+                allow_missing_final_newline: false,
+                ..self.options
+            };
+            self.parse_line(&options)?;
+            self.line.clear();
+            self.row += 1;
+            if self.row == 33 {
+                let pattern = Box::new(self.pattern);
+                self.reset();
+                return Ok(ParseStatus::Complete { pattern, consumed });
+            }
+        }
+        Ok(ParseStatus::Incomplete)
+    }
+
+    /// Tells the parser the input has ended, getting the
+    /// pattern if the only thing missing is the final newline
+    /// and `ParseOptions::allow_missing_final_newline` is set.
+    /// Fails with `ParseErrorType::UnexpectedEof` otherwise.
+    /// The parser starts over either way.
+    pub fn finish(&mut self) -> Result<Box<CyberGrindPattern>, ParseError> {
+        let options = self.options;
+        let res = match self.error.take() {
+            Some(err) => Err(err),
+            None => self.parse_line(&options).map(|()| Box::new(self.pattern)),
+        };
+        self.reset();
+        res
+    }
+
+    // Parses the bytes fed of the current row,
+    // keeping any error found.
+    fn parse_line(&mut self, options: &ParseOptions) -> Result<(), ParseError> {
+        let start = if self.row == 0 && self.line.starts_with(BOM) {
+            BOM.len()
+        } else {
+            0
+        };
+        let res =
+            CyberGrindPattern::parse_line(&self.line, start, self.row, &mut self.pattern, options);
+        if let Err(err) = &res {
+            self.error = Some(err.clone());
+        }
+        res
+    }
+
+    // Checks the start of a row for errors, which can't
+    // be fixed by anything fed after it. Running out of
+    // input is fine, since the rest of the row is to come.
+    fn check_partial_line(&mut self) -> Result<(), ParseError> {
+        let mut line = &self.line[..];
+        // Might be the start of a byte order mark or a CRLF
+        if self.row == 0 && BOM.starts_with(line) {
+            return Ok(());
+        }
+        if self.options.allow_crlf {
+            line = line.strip_suffix(b"\r").unwrap_or(line);
+        }
+
+        let start = if self.row == 0 && line.starts_with(BOM) {
+            BOM.len()
+        } else {
+            0
+        };
+        let options = ParseOptions {
+            allow_missing_final_newline: false,
+            ..self.options
+        };
+        match CyberGrindPattern::parse_line(line, start, self.row, &mut Discard, &options) {
+            Err(err) if err.kind != ParseErrorType::UnexpectedEof => {
+                self.error = Some(err.clone());
+                Err(err)
+            }
+            _ => Ok(()),
+        }
+    }
 }
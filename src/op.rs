@@ -0,0 +1,353 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{Display, Formatter, Result as FmtRes},
+    str::FromStr,
+};
+
+use crate::{error::OpParseError, CyberGrindPattern, Prefab, Rect, Selection, SubPattern, Tile};
+
+/// A single edit made to a Cybergrind pattern.
+/// Ops can be recorded with `CyberGrindPattern::record()`
+/// and applied again with `CyberGrindPattern::replay()`.
+///
+/// Every op can be written as a single line of
+/// text and parsed back, so op logs can be saved
+/// to disk or sent over the network.
+///
+/// Edits which copy tiles from somewhere else, like
+/// `CyberGrindPattern::blit()`, don't have ops of their
+/// own. A `Recorder` records them as the `Op::SetTile`s
+/// they amount to, so every op stays small and `Copy`.
+/// ```
+/// use tinycbg::{Op, Prefab, Tile};
+///
+/// let op = Op::SetTile { index: 17, tile: Tile::new(-4, Prefab::Melee) };
+/// assert_eq!(op.to_string(), "set 17 -4 n");
+/// assert_eq!("set 17 -4 n".parse(), Ok(op));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    /// Replaces the tile at `index`
+    SetTile { index: usize, tile: Tile },
+    /// Sets the height of the tile at `index`
+    SetHeight { index: usize, height: i8 },
+    /// Sets the prefab of the tile at `index`
+    SetPrefab { index: usize, prefab: Prefab },
+    /// Same as `CyberGrindPattern::copy_tile_to_row()`
+    CopyTileToRow { tile: Tile, row: usize },
+    /// Same as `CyberGrindPattern::copy_tile_to_column()`
+    CopyTileToColumn { tile: Tile, column: usize },
+    /// Sets every tile in the pattern to `tile`
+    Fill(Tile),
+    /// Same as `CyberGrindPattern::fill_selection()`
+    FillSelection { selection: Selection, tile: Tile },
+}
+
+impl Op {
+    /// Applies the op to `pattern`. Panics if
+    /// the op refers to a tile outside of the pattern.
+    pub fn apply(&self, pattern: &mut CyberGrindPattern) {
+        match *self {
+            Op::SetTile { index, tile } => pattern[index] = tile,
+            Op::SetHeight { index, height } => pattern[index].set_height(height),
+            Op::SetPrefab { index, prefab } => pattern[index].set_prefab(prefab),
+            Op::CopyTileToRow { tile, row } => pattern.copy_tile_to_row(tile, row),
+            Op::CopyTileToColumn { tile, column } => pattern.copy_tile_to_column(tile, column),
+            Op::Fill(tile) => pattern.fill(tile),
+            Op::FillSelection { selection, tile } => pattern.fill_selection(&selection, tile),
+        }
+    }
+
+    /// Returns whether the op only refers to tiles
+    /// inside the pattern, so it can be applied
+    /// without panicking.
+    /// ```
+    /// use tinycbg::{Op, Tile};
+    ///
+    /// assert!(Op::SetHeight { index: 255, height: 4 }.is_valid());
+    /// assert!(!Op::CopyTileToRow { tile: Tile::default(), row: 16 }.is_valid());
+    /// ```
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Op::SetTile { index, .. }
+            | Op::SetHeight { index, .. }
+            | Op::SetPrefab { index, .. } => index < 256,
+            Op::CopyTileToRow { row: line, .. } | Op::CopyTileToColumn { column: line, .. } => {
+                line < 16
+            }
+            Op::Fill(_) | Op::FillSelection { .. } => true,
+        }
+    }
+
+    /// Writes a list of ops as text,
+    /// one op per line.
+    pub fn write_log(ops: &[Op]) -> String {
+        let mut log = String::new();
+        for op in ops {
+            log.push_str(&op.to_string());
+            log.push('\n');
+        }
+        log
+    }
+
+    /// Parses a list of ops written by `Op::write_log()`.
+    /// Empty lines are skipped. Ops which refer to a tile
+    /// outside of the pattern are rejected, so parsed
+    /// ops can always be applied.
+    pub fn parse_log(log: &str) -> Result<Vec<Op>, OpParseError> {
+        let mut ops = Vec::new();
+        for (i, line) in log.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let op = parse_op(line).ok_or(OpParseError { line: i as u32 + 1 })?;
+            ops.push(op);
+        }
+        Ok(ops)
+    }
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtRes {
+        match *self {
+            Op::SetTile { index, tile } => {
+                write!(f, "set {index} {} {}", tile.height(), tile.prefab())
+            }
+            Op::SetHeight { index, height } => write!(f, "height {index} {height}"),
+            Op::SetPrefab { index, prefab } => write!(f, "prefab {index} {prefab}"),
+            Op::CopyTileToRow { tile, row } => {
+                write!(f, "row {row} {} {}", tile.height(), tile.prefab())
+            }
+            Op::CopyTileToColumn { tile, column } => {
+                write!(f, "column {column} {} {}", tile.height(), tile.prefab())
+            }
+            Op::Fill(tile) => write!(f, "fill {} {}", tile.height(), tile.prefab()),
+            Op::FillSelection { selection, tile } => {
+                // Each row of the selection as 4 hex digits,
+                // with bit `x` set for tile `(x, y)`
+                f.write_str("selection ")?;
+                for y in 0..16 {
+                    let row: u16 = (0..16)
+                        .filter(|&x| selection.contains((x, y)))
+                        .map(|x| 1 << x)
+                        .sum();
+                    write!(f, "{row:04x}")?;
+                }
+                write!(f, " {} {}", tile.height(), tile.prefab())
+            }
+        }
+    }
+}
+
+// Parses an index, row or column,
+// which must be less than `limit`.
+fn parse_index(word: Option<&str>, limit: usize) -> Option<usize> {
+    let index = word?.parse().ok()?;
+    (index < limit).then_some(index)
+}
+
+fn parse_height(word: Option<&str>) -> Option<i8> {
+    let height = word?.parse().ok()?;
+    Tile::is_valid_height(height).then_some(height)
+}
+
+fn parse_prefab(word: Option<&str>) -> Option<Prefab> {
+    let bytes = word?.as_bytes();
+    if bytes.len() != 1 {
+        return None;
+    }
+    Prefab::from_byte(bytes[0])
+}
+
+fn parse_tile(height: Option<&str>, prefab: Option<&str>) -> Option<Tile> {
+    Some(Tile::new(parse_height(height)?, parse_prefab(prefab)?))
+}
+
+fn parse_selection(word: Option<&str>) -> Option<Selection> {
+    let word = word?;
+    if word.len() != 64 || !word.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut selection = Selection::new();
+    for y in 0..16 {
+        let row = u16::from_str_radix(&word[y * 4..y * 4 + 4], 16).ok()?;
+        for x in (0..16).filter(|x| row & (1 << x) != 0) {
+            selection.insert((x, y));
+        }
+    }
+    Some(selection)
+}
+
+// Parses a single line of an op log.
+fn parse_op(line: &str) -> Option<Op> {
+    let mut words = line.split_whitespace();
+    let op = match words.next() {
+        Some("set") => parse_index(words.next(), 256).and_then(|index| {
+            let tile = parse_tile(words.next(), words.next())?;
+            Some(Op::SetTile { index, tile })
+        }),
+        Some("height") => parse_index(words.next(), 256).and_then(|index| {
+            let height = parse_height(words.next())?;
+            Some(Op::SetHeight { index, height })
+        }),
+        Some("prefab") => parse_index(words.next(), 256).and_then(|index| {
+            let prefab = parse_prefab(words.next())?;
+            Some(Op::SetPrefab { index, prefab })
+        }),
+        Some("row") => parse_index(words.next(), 16).and_then(|row| {
+            let tile = parse_tile(words.next(), words.next())?;
+            Some(Op::CopyTileToRow { tile, row })
+        }),
+        Some("column") => parse_index(words.next(), 16).and_then(|column| {
+            let tile = parse_tile(words.next(), words.next())?;
+            Some(Op::CopyTileToColumn { tile, column })
+        }),
+        Some("fill") => parse_tile(words.next(), words.next()).map(Op::Fill),
+        Some("selection") => parse_selection(words.next()).and_then(|selection| {
+            let tile = parse_tile(words.next(), words.next())?;
+            Some(Op::FillSelection { selection, tile })
+        }),
+        _ => None,
+    };
+
+    match words.next() {
+        None => op,
+        Some(_) => None,
+    }
+}
+
+/// Parses a single op, written on one line like
+/// `Op::write_log()` does. Errors are on line 1,
+/// unless there's anything after the first line.
+impl FromStr for Op {
+    type Err = OpParseError;
+    fn from_str(s: &str) -> Result<Self, OpParseError> {
+        let mut lines = s.lines();
+        let op = parse_op(lines.next().unwrap_or_default()).ok_or(OpParseError { line: 1 })?;
+        match lines.next() {
+            None => Ok(op),
+            Some(_) => Err(OpParseError { line: 2 }),
+        }
+    }
+}
+
+/// An editing handle over a pattern which
+/// records every op applied through it.
+/// Created with `CyberGrindPattern::record()`.
+/// ```
+/// use tinycbg::{CyberGrindPattern, Prefab, Tile};
+///
+/// let mut pat = CyberGrindPattern::new();
+/// let mut recorder = pat.record();
+/// recorder.copy_tile_to_row(Tile::with_height(20), 0);
+/// recorder.set_prefab(40, Prefab::Melee);
+/// let ops = recorder.finish();
+///
+/// let mut copy = CyberGrindPattern::new();
+/// copy.replay(&ops);
+/// assert_eq!(copy[..], pat[..]);
+/// ```
+pub struct Recorder<'a> {
+    pattern: &'a mut CyberGrindPattern,
+    ops: Vec<Op>,
+}
+
+impl<'a> Recorder<'a> {
+    /// Applies `op` to the pattern and records it.
+    pub fn apply(&mut self, op: Op) {
+        op.apply(self.pattern);
+        self.ops.push(op);
+    }
+
+    pub fn set_tile(&mut self, index: usize, tile: Tile) {
+        self.apply(Op::SetTile { index, tile });
+    }
+
+    pub fn set_height(&mut self, index: usize, height: i8) {
+        self.apply(Op::SetHeight { index, height });
+    }
+
+    pub fn set_prefab(&mut self, index: usize, prefab: Prefab) {
+        self.apply(Op::SetPrefab { index, prefab });
+    }
+
+    pub fn copy_tile_to_row(&mut self, tile: Tile, row: usize) {
+        self.apply(Op::CopyTileToRow { tile, row });
+    }
+
+    pub fn copy_tile_to_column(&mut self, tile: Tile, column: usize) {
+        self.apply(Op::CopyTileToColumn { tile, column });
+    }
+
+    pub fn fill(&mut self, tile: Tile) {
+        self.apply(Op::Fill(tile));
+    }
+
+    pub fn fill_selection(&mut self, selection: &Selection, tile: Tile) {
+        self.apply(Op::FillSelection {
+            selection: *selection,
+            tile,
+        });
+    }
+
+    /// Same as `CyberGrindPattern::blit()`, recorded
+    /// as an `Op::SetTile` for every tile it changes.
+    pub fn blit(&mut self, sub: &SubPattern, at: (usize, usize)) {
+        self.record_tiles(|pattern| pattern.blit(sub, at));
+    }
+
+    /// Same as `CyberGrindPattern::copy_region_from()`, recorded
+    /// as an `Op::SetTile` for every tile it changes.
+    pub fn copy_region_from(&mut self, other: &CyberGrindPattern, src: Rect, dst: (usize, usize)) {
+        self.record_tiles(|pattern| pattern.copy_region_from(other, src, dst));
+    }
+
+    // Makes an edit which has no op of its own,
+    // recording the tiles it changed instead.
+    fn record_tiles(&mut self, edit: impl FnOnce(&mut CyberGrindPattern)) {
+        let before = *self.pattern;
+        edit(self.pattern);
+        for index in 0..256 {
+            let tile = self.pattern[index];
+            if tile != before[index] {
+                self.ops.push(Op::SetTile { index, tile });
+            }
+        }
+    }
+
+    /// Gets the pattern being edited.
+    pub fn pattern(&self) -> &CyberGrindPattern {
+        self.pattern
+    }
+
+    /// Gets every op recorded so far.
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Stops recording and returns the recorded ops.
+    pub fn finish(self) -> Vec<Op> {
+        self.ops
+    }
+}
+
+impl CyberGrindPattern {
+    /// Starts recording the edits made to this
+    /// pattern through the returned `Recorder`.
+    pub fn record(&mut self) -> Recorder<'_> {
+        Recorder {
+            pattern: self,
+            ops: Vec::new(),
+        }
+    }
+
+    /// Applies every op in `ops`, in order.
+    pub fn replay(&mut self, ops: &[Op]) {
+        for op in ops {
+            op.apply(self);
+        }
+    }
+}
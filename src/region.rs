@@ -0,0 +1,531 @@
+use alloc::{vec, vec::Vec};
+use core::ops::Index;
+
+use crate::{CyberGrindPattern, Selection, Tile};
+
+// Divides, rounding halfway cases away from zero
+// like `f32::round()`, which isn't in `core`.
+fn div_round(num: i32, den: i32) -> i32 {
+    (2 * num + num.signum() * den) / (2 * den)
+}
+
+/// A rectangle of tiles, starting at the top left
+/// corner `(x, y)`. Parts of a rectangle which are
+/// outside of the 16x16 grid are ignored.
+/// ```
+/// use tinycbg::Rect;
+///
+/// let rect = Rect::new(14, 0, 4, 2);
+/// assert!(rect.contains((15, 1)));
+/// assert_eq!(rect.positions().count(), 4); // Clipped to the grid
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    /// A rectangle covering the whole pattern.
+    pub const FULL: Rect = Rect::new(0, 0, 16, 16);
+
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Creates a square of tiles no further than
+    /// `radius` tiles from `(x, y)`, clipped to the grid.
+    /// ```
+    /// use tinycbg::Rect;
+    ///
+    /// assert_eq!(Rect::around((5, 0), 1), Rect::new(4, 0, 3, 2));
+    /// ```
+    pub fn around((x, y): (usize, usize), radius: usize) -> Self {
+        let (left, top) = (x.saturating_sub(radius), y.saturating_sub(radius));
+        Rect::new(left, top, x + radius + 1 - left, y + radius + 1 - top).clip()
+    }
+
+    /// Returns whether `(x, y)` is inside the rectangle.
+    pub const fn contains(&self, (x, y): (usize, usize)) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// Returns whether the rectangle has no tiles,
+    /// ignoring the parts outside of the grid.
+    pub fn is_empty(&self) -> bool {
+        self.area() == 0
+    }
+
+    /// Gets the number of tiles inside both
+    /// the rectangle and the grid.
+    pub fn area(&self) -> usize {
+        let rect = self.clip();
+        rect.width * rect.height
+    }
+
+    /// Gets the overlap between two rectangles,
+    /// or `None` if they don't overlap.
+    /// ```
+    /// use tinycbg::Rect;
+    ///
+    /// let a = Rect::new(0, 0, 6, 6);
+    /// let b = Rect::new(4, 2, 6, 2);
+    /// assert_eq!(a.intersect(&b), Some(Rect::new(4, 2, 2, 2)));
+    /// assert_eq!(a.intersect(&Rect::new(6, 0, 2, 2)), None);
+    /// ```
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        if x < right && y < bottom {
+            Some(Rect::new(x, y, right - x, bottom - y))
+        } else {
+            None
+        }
+    }
+
+    /// Moves the rectangle by `dx` tiles to the right
+    /// and `dy` tiles down. The parts moved past the
+    /// top or left edge of the grid are cut off.
+    /// ```
+    /// use tinycbg::Rect;
+    ///
+    /// let rect = Rect::new(2, 2, 4, 4);
+    /// assert_eq!(rect.translate(3, -1), Rect::new(5, 1, 4, 4));
+    /// assert_eq!(rect.translate(-3, 0), Rect::new(0, 2, 3, 4));
+    /// ```
+    pub fn translate(&self, dx: isize, dy: isize) -> Rect {
+        let (x, width) = translate_span(self.x, self.width, dx);
+        let (y, height) = translate_span(self.y, self.height, dy);
+        Rect::new(x, y, width, height)
+    }
+
+    /// Gets the part of the rectangle
+    /// which is inside the 16x16 grid.
+    pub fn clip(&self) -> Rect {
+        let x = self.x.min(16);
+        let y = self.y.min(16);
+        Rect {
+            x,
+            y,
+            width: self.width.min(16 - x),
+            height: self.height.min(16 - y),
+        }
+    }
+
+    // Gets the distance from `(x, y)` to the
+    // nearest tile inside the rectangle.
+    fn distance(&self, (x, y): (usize, usize)) -> usize {
+        let dx = if x < self.x {
+            self.x - x
+        } else {
+            (x + 1).saturating_sub(self.x + self.width)
+        };
+        let dy = if y < self.y {
+            self.y - y
+        } else {
+            (y + 1).saturating_sub(self.y + self.height)
+        };
+        dx.max(dy)
+    }
+
+    /// Iterates over every position inside the
+    /// rectangle and the grid, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + use<> {
+        let rect = self.clip();
+        (rect.y..rect.y + rect.height)
+            .flat_map(move |y| (rect.x..rect.x + rect.width).map(move |x| (x, y)))
+    }
+}
+
+/// A rectangle of tiles taken out of a pattern with
+/// `CyberGrindPattern::extract()`, which can be stamped
+/// into patterns with `CyberGrindPattern::blit()`.
+/// ```
+/// use tinycbg::{CyberGrindPattern, Rect, Tile};
+///
+/// let mut source = CyberGrindPattern::new();
+/// source[(1, 0)] = Tile::with_height(5);
+/// let bunker = source.extract(Rect::new(0, 0, 3, 2));
+/// assert_eq!((bunker.width(), bunker.height()), (3, 2));
+/// assert_eq!(bunker[(1, 0)].height(), 5);
+///
+/// let mut pat = CyberGrindPattern::new();
+/// pat.blit(&bunker, (4, 4));
+/// pat.blit(&bunker, (10, 4));
+/// assert_eq!(pat[(5, 4)].height(), 5);
+/// assert_eq!(pat[(11, 4)].height(), 5);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubPattern {
+    width: usize,
+    height: usize,
+    tiles: Vec<Tile>,
+}
+
+impl SubPattern {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Gets the tile at `(x, y)`, or `None` if
+    /// it's outside of the sub-pattern.
+    pub fn get(&self, (x, y): (usize, usize)) -> Option<Tile> {
+        if x < self.width && y < self.height {
+            Some(self.tiles[y * self.width + x])
+        } else {
+            None
+        }
+    }
+}
+
+impl Index<(usize, usize)> for SubPattern {
+    type Output = Tile;
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        assert!(
+            x < self.width && y < self.height,
+            "Coordinates must be inside the sub-pattern"
+        );
+        &self.tiles[y * self.width + x]
+    }
+}
+
+// Moves the span starting at `start` by `delta`,
+// cutting off the part that ends up below zero.
+fn translate_span(start: usize, len: usize, delta: isize) -> (usize, usize) {
+    let moved = start as isize + delta;
+    if moved >= 0 {
+        (moved as usize, len)
+    } else {
+        (0, len.saturating_sub(moved.unsigned_abs()))
+    }
+}
+
+impl CyberGrindPattern {
+    /// Sets every tile inside `rect` to `tile`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Rect, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.fill_rect(Rect::new(6, 6, 4, 4), Tile::with_height(15));
+    /// assert_eq!(pat[(9, 9)].height(), 15);
+    /// assert_eq!(pat[(10, 9)].height(), 0);
+    /// ```
+    pub fn fill_rect(&mut self, rect: Rect, tile: Tile) {
+        for pos in rect.positions() {
+            self[pos] = tile;
+        }
+    }
+
+    /// Copies the tiles inside `rect` into a `SubPattern`.
+    /// Parts of `rect` outside of the grid are left out.
+    pub fn extract(&self, rect: Rect) -> SubPattern {
+        let rect = rect.clip();
+        SubPattern {
+            width: rect.width,
+            height: rect.height,
+            tiles: rect.positions().map(|pos| self[pos]).collect(),
+        }
+    }
+
+    /// Copies `sub` into the pattern so that its top left
+    /// corner lands on `at`. Tiles which would land
+    /// outside of the grid are skipped.
+    pub fn blit(&mut self, sub: &SubPattern, at: (usize, usize)) {
+        // Sub-patterns are never wider than 16, so this
+        // also stops the additions below from overflowing
+        if at.0 >= 16 || at.1 >= 16 {
+            return;
+        }
+        for y in 0..sub.height {
+            for x in 0..sub.width {
+                let (to_x, to_y) = (at.0 + x, at.1 + y);
+                if to_x < 16 && to_y < 16 {
+                    self[(to_x, to_y)] = sub[(x, y)];
+                }
+            }
+        }
+    }
+
+    /// Iterates over the tiles inside `rect`
+    /// and their positions, row by row.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Rect, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(3, 4)] = Tile::with_height(5);
+    ///
+    /// let total: i32 = pat
+    ///     .tiles_in(Rect::new(2, 4, 3, 3))
+    ///     .map(|(_, tile)| tile.height() as i32)
+    ///     .sum();
+    /// assert_eq!(total, 5);
+    /// ```
+    pub fn tiles_in(&self, rect: Rect) -> impl Iterator<Item = ((usize, usize), Tile)> + '_ {
+        rect.positions().map(move |pos| (pos, self[pos]))
+    }
+
+    /// Copies row number `row` from `other`.
+    /// Panics if `row` is greater than 15.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let walls = CyberGrindPattern::splat(Tile::with_height(20));
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.copy_row_from(&walls, 3);
+    /// assert_eq!(pat[(5, 3)].height(), 20);
+    /// assert_eq!(pat[(5, 4)].height(), 0);
+    /// ```
+    pub fn copy_row_from(&mut self, other: &CyberGrindPattern, row: usize) {
+        assert!(row < 16, "Row must be less than 16");
+        let range = row * 16..row * 16 + 16;
+        self[range.clone()].copy_from_slice(&other[range]);
+    }
+
+    /// Copies column number `column` from `other`.
+    /// Panics if `column` is greater than 15.
+    pub fn copy_column_from(&mut self, other: &CyberGrindPattern, column: usize) {
+        assert!(column < 16, "Column must be less than 16");
+        for i in 0..16 {
+            self[(column, i)] = other[(column, i)];
+        }
+    }
+
+    /// Exchanges rows number `a` and `b`.
+    /// Panics if either is greater than 15.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.copy_tile_to_row(Tile::with_height(8), 2);
+    /// pat.swap_rows(2, 9);
+    /// assert_eq!(pat[(4, 9)].height(), 8);
+    /// assert_eq!(pat[(4, 2)].height(), 0);
+    /// ```
+    pub fn swap_rows(&mut self, a: usize, b: usize) {
+        assert!(a < 16 && b < 16, "Rows must be less than 16");
+        for x in 0..16 {
+            self.tiles.swap(a * 16 + x, b * 16 + x);
+        }
+    }
+
+    /// Exchanges columns number `a` and `b`.
+    /// Panics if either is greater than 15.
+    pub fn swap_columns(&mut self, a: usize, b: usize) {
+        assert!(a < 16 && b < 16, "Columns must be less than 16");
+        for y in 0..16 {
+            self.tiles.swap(y * 16 + a, y * 16 + b);
+        }
+    }
+
+    /// Exchanges the tiles at `a` and `b`.
+    /// Panics if any coordinate is greater than 15.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Prefab, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(0, 0)] = Tile::new(0, Prefab::JumpPad);
+    /// pat.swap_tiles((0, 0), (7, 8));
+    /// assert_eq!(pat[(7, 8)].prefab(), Prefab::JumpPad);
+    /// ```
+    pub fn swap_tiles(&mut self, a: (usize, usize), b: (usize, usize)) {
+        assert!(
+            a.0 < 16 && a.1 < 16 && b.0 < 16 && b.1 < 16,
+            "Coordinates must be less than 16"
+        );
+        self.tiles.swap(a.1 * 16 + a.0, b.1 * 16 + b.0);
+    }
+
+    /// Copies the tiles of `other` inside `src` so that the
+    /// top left corner of `src` lands on `dst`. Tiles which
+    /// would land outside of the grid are skipped.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Rect, Tile};
+    ///
+    /// let mut other = CyberGrindPattern::new();
+    /// other[(1, 1)] = Tile::with_height(10);
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.copy_region_from(&other, Rect::new(0, 0, 4, 4), (12, 6));
+    /// assert_eq!(pat[(13, 7)].height(), 10);
+    /// ```
+    pub fn copy_region_from(&mut self, other: &CyberGrindPattern, src: Rect, dst: (usize, usize)) {
+        let src = src.clip();
+        if dst.0 >= 16 || dst.1 >= 16 {
+            return;
+        }
+        for (x, y) in src.positions() {
+            let (to_x, to_y) = (x - src.x + dst.0, y - src.y + dst.1);
+            if to_x < 16 && to_y < 16 {
+                self[(to_x, to_y)] = other[(x, y)];
+            }
+        }
+    }
+
+    /// Exchanges the tiles inside `rect` with the
+    /// tiles at the same positions in `other`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Rect, Tile};
+    ///
+    /// let mut a = CyberGrindPattern::splat(Tile::with_height(1));
+    /// let mut b = CyberGrindPattern::splat(Tile::with_height(2));
+    /// a.swap_region(&mut b, Rect::new(0, 0, 8, 16));
+    ///
+    /// assert_eq!((a[(0, 0)].height(), a[(8, 0)].height()), (2, 1));
+    /// assert_eq!((b[(0, 0)].height(), b[(8, 0)].height()), (1, 2));
+    /// ```
+    pub fn swap_region(&mut self, other: &mut CyberGrindPattern, rect: Rect) {
+        for (x, y) in rect.positions() {
+            core::mem::swap(&mut self[(x, y)], &mut other[(x, y)]);
+        }
+    }
+
+    /// Sets every tile inside `rect` to the average
+    /// height of the tiles inside it, rounded to the
+    /// nearest whole number, and returns that height.
+    /// Prefabs are left alone.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Rect};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(0, 0)].set_height(10);
+    /// pat[(1, 0)].set_height(3);
+    ///
+    /// assert_eq!(pat.level_region(Rect::new(0, 0, 2, 1)), 7);
+    /// assert_eq!(pat[(0, 0)].height(), 7);
+    /// ```
+    pub fn level_region(&mut self, rect: Rect) -> i8 {
+        self.level_region_smoothed(rect, 0)
+    }
+
+    /// Same as `CyberGrindPattern::level_region()`, but also
+    /// blends the `falloff` rings of tiles around `rect`
+    /// towards the new height, so the leveled area slopes
+    /// into its surroundings instead of ending in a cliff.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Rect, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::splat(Tile::with_height(12));
+    /// pat.copy_tile_to_row(Tile::with_height(0), 7);
+    ///
+    /// pat.level_region_smoothed(Rect::new(0, 6, 16, 3), 2);
+    /// assert_eq!(pat[(0, 7)].height(), 8);
+    /// assert_eq!(pat[(0, 5)].height(), 9);
+    /// assert_eq!(pat[(0, 4)].height(), 11);
+    /// assert_eq!(pat[(0, 3)].height(), 12);
+    /// ```
+    pub fn level_region_smoothed(&mut self, rect: Rect, falloff: usize) -> i8 {
+        let rect = rect.clip();
+        self.level_area(rect.positions(), |pos| rect.distance(pos), falloff)
+    }
+
+    /// Same as `CyberGrindPattern::level_region()`,
+    /// but for the tiles in `selection`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Selection};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(8, 8)].set_height(9);
+    ///
+    /// let site = Selection::disc((8, 8), 1);
+    /// assert_eq!(pat.level_selection(&site), 2);
+    /// assert_eq!(pat[(8, 7)].height(), 2);
+    /// assert_eq!(pat[(7, 7)].height(), 0);
+    /// ```
+    pub fn level_selection(&mut self, selection: &Selection) -> i8 {
+        self.level_selection_smoothed(selection, 0)
+    }
+
+    /// Same as `CyberGrindPattern::level_region_smoothed()`,
+    /// but for the tiles in `selection`.
+    pub fn level_selection_smoothed(&mut self, selection: &Selection, falloff: usize) -> i8 {
+        self.level_area(
+            selection.positions(),
+            |pos| selection.distance(pos),
+            falloff,
+        )
+    }
+
+    // Levels the tiles at `positions`, blending the tiles
+    // up to `falloff` away from them, as measured by `distance`.
+    fn level_area(
+        &mut self,
+        positions: impl Iterator<Item = (usize, usize)>,
+        distance: impl Fn((usize, usize)) -> usize,
+        falloff: usize,
+    ) -> i8 {
+        let (sum, count) = positions.fold((0, 0), |(sum, count), pos| {
+            (sum + self[pos].height() as i32, count + 1)
+        });
+        if count == 0 {
+            return 0;
+        }
+        let level = div_round(sum, count) as i8;
+
+        for pos in Rect::FULL.positions() {
+            let distance = distance(pos);
+            if distance > falloff {
+                continue;
+            }
+            let height = self[pos].height() as i32;
+            let steps = falloff as i32 + 1;
+            let blended = level as i32 * steps + (height - level as i32) * distance as i32;
+            self[pos].set_height(div_round(blended, steps) as i8);
+        }
+        level
+    }
+
+    /// Turns the outer `thickness` rings of the pattern
+    /// into walls of height `height`, with no prefabs.
+    /// Panics if `height` is greater than 50 or less than -50.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.build_border(20, 2);
+    /// assert_eq!(pat[(1, 8)].height(), 20);
+    /// assert_eq!(pat[(14, 14)].height(), 20);
+    /// assert_eq!(pat[(2, 8)].height(), 0);
+    /// ```
+    pub fn build_border(&mut self, height: i8, thickness: usize) {
+        let profile = vec![height; thickness.min(8)];
+        self.build_border_profile(&profile);
+    }
+
+    /// Turns the outer rings of the pattern into walls,
+    /// with the outermost ring getting the first height
+    /// in `profile`, the next ring the second, and so on.
+    /// Rings past the end of `profile` are left alone.
+    /// Panics if a height is greater than 50 or less than -50.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// // A stepped wall
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.build_border_profile(&[30, 20, 10]);
+    /// assert_eq!(pat[(0, 0)].height(), 30);
+    /// assert_eq!(pat[(5, 1)].height(), 20);
+    /// assert_eq!(pat[(2, 13)].height(), 10);
+    /// assert_eq!(pat[(3, 12)].height(), 0);
+    /// ```
+    pub fn build_border_profile(&mut self, profile: &[i8]) {
+        for (x, y) in Rect::FULL.positions() {
+            let ring = x.min(y).min(15 - x).min(15 - y);
+            if let Some(&height) = profile.get(ring) {
+                self[(x, y)] = Tile::with_height(height);
+            }
+        }
+    }
+}
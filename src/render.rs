@@ -0,0 +1,302 @@
+use std::fmt::Write;
+
+use crate::{CyberGrindPattern, Prefab};
+
+/// Size of a tile in pixels when
+/// rendering a pattern to a PNG.
+pub const PNG_TILE_SIZE: usize = 16;
+
+// Maps a height to a shade of gray,
+// -50 being black and 50 being white.
+fn height_shade(height: i8) -> u8 {
+    ((height as i32 + 50) * 255 / 100) as u8
+}
+
+// Darkens a shade by up to 70% for fully
+// occluded tiles.
+fn darken(shade: u8, occlusion: f32) -> u8 {
+    (shade as f32 * (1.0 - occlusion * 0.7)) as u8
+}
+
+// Maps a change in height to a color, red for
+// raised tiles and blue for lowered ones, getting
+// brighter the bigger the change is.
+fn delta_color(delta: i8) -> [u8; 3] {
+    let intensity = (delta.unsigned_abs() as u32 * 255 / 100) as u8;
+    match delta {
+        0 => [0, 0, 0],
+        1.. => [intensity.max(40), 0, 0],
+        _ => [0, 0, intensity.max(40)],
+    }
+}
+
+fn prefab_color(prefab: Prefab) -> Option<[u8; 3]> {
+    match prefab {
+        Prefab::Melee => Some([220, 50, 50]),
+        Prefab::Projectile => Some([240, 160, 30]),
+        Prefab::HideousMass => Some([150, 60, 200]),
+        Prefab::JumpPad => Some([50, 200, 80]),
+        Prefab::Stairs => Some([60, 120, 230]),
+        Prefab::Unknown(_) => Some([128, 128, 128]),
+        Prefab::None => None,
+    }
+}
+
+// Escapes a prefab character for use in SVG text.
+fn escape(char: char) -> String {
+    match char {
+        '<' => String::from("&lt;"),
+        '>' => String::from("&gt;"),
+        '&' => String::from("&amp;"),
+        _ => String::from(char),
+    }
+}
+
+impl CyberGrindPattern {
+    /// Renders a top-down view of the pattern as an SVG image.
+    /// Heights are drawn as shades of gray, from black at -50
+    /// to white at 50, and prefabs as colored, lettered circles.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let svg = CyberGrindPattern::new().to_svg();
+    /// assert!(svg.starts_with("<svg"));
+    /// ```
+    pub fn to_svg(&self) -> String {
+        self.render_svg(&[0.0; 256])
+    }
+
+    /// Same as `CyberGrindPattern::to_svg()`, but with tiles
+    /// darkened by `CyberGrindPattern::ambient_occlusion()`,
+    /// so pits and canyons stand out.
+    pub fn to_svg_shaded(&self) -> String {
+        self.render_svg(&self.ambient_occlusion())
+    }
+
+    fn render_svg(&self, ao: &[f32; 256]) -> String {
+        let mut svg = String::from(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 16 16\" \
+             width=\"256\" height=\"256\" shape-rendering=\"crispEdges\">",
+        );
+        for y in 0..16 {
+            for x in 0..16 {
+                let tile = self[(x, y)];
+                let shade = darken(height_shade(tile.height()), ao[y * 16 + x]);
+                write!(
+                    svg,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"1\" height=\"1\" \
+                     fill=\"rgb({shade},{shade},{shade})\"><title>{}</title></rect>",
+                    tile.height()
+                )
+                .expect("Writing to a String can't fail");
+
+                if let Some([r, g, b]) = prefab_color(tile.prefab()) {
+                    write!(
+                        svg,
+                        "<circle cx=\"{x}.5\" cy=\"{y}.5\" r=\"0.35\" fill=\"rgb({r},{g},{b})\"/>\
+                         <text x=\"{x}.5\" y=\"{y}.62\" font-size=\"0.4\" text-anchor=\"middle\" \
+                         fill=\"white\">{}</text>",
+                        escape(tile.prefab().char())
+                    )
+                    .expect("Writing to a String can't fail");
+                }
+            }
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Renders a top-down view of the pattern as a PNG image,
+    /// `PNG_TILE_SIZE` pixels per tile. Uses the same colors
+    /// as `CyberGrindPattern::to_svg()`, with prefabs drawn
+    /// as colored squares.
+    pub fn to_png(&self) -> Vec<u8> {
+        self.render_png(&[0.0; 256])
+    }
+
+    /// Same as `CyberGrindPattern::to_png()`, but with tiles
+    /// darkened by `CyberGrindPattern::ambient_occlusion()`.
+    pub fn to_png_shaded(&self) -> Vec<u8> {
+        self.render_png(&self.ambient_occlusion())
+    }
+
+    fn render_png(&self, ao: &[f32; 256]) -> Vec<u8> {
+        const SIZE: usize = 16 * PNG_TILE_SIZE;
+        let mut rgb = vec![0; SIZE * SIZE * 3];
+        for py in 0..SIZE {
+            for px in 0..SIZE {
+                let (x, y) = (px / PNG_TILE_SIZE, py / PNG_TILE_SIZE);
+                let tile = self[(x, y)];
+                let (tx, ty) = (px % PNG_TILE_SIZE, py % PNG_TILE_SIZE);
+                let inner =
+                    (4..PNG_TILE_SIZE - 4).contains(&tx) && (4..PNG_TILE_SIZE - 4).contains(&ty);
+
+                let shade = darken(height_shade(tile.height()), ao[y * 16 + x]);
+                let color = match prefab_color(tile.prefab()) {
+                    Some(color) if inner => color,
+                    _ => [shade; 3],
+                };
+                let i = (py * SIZE + px) * 3;
+                rgb[i..i + 3].copy_from_slice(&color);
+            }
+        }
+        encode_png(SIZE as u32, SIZE as u32, &rgb)
+    }
+
+    /// Renders the differences between `self` and `other`
+    /// as text with ANSI colors, one character per tile.
+    /// Tiles `other` raised have a red background and
+    /// tiles it lowered a blue one, brighter the more the
+    /// height changed. Tiles with a different prefab show
+    /// the new prefab's letter, and others show a dot.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let diff = CyberGrindPattern::new().diff_heatmap_ansi(&CyberGrindPattern::new());
+    /// assert_eq!(diff.lines().count(), 16);
+    /// ```
+    pub fn diff_heatmap_ansi(&self, other: &CyberGrindPattern) -> String {
+        let mut text = String::new();
+        for y in 0..16 {
+            for x in 0..16 {
+                let (old, new) = (self[(x, y)], other[(x, y)]);
+                let marker = if old.prefab() == new.prefab() {
+                    '.'
+                } else {
+                    new.prefab().char()
+                };
+                let [r, g, b] = delta_color(new.height() - old.height());
+                write!(text, "\x1b[48;2;{r};{g};{b}m\x1b[97m{marker}")
+                    .expect("Writing to a String can't fail");
+            }
+            text.push_str("\x1b[0m\n");
+        }
+        text
+    }
+
+    /// Renders the differences between `self` and `other`
+    /// as a PNG image, `PNG_TILE_SIZE` pixels per tile. Uses
+    /// the same colors as `CyberGrindPattern::diff_heatmap_ansi()`,
+    /// with changed prefabs drawn as squares in the new
+    /// prefab's color, or white if it was removed.
+    pub fn diff_heatmap_png(&self, other: &CyberGrindPattern) -> Vec<u8> {
+        const SIZE: usize = 16 * PNG_TILE_SIZE;
+        let mut rgb = vec![0; SIZE * SIZE * 3];
+        for py in 0..SIZE {
+            for px in 0..SIZE {
+                let (x, y) = (px / PNG_TILE_SIZE, py / PNG_TILE_SIZE);
+                let (old, new) = (self[(x, y)], other[(x, y)]);
+                let (tx, ty) = (px % PNG_TILE_SIZE, py % PNG_TILE_SIZE);
+                let inner =
+                    (4..PNG_TILE_SIZE - 4).contains(&tx) && (4..PNG_TILE_SIZE - 4).contains(&ty);
+
+                let color = if inner && old.prefab() != new.prefab() {
+                    prefab_color(new.prefab()).unwrap_or([255; 3])
+                } else {
+                    delta_color(new.height() - old.height())
+                };
+                let i = (py * SIZE + px) * 3;
+                rgb[i..i + 3].copy_from_slice(&color);
+            }
+        }
+        encode_png(SIZE as u32, SIZE as u32, &rgb)
+    }
+
+    /// Renders a 16x16 tangent-space normal map of the pattern
+    /// as a PNG image, one pixel per tile, from
+    /// `CyberGrindPattern::gradients()`. Follows the OpenGL
+    /// convention, with green pointing up the image, and treats
+    /// a unit of height as being as long as a tile is wide.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let png = CyberGrindPattern::new().to_normal_map_png();
+    /// assert!(png.starts_with(b"\x89PNG"));
+    /// ```
+    pub fn to_normal_map_png(&self) -> Vec<u8> {
+        let mut rgb = Vec::with_capacity(256 * 3);
+        for (dx, dy) in self.gradients() {
+            // Flip y, since the image's y axis points down
+            let normal = [-dx, dy, 1.0];
+            let length = normal.iter().map(|n| n * n).sum::<f32>().sqrt();
+            for n in normal {
+                rgb.push(((n / length * 0.5 + 0.5) * 255.0).round() as u8);
+            }
+        }
+        encode_png(16, 16, &rgb)
+    }
+}
+
+fn crc32(chunks: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+    for chunk in chunks {
+        for &byte in *chunk {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                let mask = (crc & 1).wrapping_neg();
+                crc = (crc >> 1) ^ (0xedb88320 & mask);
+            }
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc32(&[kind, data]).to_be_bytes());
+}
+
+// Encodes 8-bit RGB pixels as a PNG. The image data
+// is stored uncompressed, which keeps the encoder
+// tiny at the cost of larger files.
+pub(crate) fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    let row_len = width as usize * 3;
+    let mut raw = Vec::with_capacity((row_len + 1) * height as usize);
+    for row in rgb.chunks(row_len) {
+        raw.push(0); // No filter
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(u16::MAX as usize).peekable();
+    while let Some(block) = blocks.next() {
+        zlib.push(blocks.peek().is_none() as u8);
+        zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8-bit RGB
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32};
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(&[b"IEND"]), 0xae426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+}
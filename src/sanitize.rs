@@ -0,0 +1,274 @@
+use alloc::vec::Vec;
+
+use crate::{error::HeightError, CyberGrindPattern, Prefab, Tile};
+
+/// What to do with heights outside of -50 to 50.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutOfRange {
+    /// Move the height to the nearest valid one
+    #[default]
+    Clamp,
+    /// Use this height instead
+    Replace(i8),
+}
+
+impl OutOfRange {
+    // Gets the height to use in place of `height`,
+    // which is outside of -50 to 50.
+    pub(crate) fn resolve(self, height: i32) -> i8 {
+        match self {
+            OutOfRange::Clamp => height.clamp(-50, 50) as i8,
+            OutOfRange::Replace(to) => to.clamp(-50, 50),
+        }
+    }
+}
+
+impl CyberGrindPattern {
+    /// Raises the height of every tile by `amount`, using
+    /// `out_of_range` for tiles which would end up above 50
+    /// or below -50.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, OutOfRange, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(3, 3)].set_height(40);
+    ///
+    /// pat.raise_all(20, OutOfRange::Clamp);
+    /// assert_eq!(pat[(0, 0)].height(), 20);
+    /// assert_eq!(pat[(3, 3)].height(), 50);
+    ///
+    /// pat.raise_all(10, OutOfRange::Replace(0));
+    /// assert_eq!(pat[(0, 0)].height(), 30);
+    /// assert_eq!(pat[(3, 3)].height(), 0);
+    /// ```
+    pub fn raise_all(&mut self, amount: i8, out_of_range: OutOfRange) {
+        self.shift_heights(amount as i32, out_of_range);
+    }
+
+    /// Lowers the height of every tile by `amount`. See
+    /// `CyberGrindPattern::raise_all()`.
+    pub fn lower_all(&mut self, amount: i8, out_of_range: OutOfRange) {
+        self.shift_heights(-(amount as i32), out_of_range);
+    }
+
+    /// Replaces the height of every tile with the result
+    /// of calling `f` on it, using `out_of_range` for
+    /// results above 50 or below -50. `f` returns an `i32`
+    /// so steep curves can't overflow before being clamped.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, OutOfRange, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::splat(Tile::with_height(30));
+    /// pat[(2, 2)].set_height(-9);
+    ///
+    /// pat.map_heights(|height| height as i32 / 2, OutOfRange::Clamp);
+    /// assert_eq!(pat[(0, 0)].height(), 15);
+    /// assert_eq!(pat[(2, 2)].height(), -4);
+    ///
+    /// pat.map_heights(|height| height as i32 * 40, OutOfRange::Clamp);
+    /// assert_eq!(pat[(0, 0)].height(), 50);
+    /// ```
+    pub fn map_heights<F: FnMut(i8) -> i32>(&mut self, mut f: F, out_of_range: OutOfRange) {
+        for tile in &mut self.tiles {
+            let height = f(tile.height());
+            let height = if (-50..=50).contains(&height) {
+                height as i8
+            } else {
+                out_of_range.resolve(height)
+            };
+            tile.set_height(height);
+        }
+    }
+
+    /// Same as `CyberGrindPattern::map_heights()`, but fails
+    /// if `f` returns a height above 50 or below -50, leaving
+    /// the pattern unchanged. Results which don't fit in an
+    /// `i8` are saturated in the `HeightError`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    /// use tinycbg::error::HeightError;
+    ///
+    /// let mut pat = CyberGrindPattern::splat(Tile::with_height(30));
+    /// let err = pat.try_map_heights(|height| height as i32 * 2).unwrap_err();
+    /// assert_eq!(err, HeightError { height: 60 });
+    /// assert_eq!(pat[0].height(), 30);
+    /// ```
+    pub fn try_map_heights<F: FnMut(i8) -> i32>(&mut self, mut f: F) -> Result<(), HeightError> {
+        let mut heights = [0; 256];
+        for (height, tile) in heights.iter_mut().zip(&self.tiles) {
+            let result = f(tile.height());
+            if !(-50..=50).contains(&result) {
+                let height = result.clamp(i8::MIN as i32, i8::MAX as i32) as i8;
+                return Err(HeightError { height });
+            }
+            *height = result as i8;
+        }
+        for (tile, height) in self.tiles.iter_mut().zip(heights) {
+            tile.set_height(height);
+        }
+        Ok(())
+    }
+
+    fn shift_heights(&mut self, amount: i32, out_of_range: OutOfRange) {
+        for tile in &mut self.tiles {
+            let height = tile.height() as i32 + amount;
+            let height = if (-50..=50).contains(&height) {
+                height as i8
+            } else {
+                out_of_range.resolve(height)
+            };
+            tile.set_height(height);
+        }
+    }
+}
+
+/// Settings for `RawPattern::sanitize()`.
+/// ```
+/// use tinycbg::{OutOfRange, Prefab, SanitizeConfig};
+///
+/// let config = SanitizeConfig {
+///     out_of_range: OutOfRange::Replace(0),
+///     unknown_prefab: Prefab::Stairs,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SanitizeConfig {
+    pub out_of_range: OutOfRange,
+    /// Used in place of prefab characters
+    /// that aren't valid, `Prefab::None`
+    /// by default
+    pub unknown_prefab: Prefab,
+    /// Used for tiles past the end of the data,
+    /// `Tile::default()` by default
+    pub missing_tile: Tile,
+}
+
+impl Default for SanitizeConfig {
+    fn default() -> Self {
+        SanitizeConfig {
+            out_of_range: OutOfRange::Clamp,
+            unknown_prefab: Prefab::None,
+            missing_tile: Tile::default(),
+        }
+    }
+}
+
+/// A change made by `RawPattern::sanitize()`
+/// to turn the data into a valid pattern.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Correction {
+    /// The height at `index` was out of range
+    Height { index: usize, from: i32, to: i8 },
+    /// The prefab character at `index` wasn't valid
+    Prefab { index: usize, from: u8, to: Prefab },
+    /// There was no height at `index`
+    MissingHeight { index: usize },
+    /// There was no prefab at `index`
+    MissingPrefab { index: usize },
+    /// There were `count` more heights than fit
+    ExtraHeights { count: usize },
+    /// There were `count` more prefabs than fit
+    ExtraPrefabs { count: usize },
+}
+
+/// Pattern data from somewhere else, like an image, a
+/// CSV file or another game, which may not be valid.
+/// Heights can be any number, and prefabs are
+/// characters like in a `.cgp` file.
+/// ```
+/// use tinycbg::{Correction, RawPattern};
+///
+/// let raw = RawPattern {
+///     heights: vec![70, -3],
+///     prefabs: b"nq".to_vec(),
+/// };
+/// let (pattern, corrections) = raw.sanitize(&Default::default());
+/// assert_eq!(pattern[0].height(), 50);
+/// assert_eq!(corrections[0], Correction::Height { index: 0, from: 70, to: 50 });
+/// assert_eq!(corrections.len(), 1 + 1 + 254 * 2);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RawPattern {
+    pub heights: Vec<i32>,
+    pub prefabs: Vec<u8>,
+}
+
+impl RawPattern {
+    /// Turns the data into a valid pattern, fixing
+    /// anything invalid following `config`. Returns
+    /// the pattern along with every fix made, in the
+    /// order of the tiles they were made to.
+    pub fn sanitize(&self, config: &SanitizeConfig) -> (CyberGrindPattern, Vec<Correction>) {
+        let mut pattern = CyberGrindPattern::splat(config.missing_tile);
+        let mut corrections = Vec::new();
+
+        for index in 0..256 {
+            match self.heights.get(index) {
+                Some(&height) if (-50..=50).contains(&height) => {
+                    pattern[index].set_height(height as i8);
+                }
+                Some(&height) => {
+                    let to = config.out_of_range.resolve(height);
+                    pattern[index].set_height(to);
+                    corrections.push(Correction::Height {
+                        index,
+                        from: height,
+                        to,
+                    });
+                }
+                None => corrections.push(Correction::MissingHeight { index }),
+            }
+
+            match self.prefabs.get(index) {
+                Some(&byte) => match Prefab::try_from(byte) {
+                    Ok(prefab) => pattern[index].set_prefab(prefab),
+                    Err(_) => {
+                        pattern[index].set_prefab(config.unknown_prefab);
+                        corrections.push(Correction::Prefab {
+                            index,
+                            from: byte,
+                            to: config.unknown_prefab,
+                        });
+                    }
+                },
+                None => corrections.push(Correction::MissingPrefab { index }),
+            }
+        }
+
+        if self.heights.len() > 256 {
+            corrections.push(Correction::ExtraHeights {
+                count: self.heights.len() - 256,
+            });
+        }
+        if self.prefabs.len() > 256 {
+            corrections.push(Correction::ExtraPrefabs {
+                count: self.prefabs.len() - 256,
+            });
+        }
+        (pattern, corrections)
+    }
+}
+
+impl CyberGrindPattern {
+    /// Creates a new Cybergrind pattern from heights
+    /// and prefab characters which may not be valid,
+    /// using `SanitizeConfig::default()` to fix them.
+    /// Use `RawPattern::sanitize()` to find out what
+    /// was fixed.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Prefab};
+    ///
+    /// let pat = CyberGrindPattern::from_parts_lossy(&[-100, 4], b"?J");
+    /// assert_eq!(pat[0].height(), -50);
+    /// assert_eq!(pat[0].prefab(), Prefab::None);
+    /// assert_eq!(pat[1].prefab(), Prefab::JumpPad);
+    /// ```
+    pub fn from_parts_lossy(heights: &[i32], prefabs: &[u8]) -> Self {
+        let raw = RawPattern {
+            heights: heights.to_vec(),
+            prefabs: prefabs.to_vec(),
+        };
+        raw.sanitize(&SanitizeConfig::default()).0
+    }
+}
@@ -0,0 +1,73 @@
+/// Maps tiles and heights to positions in a 3D world and
+/// back, so tools drawing on top of a pattern or exporting
+/// meshes of it agree on where everything is.
+///
+/// The world's x axis follows the pattern's x axis, its
+/// z axis follows the pattern's y axis, and y points up.
+/// ```
+/// use tinycbg::WorldScale;
+///
+/// let scale = WorldScale {
+///     tile_size: 10.0,
+///     height_step: 2.0,
+///     ..Default::default()
+/// };
+/// assert_eq!(scale.tile_to_world((1, 2), -3), [15.0, -6.0, 25.0]);
+/// assert_eq!(scale.world_to_tile(19.9, 20.0), Some((1, 2)));
+/// assert_eq!(scale.world_to_height(-6.4), -3);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldScale {
+    /// Width and length of a tile in world units
+    pub tile_size: f32,
+    /// World units per unit of height
+    pub height_step: f32,
+    /// Where the top left corner of tile `(0, 0)`,
+    /// at height 0, is in the world
+    pub origin: [f32; 3],
+}
+
+impl Default for WorldScale {
+    /// One world unit per tile and per unit of height,
+    /// with the pattern's corner at the world's origin.
+    fn default() -> Self {
+        WorldScale {
+            tile_size: 1.0,
+            height_step: 1.0,
+            origin: [0.0; 3],
+        }
+    }
+}
+
+impl WorldScale {
+    /// Gets the world position of the center of
+    /// the top of tile `(x, y)` at height `height`.
+    pub fn tile_to_world(&self, (x, y): (usize, usize), height: i8) -> [f32; 3] {
+        [
+            self.origin[0] + (x as f32 + 0.5) * self.tile_size,
+            self.height_to_world(height),
+            self.origin[2] + (y as f32 + 0.5) * self.tile_size,
+        ]
+    }
+
+    /// Gets the world elevation of `height`.
+    pub fn height_to_world(&self, height: i8) -> f32 {
+        self.origin[1] + height as f32 * self.height_step
+    }
+
+    /// Gets the tile under the world position `(x, z)`,
+    /// or `None` if it's outside of the pattern.
+    pub fn world_to_tile(&self, x: f32, z: f32) -> Option<(usize, usize)> {
+        let x = ((x - self.origin[0]) / self.tile_size).floor();
+        let z = ((z - self.origin[2]) / self.tile_size).floor();
+        ((0.0..16.0).contains(&x) && (0.0..16.0).contains(&z)).then_some((x as usize, z as usize))
+    }
+
+    /// Gets the nearest valid height to the world
+    /// elevation `y`, clamped between -50 and 50.
+    pub fn world_to_height(&self, y: f32) -> i8 {
+        ((y - self.origin[1]) / self.height_step)
+            .round()
+            .clamp(-50.0, 50.0) as i8
+    }
+}
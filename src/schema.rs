@@ -0,0 +1,85 @@
+//! `JsonSchema` for patterns, tiles and prefabs, describing
+//! the JSON written by their `Serialize` impls.
+//!
+//! Prefabs are described by their in-game names, or any single
+//! printable ASCII character for unknown prefabs. Aliases like
+//! `"Jump Pad"` are still read back, but aren't part of the schema.
+
+use alloc::{borrow::Cow, vec::Vec};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+
+use crate::{CyberGrindPattern, Prefab, Tile};
+
+impl JsonSchema for Prefab {
+    fn schema_name() -> Cow<'static, str> {
+        "Prefab".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        let names: Vec<&str> = Prefab::iter().map(Prefab::name).collect();
+        json_schema!({
+            "anyOf": [
+                { "enum": names },
+                { "type": "string", "pattern": "^[!-~]$" }
+            ]
+        })
+    }
+}
+
+fn height_schema() -> Schema {
+    json_schema!({
+        "type": "integer",
+        "minimum": -50,
+        "maximum": 50
+    })
+}
+
+// A 16x16 grid of `item`, indexed by row and then column.
+fn grid_schema(item: Schema) -> Schema {
+    let row = json_schema!({
+        "type": "array",
+        "items": item,
+        "minItems": 16,
+        "maxItems": 16
+    });
+    json_schema!({
+        "type": "array",
+        "items": row,
+        "minItems": 16,
+        "maxItems": 16
+    })
+}
+
+impl JsonSchema for Tile {
+    fn schema_name() -> Cow<'static, str> {
+        "Tile".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "height": height_schema(),
+                "prefab": generator.subschema_for::<Prefab>()
+            },
+            "required": ["height", "prefab"]
+        })
+    }
+}
+
+impl JsonSchema for CyberGrindPattern {
+    fn schema_name() -> Cow<'static, str> {
+        "CyberGrindPattern".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "heights": grid_schema(height_schema()),
+                "prefabs": grid_schema(generator.subschema_for::<Prefab>())
+            },
+            "required": ["heights", "prefabs"]
+        })
+    }
+}
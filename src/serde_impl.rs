@@ -0,0 +1,108 @@
+//! `Serialize` and `Deserialize` for patterns, tiles and prefabs.
+//!
+//! Prefabs are written as their in-game names, like `"jump pad"`,
+//! and unknown prefabs as their character. Any name or alias
+//! `Prefab::from_str()` accepts can be read back. Tiles are
+//! structs with a `height` and a `prefab`, and patterns are
+//! structs with 16x16 nested arrays of `heights` and `prefabs`,
+//! indexed by row and then column.
+
+use alloc::string::String;
+use serde::{
+    de::{Error, Unexpected},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{CyberGrindPattern, Prefab, Tile, UnknownPrefab};
+
+impl Serialize for Prefab {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Prefab::Unknown(char) => serializer.serialize_str(char.char().encode_utf8(&mut [0; 4])),
+            _ => serializer.serialize_str(self.name()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Prefab {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        if let Ok(prefab) = name.parse() {
+            return Ok(prefab);
+        }
+        match name.as_bytes() {
+            &[byte] => UnknownPrefab::Keep.resolve(byte),
+            _ => None,
+        }
+        .ok_or_else(|| Error::invalid_value(Unexpected::Str(&name), &"a prefab name"))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TileRepr {
+    height: i8,
+    prefab: Prefab,
+}
+
+fn check_height<E: Error>(height: i8) -> Result<i8, E> {
+    if Tile::is_valid_height(height) {
+        Ok(height)
+    } else {
+        Err(Error::invalid_value(
+            Unexpected::Signed(height as i64),
+            &"a height between -50 and 50",
+        ))
+    }
+}
+
+impl Serialize for Tile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TileRepr {
+            height: self.height(),
+            prefab: self.prefab(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = TileRepr::deserialize(deserializer)?;
+        Ok(Tile::new(check_height(repr.height)?, repr.prefab))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PatternRepr {
+    heights: [[i8; 16]; 16],
+    prefabs: [[Prefab; 16]; 16],
+}
+
+impl Serialize for CyberGrindPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut repr = PatternRepr {
+            heights: [[0; 16]; 16],
+            prefabs: [[Prefab::None; 16]; 16],
+        };
+        for y in 0..16 {
+            for x in 0..16 {
+                repr.heights[y][x] = self[(x, y)].height();
+                repr.prefabs[y][x] = self[(x, y)].prefab();
+            }
+        }
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CyberGrindPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = PatternRepr::deserialize(deserializer)?;
+        let mut pattern = CyberGrindPattern::new();
+        for y in 0..16 {
+            for x in 0..16 {
+                pattern[(x, y)] = Tile::new(check_height(repr.heights[y][x])?, repr.prefabs[y][x]);
+            }
+        }
+        Ok(pattern)
+    }
+}
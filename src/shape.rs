@@ -0,0 +1,457 @@
+use alloc::vec::Vec;
+
+use crate::{CyberGrindPattern, Rect, Tile};
+
+/// A set of tiles, usually made by one of the drawing
+/// functions like `Selection::line()`. Positions outside
+/// of the 16x16 grid are never selected.
+/// ```
+/// use tinycbg::{CyberGrindPattern, Selection, Tile};
+///
+/// let wall = Selection::line((0, 0), (15, 3));
+/// let mut pat = CyberGrindPattern::new();
+/// pat.fill_selection(&wall, Tile::with_height(20));
+///
+/// assert_eq!(wall.len(), 16);
+/// assert_eq!(pat[(15, 3)].height(), 20);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Selection {
+    // One bit for every tile, with bit `x` of `rows[y]` for `(x, y)`
+    rows: [u16; 16],
+}
+
+impl Selection {
+    /// A selection with no tiles.
+    pub const fn new() -> Self {
+        Selection { rows: [0; 16] }
+    }
+
+    /// Selects every tile on the straight line from `a`
+    /// to `b`, including both ends, with no gaps between
+    /// tiles. The line can go in any direction.
+    /// ```
+    /// use tinycbg::Selection;
+    ///
+    /// let line = Selection::line((10, 1), (1, 4));
+    /// assert_eq!(line.len(), 10);
+    /// assert!(line.contains((1, 4)));
+    /// assert!(line.contains((7, 2)));
+    /// ```
+    pub fn line(a: (usize, usize), b: (usize, usize)) -> Self {
+        let mut selection = Selection::new();
+        selection.insert_line(signed(a), signed(b));
+        selection
+    }
+
+    /// Selects every tile whose center is no more than
+    /// `width / 2` tiles from the line from `a` to `b`,
+    /// along with the tiles of `Selection::line()`, so
+    /// thin lines still have no gaps. The ends are rounded,
+    /// so they stick out past `a` and `b` for wide lines.
+    /// ```
+    /// use tinycbg::Selection;
+    ///
+    /// let wall = Selection::thick_line((2, 8), (13, 8), 3);
+    /// assert_eq!(wall.len(), 14 * 3);
+    /// assert!(wall.contains((1, 7)) && wall.contains((14, 9)));
+    /// assert!(!wall.contains((2, 6)) && !wall.contains((0, 8)));
+    /// ```
+    pub fn thick_line(a: (usize, usize), b: (usize, usize), width: usize) -> Self {
+        let mut selection = Selection::line(a, b);
+        let (ax, ay) = (a.0 as f32, a.1 as f32);
+        let (dx, dy) = (b.0 as f32 - ax, b.1 as f32 - ay);
+        let length = dx * dx + dy * dy;
+        // Compared squared to avoid `f32::sqrt()`, which isn't in `core`
+        let max = (width * width) as f32 / 4.0;
+        for (x, y) in Rect::FULL.positions() {
+            let (px, py) = (x as f32 - ax, y as f32 - ay);
+            let t = if length == 0.0 {
+                0.0
+            } else {
+                ((px * dx + py * dy) / length).clamp(0.0, 1.0)
+            };
+            let (ox, oy) = (px - t * dx, py - t * dy);
+            if ox * ox + oy * oy <= max {
+                selection.insert((x, y));
+            }
+        }
+        selection
+    }
+
+    /// Selects the lines between each point in `points`
+    /// and the next one, making a path through all of them.
+    /// ```
+    /// use tinycbg::Selection;
+    ///
+    /// let zigzag = Selection::path(&[(0, 0), (4, 4), (8, 0), (12, 4)]);
+    /// assert_eq!(zigzag.len(), 13);
+    /// assert!(zigzag.contains((6, 2)));
+    /// ```
+    pub fn path(points: &[(usize, usize)]) -> Self {
+        let mut selection = Selection::new();
+        if let Some(&first) = points.first() {
+            selection.insert(first);
+        }
+        for segment in points.windows(2) {
+            selection.insert_line(signed(segment[0]), signed(segment[1]));
+        }
+        selection
+    }
+
+    /// Selects the outline of a circle around `center`,
+    /// using the midpoint circle algorithm. Parts of
+    /// the circle outside of the grid are left out.
+    /// ```
+    /// use tinycbg::Selection;
+    ///
+    /// let ring = Selection::circle((7, 7), 5);
+    /// assert!(ring.contains((12, 7)) && ring.contains((7, 2)));
+    /// assert!(!ring.contains((7, 7)));
+    /// ```
+    pub fn circle(center: (usize, usize), radius: usize) -> Self {
+        Selection::ellipse(center, radius, radius)
+    }
+
+    /// Selects the outline of an ellipse around `center`,
+    /// stretching `rx` tiles left and right and `ry` tiles
+    /// up and down, using the midpoint ellipse algorithm.
+    /// Parts of the ellipse outside of the grid are left out.
+    /// ```
+    /// use tinycbg::Selection;
+    ///
+    /// let ring = Selection::ellipse((7, 7), 6, 3);
+    /// assert!(ring.contains((1, 7)) && ring.contains((13, 7)));
+    /// assert!(ring.contains((7, 4)) && ring.contains((7, 10)));
+    /// ```
+    pub fn ellipse(center: (usize, usize), rx: usize, ry: usize) -> Self {
+        let mut selection = Selection::new();
+        let center = signed(center);
+        for (x, y) in ellipse_quadrant(rx as i64, ry as i64) {
+            for (x, y) in [(x, y), (-x, y), (x, -y), (-x, -y)] {
+                selection.insert_signed((center.0 + x as isize, center.1 + y as isize));
+            }
+        }
+        selection
+    }
+
+    /// Selects a filled circle around `center`, with the
+    /// same edge as `Selection::circle()`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Selection, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat.fill_selection(&Selection::disc((8, 8), 3), Tile::with_height(5));
+    /// assert_eq!(pat[(8, 8)].height(), 5);
+    /// assert_eq!(pat[(6, 9)].height(), 5);
+    /// assert_eq!(pat[(5, 5)].height(), 0);
+    /// ```
+    pub fn disc(center: (usize, usize), radius: usize) -> Self {
+        Selection::filled_ellipse(center, radius, radius)
+    }
+
+    /// Selects a filled ellipse around `center`, with
+    /// the same edge as `Selection::ellipse()`.
+    pub fn filled_ellipse(center: (usize, usize), rx: usize, ry: usize) -> Self {
+        let mut selection = Selection::new();
+        let center = signed(center);
+        for (x, y) in ellipse_quadrant(rx as i64, ry as i64) {
+            let (x, y) = (x as isize, y as isize);
+            for y in [center.1 - y, center.1 + y] {
+                selection.insert_line((center.0 - x, y), (center.0 + x, y));
+            }
+        }
+        selection
+    }
+
+    /// Selects the tiles around the edge of `rect`. Edges
+    /// outside of the grid are left out, so clipped
+    /// rectangles are open on that side.
+    /// ```
+    /// use tinycbg::{Rect, Selection};
+    ///
+    /// let room = Selection::rect_outline(Rect::new(2, 2, 5, 4));
+    /// assert_eq!(room.len(), 14);
+    /// assert!(room.contains((6, 5)));
+    /// assert!(!room.contains((3, 3)));
+    /// ```
+    pub fn rect_outline(rect: Rect) -> Self {
+        let mut selection = Selection::new();
+        if rect.width == 0 || rect.height == 0 {
+            return selection;
+        }
+        let (left, top) = signed((rect.x, rect.y));
+        let (right, bottom) = signed((rect.x + rect.width - 1, rect.y + rect.height - 1));
+        selection.insert_line((left, top), (right, top));
+        selection.insert_line((right, top), (right, bottom));
+        selection.insert_line((right, bottom), (left, bottom));
+        selection.insert_line((left, bottom), (left, top));
+        selection
+    }
+
+    /// Selects every tile inside `rect`.
+    pub fn rect_filled(rect: Rect) -> Self {
+        let mut selection = Selection::new();
+        for pos in rect.positions() {
+            selection.insert(pos);
+        }
+        selection
+    }
+
+    /// Selects a filled polygon with corners at `points`,
+    /// including its edges. The last point connects back
+    /// to the first, and edges shouldn't cross each other.
+    /// ```
+    /// use tinycbg::Selection;
+    ///
+    /// let ramp = Selection::polygon(&[(2, 2), (12, 2), (2, 12)]);
+    /// assert!(ramp.contains((4, 4)));
+    /// assert!(ramp.contains((7, 7)));
+    /// assert!(!ramp.contains((9, 9)));
+    /// ```
+    pub fn polygon(points: &[(usize, usize)]) -> Self {
+        let mut selection = Selection::path(points);
+        if let (Some(&first), Some(&last)) = (points.first(), points.last()) {
+            selection.insert_line(signed(last), signed(first));
+        }
+        for (x, y) in Rect::FULL.positions() {
+            // Counts the edges crossed going right from the
+            // center of the tile, which is odd when inside
+            let (x, y) = (x as f32, y as f32);
+            let mut inside = false;
+            for (i, &(ax, ay)) in points.iter().enumerate() {
+                let (bx, by) = points[(i + 1) % points.len()];
+                let (ax, ay, bx, by) = (ax as f32, ay as f32, bx as f32, by as f32);
+                if (ay > y) != (by > y) && x < ax + (bx - ax) * (y - ay) / (by - ay) {
+                    inside = !inside;
+                }
+            }
+            if inside {
+                selection.insert((x as usize, y as usize));
+            }
+        }
+        selection
+    }
+
+    /// Selects a quadratic Bezier curve from `p0` to `p2`,
+    /// bending towards `p1`, with no gaps between tiles.
+    /// ```
+    /// use tinycbg::Selection;
+    ///
+    /// let curve = Selection::bezier((0, 15), (0, 0), (15, 0));
+    /// assert!(curve.contains((0, 15)) && curve.contains((15, 0)));
+    /// assert!(curve.contains((4, 4)));
+    /// assert!(!curve.contains((0, 0)));
+    /// ```
+    pub fn bezier(p0: (usize, usize), p1: (usize, usize), p2: (usize, usize)) -> Self {
+        let mut selection = Selection::new();
+        // Enough steps for every step to move at most one tile
+        let legs =
+            p0.0.abs_diff(p1.0) + p0.1.abs_diff(p1.1) + p1.0.abs_diff(p2.0) + p1.1.abs_diff(p2.1);
+        let steps = legs.max(1);
+        let point = |t: f32| {
+            let (u, [a, b, c]) = (1.0 - t, [p0, p1, p2].map(|(x, y)| (x as f32, y as f32)));
+            let x = u * u * a.0 + 2.0 * u * t * b.0 + t * t * c.0;
+            let y = u * u * a.1 + 2.0 * u * t * b.1 + t * t * c.1;
+            // Coordinates are never negative, so this rounds
+            ((x + 0.5) as isize, (y + 0.5) as isize)
+        };
+        let mut last = signed(p0);
+        for step in 1..=steps {
+            let next = point(step as f32 / steps as f32);
+            selection.insert_line(last, next);
+            last = next;
+        }
+        selection
+    }
+
+    #[cfg(feature = "std")]
+    /// Selects part of the outline of `Selection::circle()`,
+    /// going clockwise from `start_angle` to `end_angle`.
+    /// Angles are in degrees, with 0 pointing right
+    /// (towards higher `x`) and 90 pointing down.
+    /// ```
+    /// use tinycbg::Selection;
+    ///
+    /// let arc = Selection::arc((7, 7), 5, 0.0, 90.0);
+    /// assert!(arc.contains((12, 7)) && arc.contains((7, 12)));
+    /// assert!(!arc.contains((7, 2)) && !arc.contains((2, 7)));
+    ///
+    /// // Arcs can wrap past 0 degrees
+    /// let arc = Selection::arc((7, 7), 5, 270.0, 90.0);
+    /// assert!(arc.contains((7, 2)) && arc.contains((12, 7)));
+    /// assert!(!arc.contains((2, 7)));
+    /// ```
+    pub fn arc(center: (usize, usize), radius: usize, start_angle: f32, end_angle: f32) -> Self {
+        let start = start_angle.rem_euclid(360.0);
+        let sweep = (end_angle - start_angle).rem_euclid(360.0);
+        let mut selection = Selection::new();
+        for (x, y) in Selection::circle(center, radius).positions() {
+            let (dx, dy) = (x as f32 - center.0 as f32, y as f32 - center.1 as f32);
+            let angle = dy.atan2(dx).to_degrees();
+            if (angle - start).rem_euclid(360.0) <= sweep {
+                selection.insert((x, y));
+            }
+        }
+        selection
+    }
+
+    // Bresenham's line algorithm, which works for every
+    // octant by stepping along both axes with an error term.
+    fn insert_line(&mut self, (mut x, mut y): (isize, isize), (to_x, to_y): (isize, isize)) {
+        let dx = (to_x - x).abs();
+        let dy = -(to_y - y).abs();
+        let step_x = if x < to_x { 1 } else { -1 };
+        let step_y = if y < to_y { 1 } else { -1 };
+        let mut error = dx + dy;
+        loop {
+            self.insert_signed((x, y));
+            if x == to_x && y == to_y {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Adds `(x, y)` to the selection,
+    /// unless it's outside of the grid.
+    pub fn insert(&mut self, (x, y): (usize, usize)) {
+        if x < 16 && y < 16 {
+            self.rows[y] |= 1 << x;
+        }
+    }
+
+    fn insert_signed(&mut self, (x, y): (isize, isize)) {
+        if x >= 0 && y >= 0 {
+            self.insert((x as usize, y as usize));
+        }
+    }
+
+    /// Removes `(x, y)` from the selection.
+    pub fn remove(&mut self, (x, y): (usize, usize)) {
+        if x < 16 && y < 16 {
+            self.rows[y] &= !(1 << x);
+        }
+    }
+
+    /// Returns whether `(x, y)` is selected.
+    pub fn contains(&self, (x, y): (usize, usize)) -> bool {
+        x < 16 && y < 16 && self.rows[y] & (1 << x) != 0
+    }
+
+    /// Gets the number of selected tiles.
+    pub fn len(&self) -> usize {
+        self.rows.iter().map(|row| row.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.iter().all(|&row| row == 0)
+    }
+
+    // Gets the distance from `pos` to the nearest selected
+    // tile, counting diagonal steps as one like `Rect` does.
+    pub(crate) fn distance(&self, (x, y): (usize, usize)) -> usize {
+        self.positions()
+            .map(|(sx, sy)| x.abs_diff(sx).max(y.abs_diff(sy)))
+            .min()
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Iterates over every selected
+    /// position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + use<> {
+        let selection = *self;
+        Rect::FULL
+            .positions()
+            .filter(move |&pos| selection.contains(pos))
+    }
+}
+
+// The midpoint ellipse algorithm, which gets the points of
+// one quarter of the outline with integer maths, first while
+// the slope is shallow and then while it's steep.
+fn ellipse_quadrant(rx: i64, ry: i64) -> Vec<(i64, i64)> {
+    // Flat ellipses are straight lines, which would
+    // never leave the first loop when `rx` is 0
+    if rx == 0 {
+        return (0..=ry).map(|y| (0, y)).collect();
+    }
+    if ry == 0 {
+        return (0..=rx).map(|x| (x, 0)).collect();
+    }
+    if rx == ry {
+        return circle_quadrant(rx);
+    }
+
+    let mut points = Vec::new();
+    let (rx2, ry2) = (rx * rx, ry * ry);
+    let (mut x, mut y) = (0, ry);
+
+    // Decision values are scaled by 4 to stay whole numbers
+    let mut decision = 4 * ry2 - 4 * rx2 * ry + rx2;
+    while ry2 * x <= rx2 * y {
+        points.push((x, y));
+        if decision < 0 {
+            decision += 4 * ry2 * (2 * x + 3);
+        } else {
+            decision += 4 * ry2 * (2 * x + 3) - 8 * rx2 * (y - 1);
+            y -= 1;
+        }
+        x += 1;
+    }
+
+    decision = ry2 * (2 * x + 1) * (2 * x + 1) + 4 * rx2 * (y - 1) * (y - 1) - 4 * rx2 * ry2;
+    while y >= 0 {
+        points.push((x, y));
+        if decision > 0 {
+            decision += 4 * rx2 * (3 - 2 * y);
+        } else {
+            decision += 8 * ry2 * (x + 1) + 4 * rx2 * (3 - 2 * y);
+            x += 1;
+        }
+        y -= 1;
+    }
+    points
+}
+
+// The midpoint circle algorithm, which gets one eighth of
+// the outline and mirrors it along the diagonal, so circles
+// are the same after being rotated or transposed.
+fn circle_quadrant(radius: i64) -> Vec<(i64, i64)> {
+    let mut points = Vec::new();
+    let (mut x, mut y) = (radius, 0);
+    let mut decision = 1 - radius;
+    while x >= y {
+        points.push((x, y));
+        points.push((y, x));
+        y += 1;
+        if decision < 0 {
+            decision += 2 * y + 1;
+        } else {
+            x -= 1;
+            decision += 2 * (y - x) + 1;
+        }
+    }
+    points
+}
+
+fn signed((x, y): (usize, usize)) -> (isize, isize) {
+    (x as isize, y as isize)
+}
+
+impl CyberGrindPattern {
+    /// Sets every tile in `selection` to `tile`.
+    pub fn fill_selection(&mut self, selection: &Selection, tile: Tile) {
+        for pos in selection.positions() {
+            self[pos] = tile;
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{error::ShareCodeError, CyberGrindPattern};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// Gets the value of a base64 character, accepting
+// both the URL-safe and the standard alphabet.
+fn sextet(char: u8) -> Option<u32> {
+    match char {
+        b'A'..=b'Z' => Some((char - b'A') as u32),
+        b'a'..=b'z' => Some((char - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((char - b'0') as u32 + 52),
+        b'-' | b'+' => Some(62),
+        b'_' | b'/' => Some(63),
+        _ => None,
+    }
+}
+
+impl CyberGrindPattern {
+    /// Encodes the pattern as a share code, which can be pasted
+    /// into chat or URLs without getting mangled. Share codes
+    /// are the `.cgpb` format from `CyberGrindPattern::to_binary()`
+    /// encoded as URL-safe base64, without padding.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let code = CyberGrindPattern::new().to_share_code();
+    /// assert!(code.starts_with("Q0dQQg"));
+    /// assert_eq!(code.len(), 519);
+    /// ```
+    pub fn to_share_code(&self) -> String {
+        let bytes = self.to_binary();
+        let mut code = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+                group | (byte as u32) << (16 - i * 8)
+            });
+            for i in 0..=chunk.len() {
+                code.push(ALPHABET[(group >> (18 - i * 6)) as usize & 63] as char);
+            }
+        }
+        code
+    }
+
+    /// Decodes a share code made by `CyberGrindPattern::to_share_code()`.
+    /// Whitespace, such as line breaks added by chat apps,
+    /// is ignored, and standard base64 with padding is
+    /// accepted too.
+    /// ```
+    /// use tinycbg::CyberGrindPattern;
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(9, 9)].set_height(25);
+    /// let read = CyberGrindPattern::from_share_code(&pat.to_share_code()).unwrap();
+    /// assert_eq!(read[(9, 9)].height(), 25);
+    /// ```
+    pub fn from_share_code(code: &str) -> Result<CyberGrindPattern, ShareCodeError> {
+        let mut bytes = Vec::with_capacity(code.len() * 3 / 4);
+        let (mut group, mut bits) = (0u32, 0);
+        let mut padding = false;
+        for (index, char) in code.bytes().enumerate() {
+            if char.is_ascii_whitespace() {
+                continue;
+            }
+            let value = match sextet(char) {
+                Some(value) if !padding => value,
+                _ if char == b'=' => {
+                    padding = true;
+                    continue;
+                }
+                _ => return Err(ShareCodeError::InvalidChar { index }),
+            };
+            group = group << 6 | value;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((group >> bits) as u8);
+                group &= (1 << bits) - 1;
+            }
+        }
+        Ok(CyberGrindPattern::parse_binary(&bytes)?)
+    }
+}
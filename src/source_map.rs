@@ -0,0 +1,105 @@
+use crate::{
+    error::ParseError, normal_fmt::ParseSink, CyberGrindPattern, ParseOptions, Prefab, Span,
+};
+
+/// Which half of a `.cgp` file a value is in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Height,
+    Prefab,
+}
+
+/// Where each tile's height and prefab were found
+/// in the source text. Made by
+/// `CyberGrindPattern::parse_mapped()`.
+/// ```
+/// use tinycbg::{CyberGrindPattern, Layer};
+///
+/// let bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+/// let (pattern, map) = CyberGrindPattern::parse_mapped(&bytes).unwrap();
+///
+/// let span = map.height_span(0);
+/// assert_eq!(&bytes[span.start..span.end], b"(-50)");
+/// assert_eq!(map.find(span.start + 2), Some((0, Layer::Height)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SourceMap {
+    heights: [Span; 256],
+    prefabs: [Span; 256],
+}
+
+impl SourceMap {
+    /// Gets the span of the height of the tile at `index`.
+    pub fn height_span(&self, index: usize) -> Span {
+        self.heights[index]
+    }
+
+    /// Gets the span of the prefab of the tile at `index`.
+    pub fn prefab_span(&self, index: usize) -> Span {
+        self.prefabs[index]
+    }
+
+    /// Gets the span of either half of the tile at `index`.
+    pub fn span(&self, index: usize, layer: Layer) -> Span {
+        match layer {
+            Layer::Height => self.heights[index],
+            Layer::Prefab => self.prefabs[index],
+        }
+    }
+
+    /// Finds the tile whose height or prefab
+    /// covers the byte at `offset`, if any.
+    pub fn find(&self, offset: usize) -> Option<(usize, Layer)> {
+        [
+            (&self.heights, Layer::Height),
+            (&self.prefabs, Layer::Prefab),
+        ]
+        .into_iter()
+        .find_map(|(spans, layer)| {
+            let index = spans.partition_point(|span| span.end <= offset);
+            let span = spans.get(index)?;
+            (span.start <= offset).then_some((index, layer))
+        })
+    }
+}
+
+struct MapSink {
+    pattern: CyberGrindPattern,
+    map: SourceMap,
+}
+
+impl ParseSink for MapSink {
+    fn height(&mut self, index: usize, height: i8, span: Span) {
+        self.pattern[index].set_height(height);
+        self.map.heights[index] = span;
+    }
+    fn prefab(&mut self, index: usize, prefab: Prefab, span: Span) {
+        self.pattern[index].set_prefab(prefab);
+        self.map.prefabs[index] = span;
+    }
+}
+
+impl CyberGrindPattern {
+    /// Same as `CyberGrindPattern::parse()`, but also returns
+    /// where every height and prefab was found.
+    pub fn parse_mapped(bytes: &[u8]) -> Result<(Self, SourceMap), ParseError> {
+        Self::parse_mapped_with(bytes, &ParseOptions::default())
+    }
+
+    /// Same as `CyberGrindPattern::parse_with()`, but also
+    /// returns where every height and prefab was found.
+    pub fn parse_mapped_with(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Self, SourceMap), ParseError> {
+        let mut sink = MapSink {
+            pattern: CyberGrindPattern::new(),
+            map: SourceMap {
+                heights: [Span::default(); 256],
+                prefabs: [Span::default(); 256],
+            },
+        };
+        Self::parse_into(bytes, &mut sink, options)?;
+        Ok((sink.pattern, sink.map))
+    }
+}
@@ -0,0 +1,260 @@
+use alloc::{vec, vec::Vec};
+
+#[cfg(feature = "std")]
+use crate::Rng;
+use crate::{CyberGrindPattern, Rect};
+
+/// A way of repeating part of a pattern
+/// over the rest of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    /// The left half is mirrored onto the right half
+    MirrorX,
+    /// The top half is mirrored onto the bottom half
+    MirrorY,
+    /// The top left quadrant is mirrored onto the other three
+    MirrorBoth,
+    /// The left half is rotated 180 degrees onto the right half
+    Rotate180,
+    /// The top left quadrant is rotated 90, 180
+    /// and 270 degrees onto the other three
+    Rotate90,
+}
+
+impl Symmetry {
+    /// Gets the part of the pattern which is
+    /// repeated over the rest of it.
+    pub fn source(&self) -> Rect {
+        match self {
+            Symmetry::MirrorX | Symmetry::Rotate180 => Rect::new(0, 0, 8, 16),
+            Symmetry::MirrorY => Rect::new(0, 0, 16, 8),
+            Symmetry::MirrorBoth | Symmetry::Rotate90 => Rect::new(0, 0, 8, 8),
+        }
+    }
+
+    /// Gets the parts the pattern is split into,
+    /// starting with `Symmetry::source()`.
+    pub fn parts(&self) -> Vec<Rect> {
+        match self {
+            Symmetry::MirrorX | Symmetry::Rotate180 => {
+                vec![Rect::new(0, 0, 8, 16), Rect::new(8, 0, 8, 16)]
+            }
+            Symmetry::MirrorY => vec![Rect::new(0, 0, 16, 8), Rect::new(0, 8, 16, 8)],
+            Symmetry::MirrorBoth | Symmetry::Rotate90 => vec![
+                Rect::new(0, 0, 8, 8),
+                Rect::new(8, 0, 8, 8),
+                Rect::new(0, 8, 8, 8),
+                Rect::new(8, 8, 8, 8),
+            ],
+        }
+    }
+
+    /// Gets every position `(x, y)` is copied to,
+    /// not including `(x, y)` itself. For positions
+    /// outside of the source part, this gets the
+    /// positions which match it instead.
+    pub fn images(&self, (x, y): (usize, usize)) -> Vec<(usize, usize)> {
+        match self {
+            Symmetry::MirrorX => vec![(15 - x, y)],
+            Symmetry::MirrorY => vec![(x, 15 - y)],
+            Symmetry::MirrorBoth => vec![(15 - x, y), (x, 15 - y), (15 - x, 15 - y)],
+            Symmetry::Rotate180 => vec![(15 - x, 15 - y)],
+            Symmetry::Rotate90 => vec![(15 - y, x), (15 - x, 15 - y), (y, 15 - x)],
+        }
+    }
+}
+
+/// How many times a pattern repeats
+/// when rotated around its center.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SymmetryOrder {
+    /// The left half is rotated 180 degrees onto the right half
+    Two,
+    /// The top left quadrant is rotated 90, 180
+    /// and 270 degrees onto the other three
+    Four,
+}
+
+impl From<SymmetryOrder> for Symmetry {
+    fn from(order: SymmetryOrder) -> Self {
+        match order {
+            SymmetryOrder::Two => Symmetry::Rotate180,
+            SymmetryOrder::Four => Symmetry::Rotate90,
+        }
+    }
+}
+
+/// One of the four 8x8 corners of a pattern.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Quadrant {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Quadrant {
+    /// Gets the tiles inside the quadrant.
+    pub fn rect(&self) -> Rect {
+        match self {
+            Quadrant::TopLeft => Rect::new(0, 0, 8, 8),
+            Quadrant::TopRight => Rect::new(8, 0, 8, 8),
+            Quadrant::BottomLeft => Rect::new(0, 8, 8, 8),
+            Quadrant::BottomRight => Rect::new(8, 8, 8, 8),
+        }
+    }
+}
+
+/// How evenly the enemy spawns of a pattern are spread,
+/// made by `CyberGrindPattern::check_spawn_balance()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BalanceReport {
+    /// Number of spawns in each of `Symmetry::parts()`
+    pub counts: Vec<usize>,
+    /// Whether the counts are all within
+    /// the tolerance of each other
+    pub balanced: bool,
+    /// Spawns which don't have the same
+    /// spawn on the other side
+    pub unmatched: Vec<(usize, usize)>,
+}
+
+impl CyberGrindPattern {
+    /// Checks that enemy spawns are spread evenly between the
+    /// parts of the pattern `symmetry` splits it into. The
+    /// pattern is balanced if no part has more than `tolerance`
+    /// spawns more than another. Spawns whose mirrored or
+    /// rotated positions don't hold the same prefab are
+    /// reported too, even if the pattern is balanced.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Prefab, Symmetry};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(1, 1)].set_prefab(Prefab::Melee);
+    /// pat[(14, 1)].set_prefab(Prefab::Melee);
+    /// pat[(3, 3)].set_prefab(Prefab::Projectile);
+    ///
+    /// let report = pat.check_spawn_balance(Symmetry::MirrorX, 1);
+    /// assert_eq!(report.counts, [2, 1]);
+    /// assert!(report.balanced);
+    /// assert_eq!(report.unmatched, [(3, 3)]);
+    /// ```
+    pub fn check_spawn_balance(&self, symmetry: Symmetry, tolerance: usize) -> BalanceReport {
+        let counts: Vec<usize> = symmetry
+            .parts()
+            .iter()
+            .map(|part| {
+                part.positions()
+                    .filter(|&pos| self[pos].prefab().spawns_enemy())
+                    .count()
+            })
+            .collect();
+        let min = counts.iter().copied().min().unwrap_or_default();
+        let max = counts.iter().copied().max().unwrap_or_default();
+
+        let mut unmatched = Vec::new();
+        for pos in Rect::FULL.positions() {
+            let prefab = self[pos].prefab();
+            if !prefab.spawns_enemy() {
+                continue;
+            }
+            if symmetry
+                .images(pos)
+                .iter()
+                .any(|&other| self[other].prefab() != prefab)
+            {
+                unmatched.push(pos);
+            }
+        }
+
+        BalanceReport {
+            counts,
+            balanced: max - min <= tolerance,
+            unmatched,
+        }
+    }
+
+    /// Copies the source part of `symmetry` over the
+    /// rest of the pattern, overwriting it.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Prefab, Symmetry};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(2, 3)].set_prefab(Prefab::Melee);
+    /// pat.symmetrize(Symmetry::Rotate90);
+    /// assert_eq!(pat[(12, 2)].prefab(), Prefab::Melee);
+    /// assert_eq!(pat[(13, 12)].prefab(), Prefab::Melee);
+    /// assert_eq!(pat[(3, 13)].prefab(), Prefab::Melee);
+    /// ```
+    pub fn symmetrize(&mut self, symmetry: Symmetry) {
+        for pos in symmetry.source().positions() {
+            for image in symmetry.images(pos) {
+                self[image] = self[pos];
+            }
+        }
+    }
+
+    /// Mirrors `quadrant` onto the other three, so the
+    /// pattern ends up symmetric along both axes.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Quadrant, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(13, 2)] = Tile::with_height(10);
+    /// pat.mirror_quadrant(Quadrant::TopRight);
+    /// assert_eq!(pat[(2, 2)].height(), 10);
+    /// assert_eq!(pat[(13, 13)].height(), 10);
+    /// assert_eq!(pat[(2, 13)].height(), 10);
+    /// ```
+    pub fn mirror_quadrant(&mut self, quadrant: Quadrant) {
+        for pos in quadrant.rect().positions() {
+            for image in Symmetry::MirrorBoth.images(pos) {
+                self[image] = self[pos];
+            }
+        }
+    }
+
+    /// Rotates part of the pattern onto the rest of it,
+    /// so it looks the same after being rotated by
+    /// 180 degrees, or by 90 degrees with `SymmetryOrder::Four`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, SymmetryOrder, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(2, 3)] = Tile::with_height(10);
+    /// pat.apply_rotational_symmetry(SymmetryOrder::Two);
+    /// assert_eq!(pat[(13, 12)].height(), 10);
+    /// assert_eq!(pat[(12, 2)].height(), 0);
+    /// ```
+    pub fn apply_rotational_symmetry(&mut self, order: SymmetryOrder) {
+        self.symmetrize(order.into());
+    }
+
+    #[cfg(feature = "std")]
+    /// Runs `generate` on an empty pattern and makes
+    /// the result symmetric, so randomly generated
+    /// arenas still look designed. Only the source
+    /// part of `symmetry` is kept from `generate`.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, PillarConfig, Prefab, Rng, Symmetry};
+    ///
+    /// let pat = CyberGrindPattern::generate_symmetric(&mut Rng::new(4), Symmetry::MirrorX, |pat, rng| {
+    ///     pat.scatter_pillars(rng, 5, PillarConfig::default());
+    ///     pat.scatter_prefabs(rng, Prefab::Projectile, 3, 4.0, |_| true);
+    /// });
+    /// for (x, y) in (0..16).flat_map(|y| (0..16).map(move |x| (x, y))) {
+    ///     assert_eq!(pat[(x, y)], pat[(15 - x, y)]);
+    /// }
+    /// ```
+    pub fn generate_symmetric<F: FnOnce(&mut CyberGrindPattern, &mut Rng)>(
+        rng: &mut Rng,
+        symmetry: Symmetry,
+        generate: F,
+    ) -> Self {
+        let mut pattern = CyberGrindPattern::new();
+        generate(&mut pattern, rng);
+        pattern.symmetrize(symmetry);
+        pattern
+    }
+}
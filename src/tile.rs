@@ -1,13 +1,14 @@
-use std::{
+use alloc::string::String;
+use core::{
     fmt::{Debug, Display, Formatter, Result as FmtRes},
-    ops::{Add, Sub},
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    str::FromStr,
 };
 
-use crate::error::ParseErrorType;
+use crate::error::{HeightError, ParseErrorType};
 
 /// A prefab is a spawning option for a tile.
 /// There can only be one prefab per tile.
-#[repr(u8)]
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub enum Prefab {
     /// Will spawn a projectile enemy on the tile
@@ -25,69 +26,246 @@ pub enum Prefab {
     /// `CyberGrindPattern::new()`.
     #[default]
     None,
+    /// A prefab character this crate doesn't know, such
+    /// as one added by a mod or a newer game version.
+    /// Made when parsing with `UnknownPrefab::Keep` or
+    /// with `Prefab::unknown()`, so the character is
+    /// written back out unchanged.
+    Unknown(UnknownChar),
 }
 
-impl Prefab {
-    pub fn char(self) -> char {
-        self.into()
+/// The character of a `Prefab::Unknown`. It's always a
+/// printable ASCII character which no known prefab uses.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnknownChar(u8);
+
+impl UnknownChar {
+    pub const fn byte(self) -> u8 {
+        self.0
     }
-    pub fn byte(self) -> u8 {
-        self.into()
+    pub const fn char(self) -> char {
+        self.0 as char
     }
 }
 
-impl From<Prefab> for char {
-    fn from(val: Prefab) -> char {
-        match val {
-            Prefab::HideousMass => 'H',
-            Prefab::Projectile => 'p',
-            Prefab::Melee => 'n',
-            Prefab::Stairs => 's',
-            Prefab::JumpPad => 'j',
-            Prefab::None => '0',
-        }
+impl Debug for UnknownChar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtRes {
+        write!(f, "{:?}", self.char())
     }
 }
-impl From<Prefab> for u8 {
-    fn from(val: Prefab) -> u8 {
-        match val {
+
+impl Prefab {
+    /// Every prefab, in declaration order.
+    /// ```
+    /// use tinycbg::Prefab;
+    ///
+    /// let enemies = Prefab::ALL.iter().filter(|pf| pf.spawns_enemy()).count();
+    /// assert_eq!(enemies, 3);
+    /// ```
+    pub const ALL: [Prefab; 6] = [
+        Prefab::Projectile,
+        Prefab::Melee,
+        Prefab::HideousMass,
+        Prefab::JumpPad,
+        Prefab::Stairs,
+        Prefab::None,
+    ];
+
+    /// Returns an iterator over every prefab.
+    /// Same as `Prefab::ALL.into_iter()`
+    pub fn iter() -> core::array::IntoIter<Prefab, 6> {
+        Self::ALL.into_iter()
+    }
+
+    /// Returns whether the prefab spawns
+    /// an enemy, rather than placing terrain
+    /// or nothing at all.
+    pub const fn spawns_enemy(self) -> bool {
+        matches!(
+            self,
+            Prefab::Projectile | Prefab::Melee | Prefab::HideousMass
+        )
+    }
+
+    pub const fn char(self) -> char {
+        self.byte() as char
+    }
+    pub const fn byte(self) -> u8 {
+        match self {
             Prefab::HideousMass => b'H',
             Prefab::Projectile => b'p',
             Prefab::Melee => b'n',
             Prefab::Stairs => b's',
-            Prefab::JumpPad => b'j',
+            Prefab::JumpPad => b'J',
             Prefab::None => b'0',
+            Prefab::Unknown(char) => char.byte(),
+        }
+    }
+
+    /// Gets the prefab with the `.cgp` character `byte`,
+    /// or `Prefab::Unknown` if there isn't one. Returns
+    /// `None` if `byte` isn't printable ASCII, like
+    /// `Prefab::unknown()`.
+    /// ```
+    /// use tinycbg::Prefab;
+    ///
+    /// assert_eq!(Prefab::from_byte(b'J'), Some(Prefab::JumpPad));
+    /// assert_eq!(Prefab::from_byte(b'T'), Prefab::unknown(b'T'));
+    /// assert_eq!(Prefab::from_byte(b'\n'), None);
+    /// ```
+    pub const fn from_byte(byte: u8) -> Option<Prefab> {
+        match Prefab::known(byte) {
+            Some(prefab) => Some(prefab),
+            None => Prefab::unknown(byte),
+        }
+    }
+
+    /// Makes a `Prefab::Unknown` with the character `byte`.
+    /// Returns `None` if `byte` isn't printable ASCII, or
+    /// if it's the character of a known prefab.
+    /// ```
+    /// use tinycbg::Prefab;
+    ///
+    /// assert_eq!(Prefab::unknown(b'T').unwrap().char(), 'T');
+    /// assert_eq!(Prefab::unknown(b'J'), None);
+    /// assert_eq!(Prefab::unknown(b' '), None);
+    /// ```
+    pub const fn unknown(byte: u8) -> Option<Prefab> {
+        if byte.is_ascii_graphic() && Prefab::known(byte).is_none() {
+            Some(Prefab::Unknown(UnknownChar(byte)))
+        } else {
+            None
+        }
+    }
+
+    // Gets the known prefab with the `.cgp` character `byte`.
+    pub(crate) const fn known(byte: u8) -> Option<Prefab> {
+        match byte {
+            b'0' => Some(Prefab::None),
+            b'n' => Some(Prefab::Melee),
+            b'p' => Some(Prefab::Projectile),
+            b'H' => Some(Prefab::HideousMass),
+            b'J' => Some(Prefab::JumpPad),
+            b's' => Some(Prefab::Stairs),
+            _ => None,
+        }
+    }
+
+    /// Gets the in-game name of a prefab,
+    /// in lowercase.
+    /// ```
+    /// use tinycbg::Prefab;
+    ///
+    /// assert_eq!(Prefab::JumpPad.name(), "jump pad");
+    /// ```
+    pub fn name(self) -> &'static str {
+        match self {
+            Prefab::HideousMass => "hideous mass",
+            Prefab::Projectile => "projectile",
+            Prefab::Melee => "melee",
+            Prefab::Stairs => "stairs",
+            Prefab::JumpPad => "jump pad",
+            Prefab::None => "none",
+            Prefab::Unknown(_) => "unknown",
         }
     }
 }
+
+impl From<Prefab> for char {
+    fn from(val: Prefab) -> char {
+        char::from(val.byte())
+    }
+}
+impl From<Prefab> for u8 {
+    fn from(val: Prefab) -> u8 {
+        val.byte()
+    }
+}
+/// Formats the prefab as its `.cgp` character.
+/// The alternate flag (`{:#}`) formats it as
+/// its in-game name instead.
+/// ```
+/// use tinycbg::Prefab;
+///
+/// assert_eq!(format!("{}", Prefab::Melee), "n");
+/// assert_eq!(format!("{:#}", Prefab::HideousMass), "hideous mass");
+/// ```
 impl Display for Prefab {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtRes {
-        f.write_str(match self {
-            Prefab::HideousMass => "H",
-            Prefab::Projectile => "p",
-            Prefab::Melee => "n",
-            Prefab::Stairs => "s",
-            Prefab::JumpPad => "j",
-            Prefab::None => "0",
-        })
+        if f.alternate() {
+            return f.write_str(self.name());
+        }
+        write!(f, "{}", self.char())
     }
 }
 
 impl TryFrom<u8> for Prefab {
     type Error = ParseErrorType;
     fn try_from(byte: u8) -> Result<Self, ParseErrorType> {
-        match byte {
-            b'0' => Ok(Prefab::None),
-            b'n' => Ok(Prefab::Melee),
-            b'p' => Ok(Prefab::Projectile),
-            b'H' => Ok(Prefab::HideousMass),
-            b'J' => Ok(Prefab::JumpPad),
-            b's' => Ok(Prefab::Stairs),
+        Prefab::known(byte).ok_or(ParseErrorType::InvalidPrefab)
+    }
+}
+
+/// Parses a prefab from its in-game name or its
+/// `.cgp` character. Case, spaces, dashes and
+/// underscores are ignored, and a few common
+/// aliases are accepted.
+/// ```
+/// use tinycbg::Prefab;
+///
+/// assert_eq!("Jump Pad".parse(), Ok(Prefab::JumpPad));
+/// assert_eq!("hideous_mass".parse(), Ok(Prefab::HideousMass));
+/// assert_eq!("ranged".parse(), Ok(Prefab::Projectile));
+/// assert_eq!("n".parse(), Ok(Prefab::Melee));
+/// assert!("maurice".parse::<Prefab>().is_err());
+/// ```
+impl FromStr for Prefab {
+    type Err = ParseErrorType;
+    fn from_str(s: &str) -> Result<Self, ParseErrorType> {
+        let name: String = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        match name.as_str() {
+            "none" | "empty" | "0" => Ok(Prefab::None),
+            "melee" | "n" => Ok(Prefab::Melee),
+            "projectile" | "ranged" | "p" => Ok(Prefab::Projectile),
+            "hideousmass" | "hideous" | "mass" | "h" => Ok(Prefab::HideousMass),
+            "jumppad" | "jump" | "pad" | "j" => Ok(Prefab::JumpPad),
+            "stairs" | "stair" | "s" => Ok(Prefab::Stairs),
             _ => Err(ParseErrorType::InvalidPrefab),
         }
     }
 }
 
+/// Builder for a `Tile`, created
+/// with `Tile::builder()`.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct TileBuilder {
+    height: i8,
+    prefab: Prefab,
+}
+
+impl TileBuilder {
+    /// Sets the height of the tile being built.
+    pub const fn height(self, height: i8) -> Self {
+        TileBuilder { height, ..self }
+    }
+
+    /// Sets the prefab of the tile being built.
+    pub const fn prefab(self, prefab: Prefab) -> Self {
+        TileBuilder { prefab, ..self }
+    }
+
+    /// Builds the tile. Panics if the height
+    /// is greater than 50 or less than -50.
+    pub const fn build(self) -> Tile {
+        Tile::new(self.height, self.prefab)
+    }
+}
+
 /// A tile on the CyberGrindPattern.
 /// Contains a height anywhere between
 /// -50 and 50, and an optional prefab.
@@ -95,24 +273,33 @@ impl TryFrom<u8> for Prefab {
 /// use tinycbg::Prefab;
 /// tinycbg::Tile::new(51, Prefab::None);
 /// ```
-#[derive(Default, Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Tile {
     height: i8,
-    prefab: Prefab,
+    // Stored as its `.cgp` character, which keeps
+    // tiles two bytes wide with `Prefab::Unknown`
+    prefab: u8,
+}
+
+impl Default for Tile {
+    fn default() -> Self {
+        Tile::with_prefab(Prefab::None)
+    }
 }
 
 impl Debug for Tile {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtRes {
-        if self.prefab == Prefab::None {
+        if self.prefab() == Prefab::None {
             write!(f, "[{}]", self.height)
         } else {
-            let letter = match self.prefab {
+            let letter = match self.prefab() {
                 Prefab::HideousMass => 'M',
                 Prefab::Projectile => 'p',
                 Prefab::Melee => 'm',
                 Prefab::Stairs => 's',
                 Prefab::JumpPad => 'j',
                 Prefab::None => ' ',
+                Prefab::Unknown(_) => '?',
             };
 
             write!(f, "[{}, {}]", self.height, letter)
@@ -121,7 +308,11 @@ impl Debug for Tile {
 }
 
 impl Tile {
-    fn check_height(height: i8) {
+    pub(crate) const fn is_valid_height(height: i8) -> bool {
+        height >= -50 && height <= 50
+    }
+
+    const fn check_height(height: i8) {
         assert!(height <= 50, "Height cannot be greater than 50");
         assert!(height >= -50, "Height cannot be less than -50");
     }
@@ -129,9 +320,32 @@ impl Tile {
     /// Creates a new tile with height `height`
     /// and prefab `prefab`. Panics if `height`
     /// is greater than 50 or less than -50
-    pub fn new(height: i8, prefab: Prefab) -> Self {
+    pub const fn new(height: i8, prefab: Prefab) -> Self {
         Tile::check_height(height);
-        Tile { height, prefab }
+        Tile {
+            height,
+            prefab: prefab.byte(),
+        }
+    }
+
+    /// Creates a new tile with height `height` and
+    /// prefab `prefab`, or fails if `height` is
+    /// greater than 50 or less than -50.
+    /// ```
+    /// use tinycbg::{Prefab, Tile};
+    /// use tinycbg::error::HeightError;
+    ///
+    /// assert!(Tile::try_new(50, Prefab::Melee).is_ok());
+    /// assert_eq!(Tile::try_new(60, Prefab::None), Err(HeightError { height: 60 }));
+    /// ```
+    pub const fn try_new(height: i8, prefab: Prefab) -> Result<Self, HeightError> {
+        if !Tile::is_valid_height(height) {
+            return Err(HeightError { height });
+        }
+        Ok(Tile {
+            height,
+            prefab: prefab.byte(),
+        })
     }
 
     /// Creates a new tile with height
@@ -139,44 +353,139 @@ impl Tile {
     /// Panics if `height` is greater
     /// than 50 or less than -50.
     /// Equvalent to `Tile::from(height)`.
-    pub fn with_height(height: i8) -> Self {
+    pub const fn with_height(height: i8) -> Self {
         Tile::check_height(height);
         Tile {
             height,
-            prefab: Prefab::default(),
+            prefab: Prefab::None.byte(),
         }
     }
 
     /// Creates a new tile with prefab
     /// `prefab` and a height of 0.
     /// Equvalent to `Tile::from(prefab)`.
-    pub fn with_prefab(prefab: Prefab) -> Self {
+    pub const fn with_prefab(prefab: Prefab) -> Self {
         Tile {
-            height: i8::default(),
-            prefab,
+            height: 0,
+            prefab: prefab.byte(),
+        }
+    }
+
+    /// Creates a builder for a tile, starting
+    /// at a height of 0 and no prefab.
+    /// ```
+    /// use tinycbg::{Prefab, Tile};
+    ///
+    /// const PAD: Tile = Tile::builder().height(20).prefab(Prefab::JumpPad).build();
+    /// assert_eq!(PAD, Tile::new(20, Prefab::JumpPad));
+    /// ```
+    pub const fn builder() -> TileBuilder {
+        TileBuilder {
+            height: 0,
+            prefab: Prefab::None,
         }
     }
 
     /// Sets an existing tile's height to `new_height`.
     /// Panics if `new_height` is greater than 50 or less than -50.
-    pub fn set_height(&mut self, new_height: i8) {
+    pub const fn set_height(&mut self, new_height: i8) {
         Tile::check_height(new_height);
         self.height = new_height
     }
 
+    /// Sets an existing tile's height to `new_height`, or
+    /// fails without changing it if `new_height` is
+    /// greater than 50 or less than -50.
+    pub const fn try_set_height(&mut self, new_height: i8) -> Result<(), HeightError> {
+        if !Tile::is_valid_height(new_height) {
+            return Err(HeightError { height: new_height });
+        }
+        self.height = new_height;
+        Ok(())
+    }
+
     /// Sets an existing tile's prefab to `new_prefab`.
-    pub fn set_prefab(&mut self, new_prefab: Prefab) {
-        self.prefab = new_prefab
+    pub const fn set_prefab(&mut self, new_prefab: Prefab) {
+        self.prefab = new_prefab.byte()
+    }
+
+    /// Adds `rhs` to the tile's height,
+    /// stopping at 50 or -50 instead of panicking.
+    /// ```
+    /// use tinycbg::Tile;
+    ///
+    /// assert_eq!(Tile::with_height(45).saturating_add(10).height(), 50);
+    /// assert_eq!(Tile::with_height(-45).saturating_add(-10).height(), -50);
+    /// ```
+    pub const fn saturating_add(self, rhs: i8) -> Tile {
+        let height = self.height.saturating_add(rhs);
+        Tile {
+            height: if height > 50 {
+                50
+            } else if height < -50 {
+                -50
+            } else {
+                height
+            },
+            prefab: self.prefab,
+        }
+    }
+
+    /// Subtracts `rhs` from the tile's height,
+    /// stopping at 50 or -50 instead of panicking.
+    pub const fn saturating_sub(self, rhs: i8) -> Tile {
+        match rhs.checked_neg() {
+            Some(rhs) => self.saturating_add(rhs),
+            // Subtracting -128 always reaches 50
+            None => self.saturating_add(i8::MAX),
+        }
+    }
+
+    /// Adds `rhs` to the tile's height, or returns
+    /// `None` if it would go past 50 or -50.
+    /// ```
+    /// use tinycbg::Tile;
+    ///
+    /// let mut tower = Tile::with_height(0);
+    /// while let Some(taller) = tower.checked_add(15) {
+    ///     tower = taller;
+    /// }
+    /// assert_eq!(tower.height(), 45);
+    /// ```
+    pub const fn checked_add(self, rhs: i8) -> Option<Tile> {
+        match self.height.checked_add(rhs) {
+            Some(height) if Tile::is_valid_height(height) => Some(Tile {
+                height,
+                prefab: self.prefab,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Subtracts `rhs` from the tile's height, or
+    /// returns `None` if it would go past 50 or -50.
+    pub const fn checked_sub(self, rhs: i8) -> Option<Tile> {
+        match self.height.checked_sub(rhs) {
+            Some(height) if Tile::is_valid_height(height) => Some(Tile {
+                height,
+                prefab: self.prefab,
+            }),
+            _ => None,
+        }
     }
 
     /// Gets the height of a tile.
-    pub fn height(self) -> i8 {
+    pub const fn height(self) -> i8 {
         self.height
     }
 
     /// Gets the prefab of a tile.
-    pub fn prefab(self) -> Prefab {
-        self.prefab
+    pub const fn prefab(self) -> Prefab {
+        match Prefab::from_byte(self.prefab) {
+            Some(prefab) => prefab,
+            // Only valid prefab characters are ever stored
+            None => unreachable!(),
+        }
     }
 }
 
@@ -185,17 +494,14 @@ impl From<i8> for Tile {
         Tile::check_height(height);
         Self {
             height,
-            prefab: Prefab::default(),
+            prefab: Prefab::default().byte(),
         }
     }
 }
 
 impl From<Prefab> for Tile {
     fn from(prefab: Prefab) -> Self {
-        Self {
-            height: i8::default(),
-            prefab,
-        }
+        Tile::with_prefab(prefab)
     }
 }
 
@@ -228,3 +534,49 @@ impl Sub<i8> for Tile {
         }
     }
 }
+
+/// Adds to the tile's height in place.
+/// Will panic if `height` becomes
+/// greater than 50 or less than -50
+/// ```
+/// use tinycbg::{CyberGrindPattern, Tile};
+///
+/// let mut pat = CyberGrindPattern::new();
+/// for row in &mut pat {
+///     for tile in row {
+///         *tile += 3;
+///     }
+/// }
+/// assert_eq!(pat[(9, 4)], Tile::with_height(3));
+/// ```
+impl AddAssign<i8> for Tile {
+    fn add_assign(&mut self, rhs: i8) {
+        *self = *self + rhs;
+    }
+}
+
+/// Subtracts from the tile's height in place.
+/// Will panic if `height` becomes
+/// greater than 50 or less than -50
+impl SubAssign<i8> for Tile {
+    fn sub_assign(&mut self, rhs: i8) {
+        *self = *self - rhs;
+    }
+}
+
+/// Flips the tile's height, turning
+/// towers into pits and pits into towers.
+/// ```
+/// use tinycbg::{Prefab, Tile};
+///
+/// assert_eq!(-Tile::new(20, Prefab::Melee), Tile::new(-20, Prefab::Melee));
+/// ```
+impl Neg for Tile {
+    type Output = Tile;
+    fn neg(self) -> Self::Output {
+        Self {
+            height: -self.height,
+            prefab: self.prefab,
+        }
+    }
+}
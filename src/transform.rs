@@ -0,0 +1,132 @@
+use crate::{CyberGrindPattern, Rect};
+
+impl CyberGrindPattern {
+    // Moves the tile at every `(x, y)` to `to(x, y)`,
+    // which must visit every position exactly once.
+    fn remap(&mut self, to: impl Fn(usize, usize) -> (usize, usize)) {
+        let old = *self;
+        for (x, y) in Rect::FULL.positions() {
+            self[to(x, y)] = old[(x, y)];
+        }
+    }
+
+    /// Rotates the pattern 90 degrees clockwise,
+    /// so the top row becomes the right column.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(2, 0)] = Tile::with_height(10);
+    /// pat.rotate_cw();
+    /// assert_eq!(pat[(15, 2)].height(), 10);
+    /// ```
+    pub fn rotate_cw(&mut self) {
+        self.remap(|x, y| (15 - y, x));
+    }
+
+    /// Rotates the pattern 90 degrees counterclockwise,
+    /// so the top row becomes the left column.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(2, 0)] = Tile::with_height(10);
+    /// pat.rotate_ccw();
+    /// assert_eq!(pat[(0, 13)].height(), 10);
+    /// ```
+    pub fn rotate_ccw(&mut self) {
+        self.remap(|x, y| (y, 15 - x));
+    }
+
+    /// Rotates the pattern 180 degrees.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(2, 0)] = Tile::with_height(10);
+    /// pat.rotate_180();
+    /// assert_eq!(pat[(13, 15)].height(), 10);
+    /// ```
+    pub fn rotate_180(&mut self) {
+        self.tiles.reverse();
+    }
+
+    /// Mirrors the pattern from left to right.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(2, 5)] = Tile::with_height(10);
+    /// pat.flip_horizontal();
+    /// assert_eq!(pat[(13, 5)].height(), 10);
+    /// ```
+    pub fn flip_horizontal(&mut self) {
+        for row in self.tiles.chunks_exact_mut(16) {
+            row.reverse();
+        }
+    }
+
+    /// Mirrors the pattern from top to bottom.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(2, 5)] = Tile::with_height(10);
+    /// pat.flip_vertical();
+    /// assert_eq!(pat[(2, 10)].height(), 10);
+    /// ```
+    pub fn flip_vertical(&mut self) {
+        self.remap(|x, y| (x, 15 - y));
+    }
+
+    /// Mirrors the pattern along the diagonal from
+    /// the top left to the bottom right corner,
+    /// swapping rows with columns.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(2, 5)] = Tile::with_height(10);
+    /// pat.transpose();
+    /// assert_eq!(pat[(5, 2)].height(), 10);
+    /// ```
+    pub fn transpose(&mut self) {
+        self.remap(|x, y| (y, x));
+    }
+
+    /// Moves every tile `amount` columns to the right, with
+    /// tiles going past one edge wrapping around to the
+    /// other. Negative amounts move tiles to the left.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(14, 3)] = Tile::with_height(10);
+    /// pat.shift_x(3);
+    /// assert_eq!(pat[(1, 3)].height(), 10);
+    /// pat.shift_x(-2);
+    /// assert_eq!(pat[(15, 3)].height(), 10);
+    /// ```
+    pub fn shift_x(&mut self, amount: i32) {
+        let amount = amount.rem_euclid(16) as usize;
+        for row in self.tiles.chunks_exact_mut(16) {
+            row.rotate_right(amount);
+        }
+    }
+
+    /// Moves every tile `amount` rows down, with tiles
+    /// going past one edge wrapping around to the
+    /// other. Negative amounts move tiles up.
+    /// ```
+    /// use tinycbg::{CyberGrindPattern, Tile};
+    ///
+    /// let mut pat = CyberGrindPattern::new();
+    /// pat[(3, 1)] = Tile::with_height(10);
+    /// pat.shift_y(-2);
+    /// assert_eq!(pat[(3, 15)].height(), 10);
+    /// ```
+    pub fn shift_y(&mut self, amount: i32) {
+        let amount = amount.rem_euclid(16) as usize;
+        self.tiles.rotate_right(amount * 16);
+    }
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "std")]
+
+use tinycbg::*;
+
+#[test]
+fn sample_height() {
+    let mut pat = CyberGrindPattern::new();
+    pat[(15, 15)].set_height(-40);
+    pat[(14, 14)].set_height(20);
+
+    for (x, y) in Rect::FULL.positions() {
+        assert_eq!(
+            pat.sample_height(x as f32, y as f32),
+            pat[(x, y)].height() as f32
+        );
+    }
+    assert_eq!(pat.sample_height(14.5, 14.5), -5.0);
+    assert_eq!(pat.sample_height(14.25, 14.0), 15.0);
+    assert_eq!(pat.sample_height(100.0, 100.0), -40.0);
+}
+
+#[test]
+fn line_of_sight() {
+    let mut pat = CyberGrindPattern::new();
+    pat.copy_tile_to_column(Tile::with_height(10), 8);
+
+    assert!(!pat.line_of_sight((0, 4), (15, 4), 5.0));
+    assert!(pat.line_of_sight((0, 4), (7, 12), 5.0));
+    // Standing on the wall
+    assert!(pat.line_of_sight((8, 0), (0, 15), 1.0));
+    // Looking down from a tower
+    pat[(0, 0)].set_height(50);
+    assert!(pat.line_of_sight((0, 0), (15, 1), 1.0));
+    assert!(!pat.line_of_sight((0, 0), (15, 15), -49.0));
+
+    for a in Rect::FULL.positions().step_by(7) {
+        for b in Rect::FULL.positions().step_by(5) {
+            assert_eq!(pat.line_of_sight(a, b, 2.0), pat.line_of_sight(b, a, 2.0));
+        }
+    }
+}
+
+#[test]
+fn occlusion() {
+    let mut pat = CyberGrindPattern::new();
+    pat.copy_tile_to_column(Tile::with_height(10), 4);
+
+    let overhead = pat.occlusion_from(Light::Direction {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    });
+    assert!(overhead.iter().all(|&shadow| !shadow));
+
+    // Light rising 2.2 height per tile from
+    // the west, so the wall's shadow is 4 tiles long
+    let shadows = pat.occlusion_from(Light::Direction {
+        x: -1.0,
+        y: 0.0,
+        z: 2.2,
+    });
+    for (x, y) in Rect::FULL.positions() {
+        assert_eq!(shadows[y * 16 + x], (5..9).contains(&x), "{x}");
+    }
+
+    let lamp = Light::Point {
+        tile: (0, 0),
+        height: 1.0,
+    };
+    let shadows = pat.occlusion_from(lamp);
+    assert!(shadows[15]);
+    assert!(!shadows[3]);
+}
+
+#[test]
+fn cover_map() {
+    let mut pat = CyberGrindPattern::new();
+    pat.copy_tile_to_row(Tile::with_height(4), 10);
+
+    let cover = pat.cover_map((7, 0), 2.0);
+    for (x, y) in Rect::FULL.positions() {
+        assert_eq!(cover[y * 16 + x], y > 10, "({x}, {y})");
+    }
+
+    let on_wall = pat.cover_map((7, 10), 2.0);
+    assert!(on_wall.iter().all(|&hidden| !hidden));
+}
+
+#[test]
+fn profiles() {
+    let mut pat = CyberGrindPattern::new();
+    for i in 0..16 {
+        pat[(i, i)].set_height(i as i8);
+    }
+    assert_eq!(
+        pat.profile(&[(0, 0), (15, 15)]),
+        (0..16).collect::<Vec<i8>>()
+    );
+    assert_eq!(pat.profile(&[(3, 3)]), [3]);
+    assert!(pat.profile(&[]).is_empty());
+
+    let samples = pat.profile_sampled(&[(0.0, 0.0), (0.0, 3.0), (3.0, 3.0)], 1.0);
+    assert_eq!(samples, [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 3.0]);
+    let samples = pat.profile_sampled(&[(0.0, 0.0), (0.0, 1.5), (1.5, 1.5)], 1.0);
+    assert_eq!(samples.len(), 4);
+}
+
+#[test]
+fn ambient_occlusion() {
+    let mut pat = CyberGrindPattern::new();
+    assert!(pat.ambient_occlusion().iter().all(|&ao| ao == 0.0));
+
+    pat.copy_tile_to_column(Tile::with_height(8), 8);
+    let ao = pat.ambient_occlusion();
+    assert!(ao.iter().all(|ao| (0.0..=1.0).contains(ao)));
+    // Closer to the wall is more enclosed
+    assert!(ao[7] > ao[6] && ao[6] > ao[5] && ao[5] > ao[4]);
+    assert_eq!(ao[3], 0.0);
+    assert_eq!(ao[8], 0.0);
+
+    assert_ne!(pat.to_png_shaded(), pat.to_png());
+    assert_ne!(pat.to_svg_shaded(), pat.to_svg());
+}
+
+#[test]
+fn gradients() {
+    let mut pat = CyberGrindPattern::new();
+    for y in 0..16 {
+        pat.copy_tile_to_row((y as i8 * -3).into(), y);
+    }
+    let gradients = pat.gradients();
+    // Edges only see one neighbor, but the slope is even
+    assert!(gradients.iter().all(|&gradient| gradient == (0.0, -3.0)));
+    assert_eq!(pat.max_slope(), 3.0);
+}
+
+#[test]
+fn world_scale_round_trip() {
+    let scale = WorldScale {
+        tile_size: 4.0,
+        height_step: 0.5,
+        origin: [-32.0, 10.0, 100.0],
+    };
+    for (x, y) in Rect::FULL.positions() {
+        for height in [-50, -7, 0, 50] {
+            let [wx, wy, wz] = scale.tile_to_world((x, y), height);
+            assert_eq!(scale.world_to_tile(wx, wz), Some((x, y)));
+            assert_eq!(scale.world_to_height(wy), height);
+        }
+    }
+    assert_eq!(scale.world_to_tile(-32.1, 100.0), None);
+    assert_eq!(scale.world_to_tile(32.0, 100.0), None);
+    assert_eq!(scale.world_to_height(1000.0), 50);
+}
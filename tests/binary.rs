@@ -0,0 +1,110 @@
+use tinycbg::error::{BinaryError, ShareCodeError};
+use tinycbg::*;
+
+#[test]
+fn round_trip() {
+    for bytes in [
+        &include_bytes!("patterns/valid/range.cgp")[..],
+        &include_bytes!("patterns/valid/max_len.cgp")[..],
+    ] {
+        let pattern = CyberGrindPattern::parse(bytes).unwrap();
+        let bytes = pattern.to_binary();
+        assert_eq!(bytes.len(), BINARY_SIZE);
+        assert_eq!(
+            CyberGrindPattern::parse_binary(&bytes).unwrap()[..],
+            pattern[..]
+        );
+
+        #[cfg(feature = "std")]
+        {
+            let mut written = Vec::new();
+            pattern.write_binary(&mut written).unwrap();
+            assert_eq!(written, bytes);
+        }
+    }
+
+    let mut pattern = CyberGrindPattern::new();
+    pattern[3].set_prefab(Prefab::unknown(b'T').unwrap());
+    pattern[200].set_prefab(Prefab::unknown(b'~').unwrap());
+    let bytes = pattern.to_binary();
+    assert_eq!(&bytes[BINARY_SIZE..], b"T~");
+    assert_eq!(
+        CyberGrindPattern::parse_binary(&bytes).unwrap()[..],
+        pattern[..]
+    );
+}
+
+#[test]
+fn invalid_binary() {
+    let mut pattern = CyberGrindPattern::new();
+    pattern[7].set_prefab(Prefab::unknown(b'T').unwrap());
+    let bytes = pattern.to_binary();
+    let parse = |edit: &dyn Fn(&mut Vec<u8>)| {
+        let mut bytes = bytes.clone();
+        edit(&mut bytes);
+        CyberGrindPattern::parse_binary(&bytes).unwrap_err()
+    };
+
+    assert_eq!(parse(&|b| b[0] = b'X'), BinaryError::NotBinary);
+    assert_eq!(parse(&|b| b[4] = 2), BinaryError::UnsupportedVersion(2));
+    assert_eq!(
+        parse(&|b| b.truncate(100)),
+        BinaryError::TooShort { len: 100 }
+    );
+    assert_eq!(
+        parse(&|b| b.truncate(BINARY_SIZE)),
+        BinaryError::TooShort { len: BINARY_SIZE }
+    );
+    assert_eq!(parse(&|b| b[5 + 9] = 51), BinaryError::Height { index: 9 });
+    assert_eq!(
+        parse(&|b| b[5 + 256] = 0x90),
+        BinaryError::Prefab { index: 1 }
+    );
+    assert_eq!(
+        parse(&|b| b[BINARY_SIZE] = b'n'),
+        BinaryError::Prefab { index: 7 }
+    );
+    assert_eq!(parse(&|b| b.push(0)), BinaryError::TrailingData);
+}
+
+#[test]
+fn share_codes() {
+    let text = std::fs::read("tests/patterns/valid/max_len.cgp").unwrap();
+    let mut pattern = CyberGrindPattern::parse(&text).unwrap();
+    pattern[200].set_prefab(Prefab::unknown(b'T').unwrap());
+    let code = pattern.to_share_code();
+    assert!(code
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+    assert_eq!(
+        CyberGrindPattern::from_share_code(&code).unwrap()[..],
+        pattern[..]
+    );
+
+    // Line breaks, standard base64 and padding are all fine
+    let pasted: String = code
+        .as_bytes()
+        .chunks(60)
+        .map(|line| String::from_utf8(line.to_vec()).unwrap() + "\n")
+        .collect::<String>()
+        .replace('-', "+")
+        .replace('_', "/")
+        + "==";
+    assert_eq!(
+        CyberGrindPattern::from_share_code(&pasted).unwrap()[..],
+        pattern[..]
+    );
+
+    assert_eq!(
+        CyberGrindPattern::from_share_code("Q0dQ!g").unwrap_err(),
+        ShareCodeError::InvalidChar { index: 4 }
+    );
+    assert_eq!(
+        CyberGrindPattern::from_share_code("Q0dQQg==Q").unwrap_err(),
+        ShareCodeError::InvalidChar { index: 8 }
+    );
+    assert_eq!(
+        CyberGrindPattern::from_share_code("Q0dQQg").unwrap_err(),
+        ShareCodeError::Binary(BinaryError::TooShort { len: 4 })
+    );
+}
@@ -1,7 +1,9 @@
 use std::fmt::Debug;
 
+use tinycbg::error::OutOfBounds;
 use tinycbg::*;
 
+#[cfg(feature = "std")]
 #[test]
 #[ignore = "only needs to be run once"]
 fn create_test_patterns() {
@@ -32,6 +34,7 @@ fn create_test_patterns() {
         .unwrap();
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn parse() {
     let pattern = CyberGrindPattern::parse_path("tests/patterns/valid/range.cgp").unwrap();
@@ -54,14 +57,416 @@ fn parse() {
     }
 }
 
-fn traits<T: Send + Sync + Debug + Clone + Default>() {}
+fn traits<T: Send + Sync + Debug + Clone + Copy + Default>() {}
 #[test]
-fn debug() {
+fn common_traits() {
     traits::<CyberGrindPattern>();
+    traits::<Tile>();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn debug() {
     let dbg_res = format!(
         "{:?}",
         CyberGrindPattern::parse_path("test.cgp").expect("Failed to parse cgp file")
     );
     print!("{}", dbg_res);
-    traits::<Tile>();
+}
+
+#[test]
+fn try_from_tiles() {
+    let tiles = vec![Tile::with_height(3); 256];
+    let pattern = CyberGrindPattern::try_from(tiles).unwrap();
+    assert_eq!(pattern[255].height(), 3);
+
+    let err = CyberGrindPattern::try_from(vec![Tile::default(); 257]).unwrap_err();
+    assert_eq!(err.expected, 256);
+    assert_eq!(err.found, 257);
+
+    let short: &[Tile] = &[Tile::default(); 12];
+    assert!(CyberGrindPattern::try_from(short).is_err());
+}
+
+const PALETTE: [Tile; 3] = [
+    Tile::with_height(0),
+    Tile::new(20, Prefab::JumpPad),
+    Tile::with_prefab(Prefab::Melee),
+];
+
+static ARENA: CyberGrindPattern = {
+    let mut tiles = [PALETTE[0]; 256];
+    let mut i = 0;
+    while i < 16 {
+        tiles[i] = PALETTE[1];
+        tiles[i * 16].set_height(-5);
+        i += 1;
+    }
+    tiles[136].set_prefab(PALETTE[2].prefab());
+    CyberGrindPattern::from_array(tiles)
+};
+
+#[test]
+fn const_patterns() {
+    const EMPTY: CyberGrindPattern = CyberGrindPattern::new();
+    assert_eq!(EMPTY[..], CyberGrindPattern::default()[..]);
+    assert_eq!(ARENA[(3, 0)], Tile::new(20, Prefab::JumpPad));
+    assert_eq!(ARENA[(0, 3)].height(), -5);
+    assert_eq!(ARENA[(8, 8)].prefab(), Prefab::Melee);
+    const { assert!(PALETTE[2].prefab().spawns_enemy()) };
+}
+
+#[test]
+fn builder() {
+    let wall = Tile::with_height(20);
+    let built = CyberGrindPattern::builder()
+        .fill(Tile::with_height(-3))
+        .border(wall)
+        .column(4, Tile::with_prefab(Prefab::Stairs))
+        .at(4, 0, wall)
+        .build();
+
+    let mut manual = CyberGrindPattern::splat(Tile::with_height(-3));
+    for i in [0, 15] {
+        manual.copy_tile_to_row(wall, i);
+        manual.copy_tile_to_column(wall, i);
+    }
+    manual.copy_tile_to_column(Tile::with_prefab(Prefab::Stairs), 4);
+    manual[(4, 0)] = wall;
+    assert_eq!(built[..], manual[..]);
+}
+
+#[test]
+fn from_fn() {
+    let mut calls = Vec::new();
+    let pattern = CyberGrindPattern::from_fn(|x, y| {
+        calls.push((x, y));
+        Tile::with_height(x as i8 - y as i8)
+    });
+    assert_eq!(calls.len(), 256);
+    assert_eq!(calls[..2], [(0, 0), (1, 0)]);
+    assert_eq!(calls[16], (0, 1));
+    for (x, y) in calls {
+        assert_eq!(pattern[(x, y)].height(), x as i8 - y as i8);
+    }
+}
+
+#[test]
+fn map() {
+    let text = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let range = CyberGrindPattern::parse(&text).unwrap();
+
+    let mapped = range.map(|x, y, tile| {
+        if x == y {
+            Tile::with_prefab(Prefab::JumpPad)
+        } else {
+            tile
+        }
+    });
+    let mut in_place = range;
+    in_place.map_in_place(|x, y, tile| {
+        if x == y {
+            *tile = Tile::with_prefab(Prefab::JumpPad);
+        }
+    });
+    assert_eq!(mapped[..], in_place[..]);
+    for (x, y) in [(0, 0), (7, 7), (15, 15)] {
+        assert_eq!(mapped[(x, y)], Tile::with_prefab(Prefab::JumpPad));
+    }
+    assert_eq!(mapped[(1, 0)], range[(1, 0)]);
+}
+
+#[test]
+fn get() {
+    let mut pattern = CyberGrindPattern::new();
+    *pattern.get_mut(3, 15).unwrap() = Tile::with_height(9);
+    pattern.get_index_mut(0).unwrap().set_prefab(Prefab::Melee);
+
+    assert_eq!(pattern.get(3, 15), Some(&Tile::with_height(9)));
+    assert_eq!(pattern.get_index(243), Some(&Tile::with_height(9)));
+    assert_eq!(pattern.get(0, 0).unwrap().prefab(), Prefab::Melee);
+    assert!(pattern.get(20, 3).is_none());
+    assert!(pattern.get(3, 16).is_none());
+    assert!(pattern.get_mut(16, 0).is_none());
+    assert!(pattern.get_index(256).is_none());
+    assert!(pattern.get_index_mut(usize::MAX).is_none());
+
+    let wrapped = std::panic::catch_unwind(|| pattern[(20, 3)]);
+    assert!(wrapped.is_err());
+}
+
+#[test]
+fn coords() {
+    let mut pattern = CyberGrindPattern::new();
+    let coord = Coord::try_from((5, 9)).unwrap();
+    pattern[coord] = Tile::with_height(-8);
+
+    assert_eq!(pattern[(5, 9)].height(), -8);
+    assert_eq!(coord.index(), 149);
+    assert_eq!(Coord::from_index(149), Some(coord));
+    assert_eq!(<(usize, usize)>::from(coord), (5, 9));
+    assert_eq!(Coord::try_from((20, 3)), Err(OutOfBounds { x: 20, y: 3 }));
+    assert!(Coord::new(0, 16).is_none());
+    assert!(Coord::from_index(256).is_none());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn cache() {
+    let mut cache = PatternCache::new();
+    let first = cache
+        .load("tests/patterns/valid/range.cgp")
+        .expect("Failed to parse cgp file");
+    let second = cache.load("tests/patterns/valid/range.cgp").unwrap();
+    assert_eq!(first[..], second[..]);
+    assert_eq!(cache.len(), 1);
+
+    cache.invalidate("tests/patterns/valid/range.cgp");
+    assert!(cache.is_empty());
+    assert!(cache.load("tests/patterns/does_not_exist.cgp").is_err());
+}
+
+#[cfg(feature = "memmap")]
+#[test]
+fn parse_mmap() {
+    let mapped = CyberGrindPattern::parse_path_mmap("tests/patterns/valid/range.cgp").unwrap();
+    let read = CyberGrindPattern::parse_path("tests/patterns/valid/range.cgp").unwrap();
+    assert_eq!(mapped[..], read[..]);
+}
+
+#[test]
+fn parse_options() {
+    let text = std::fs::read_to_string("tests/patterns/valid/range.cgp").unwrap();
+    let odd = text
+        .replacen("(10)", "(010)", 1)
+        .replacen("(50)", "(120)", 1)
+        .replacen('J', "x", 1);
+
+    assert!(CyberGrindPattern::parse_str(&odd).is_err());
+    let pattern = CyberGrindPattern::parse_with(odd.as_bytes(), &ParseOptions::lenient()).unwrap();
+    assert_eq!(pattern[60].height(), 10);
+    assert_eq!(pattern[100].height(), 50);
+    assert_eq!(pattern[254].prefab(), Prefab::None);
+
+    let trailing = format!("{text}extra");
+    assert!(CyberGrindPattern::parse_str(&trailing).is_ok());
+    let err =
+        CyberGrindPattern::parse_with(trailing.as_bytes(), &ParseOptions::strict()).unwrap_err();
+    assert_eq!(err.kind, error::ParseErrorType::TrailingData);
+    assert_eq!((err.line, err.column), (34, 1));
+}
+
+#[test]
+fn parse_concatenated() {
+    let mut bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    bytes.extend(std::fs::read("tests/patterns/valid/max_len.cgp").unwrap());
+
+    let (first, consumed) = CyberGrindPattern::parse_prefix(&bytes).unwrap();
+    let (second, rest) = CyberGrindPattern::parse_prefix(&bytes[consumed..]).unwrap();
+    assert_eq!(consumed + rest, bytes.len());
+    assert_eq!(first[0].height(), -50);
+    assert_eq!(second[0].prefab(), Prefab::Melee);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn files_reject_trailing_data() {
+    let mut bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    bytes.extend(b"extra");
+    let path = "tests/patterns/trailing_data.cgp";
+    std::fs::write(path, &bytes).unwrap();
+    let from_path = CyberGrindPattern::parse_path(path);
+    let lenient = CyberGrindPattern::parse_path_with(path, &ParseOptions::default());
+    std::fs::remove_file(path).unwrap();
+
+    let is_trailing = |err: error::IoError| matches!(err, error::IoError::Parse(err) if err.kind == error::ParseErrorType::TrailingData);
+    assert!(is_trailing(from_path.unwrap_err()));
+    let from_reader = CyberGrindPattern::parse_reader(&mut &bytes[..]);
+    assert!(is_trailing(from_reader.unwrap_err()));
+    assert!(lenient.is_ok());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parse_large_files() {
+    let text = std::fs::read_to_string("tests/patterns/valid/max_len.cgp").unwrap();
+    let path = "tests/patterns/large_crlf_bom.cgp";
+
+    // Longer than MAX_FILE_SIZE once every newline is doubled
+    let crlf = format!("\u{feff}{}", text.replace('\n', "\r\n"));
+    assert!(crlf.len() > MAX_FILE_SIZE);
+    std::fs::write(path, &crlf).unwrap();
+    let options = ParseOptions {
+        allow_crlf: true,
+        ..Default::default()
+    };
+    let pattern = CyberGrindPattern::parse_path_with(path, &options);
+    let too_small = ParseOptions {
+        max_file_size: 1000,
+        ..options
+    };
+    let too_large = CyberGrindPattern::parse_path_with(path, &too_small);
+    std::fs::remove_file(path).unwrap();
+
+    let expected = CyberGrindPattern::parse_str(&text).unwrap();
+    assert_eq!(pattern.unwrap()[..], expected[..]);
+    assert!(matches!(
+        too_large,
+        Err(error::IoError::TooLarge { limit: 1000 })
+    ));
+
+    let bom = format!("\u{feff}{text}");
+    let (_, consumed) = CyberGrindPattern::parse_prefix(bom.as_bytes()).unwrap();
+    assert_eq!(consumed, bom.len());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parse_reader() {
+    let text = std::fs::read("tests/patterns/valid/max_len.cgp").unwrap();
+    let expected = CyberGrindPattern::parse(&text).unwrap();
+
+    // A reader which only hands out a few bytes at a time, like a socket
+    struct Trickle<'a>(&'a [u8]);
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(7);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+    let pattern = CyberGrindPattern::parse_reader(&mut Trickle(&text)).unwrap();
+    assert_eq!(pattern[..], expected[..]);
+
+    let options = ParseOptions {
+        max_file_size: 100,
+        ..Default::default()
+    };
+    assert!(matches!(
+        CyberGrindPattern::parse_reader_with(&mut Trickle(&text), &options),
+        Err(error::IoError::TooLarge { limit: 100 })
+    ));
+    assert!(matches!(
+        CyberGrindPattern::parse_reader(&mut &b"x"[..]),
+        Err(error::IoError::Parse(_))
+    ));
+}
+
+#[test]
+fn from_str() {
+    fn parse_generic<T: std::str::FromStr>(text: &str) -> Option<T> {
+        text.parse().ok()
+    }
+
+    let text = std::fs::read_to_string("tests/patterns/valid/max_len.cgp").unwrap();
+    let expected = CyberGrindPattern::parse_str(&text).unwrap();
+    let parsed: CyberGrindPattern = parse_generic(&text).unwrap();
+    assert_eq!(parsed[..], expected[..]);
+    assert_eq!(
+        CyberGrindPattern::try_from(text.as_str()).unwrap()[..],
+        expected[..]
+    );
+
+    let err = "not a pattern".parse::<CyberGrindPattern>().unwrap_err();
+    assert_eq!(
+        Err(err),
+        CyberGrindPattern::parse_str("not a pattern").map(|_| ())
+    );
+    assert!(CyberGrindPattern::try_from("?").is_err());
+}
+
+#[test]
+fn truncated_input() {
+    use error::ParseErrorType;
+
+    for path in [
+        "tests/patterns/valid/range.cgp",
+        "tests/patterns/valid/max_len.cgp",
+    ] {
+        let bytes = std::fs::read(path).unwrap();
+        for len in 0..bytes.len() {
+            let err = CyberGrindPattern::parse(&bytes[..len]).unwrap_err();
+            assert_eq!(
+                err.kind,
+                ParseErrorType::UnexpectedEof,
+                "{path} cut at {len}"
+            );
+            assert_eq!(err.char, 0);
+        }
+    }
+
+    let err = CyberGrindPattern::parse(b"12(-3").unwrap_err();
+    assert_eq!((err.line, err.column), (1, 6));
+    assert_eq!(
+        err.to_string(),
+        "Error parsing line 1, column 6: Unexpected end of input"
+    );
+    let err = "".parse::<CyberGrindPattern>().unwrap_err();
+    assert_eq!(
+        (err.line, err.column, err.kind),
+        (1, 1, ParseErrorType::UnexpectedEof)
+    );
+}
+
+#[test]
+fn missing_final_newline() {
+    use error::ParseErrorType;
+
+    let text = std::fs::read_to_string("tests/patterns/valid/max_len.cgp").unwrap();
+    let expected = CyberGrindPattern::parse_str(&text).unwrap();
+    let trimmed = text.trim_end().as_bytes();
+    let options = ParseOptions {
+        allow_missing_final_newline: true,
+        ..ParseOptions::strict()
+    };
+
+    let err = CyberGrindPattern::parse(trimmed).unwrap_err();
+    assert_eq!(err.kind, ParseErrorType::UnexpectedEof);
+    let pattern = CyberGrindPattern::parse_with(trimmed, &options).unwrap();
+    assert_eq!(pattern[..], expected[..]);
+    let pattern = CyberGrindPattern::parse_with(text.as_bytes(), &options).unwrap();
+    assert_eq!(pattern[..], expected[..]);
+
+    // Only the very last newline is optional
+    let err = CyberGrindPattern::parse_with(&trimmed[..trimmed.len() - 1], &options).unwrap_err();
+    assert_eq!(err.kind, ParseErrorType::UnexpectedEof);
+    let mut extra = trimmed.to_vec();
+    extra.push(b'n');
+    let err = CyberGrindPattern::parse_with(&extra, &options).unwrap_err();
+    assert_eq!(err.kind, ParseErrorType::ExpectedNewline);
+
+    let mut parser = Parser::with_options(options);
+    assert!(matches!(parser.feed(trimmed), Ok(ParseStatus::Incomplete)));
+    assert_eq!(parser.finish().unwrap()[..], expected[..]);
+    assert!(!parser.in_progress());
+}
+
+#[test]
+fn fill() {
+    let mut pat = CyberGrindPattern::new();
+    pat.fill(Tile::with_height(3));
+    assert!(pat[..].iter().all(|tile| tile.height() == 3));
+
+    pat.fill_border(Tile::new(10, Prefab::Stairs));
+    for i in 0..16 {
+        for (x, y) in [(i, 0), (i, 15), (0, i), (15, i)] {
+            assert_eq!(pat[(x, y)], Tile::new(10, Prefab::Stairs));
+        }
+    }
+    assert_eq!(pat[(1, 1)].height(), 3);
+
+    pat.fill_rows(4..6, Tile::with_height(-1));
+    assert!(pat[64..96].iter().all(|tile| tile.height() == -1));
+    assert_eq!(pat[(0, 3)].height(), 10);
+    assert_eq!(pat[(1, 6)].height(), 3);
+
+    pat.fill_rows(.., Tile::with_height(0));
+    assert!(pat[..].iter().all(|tile| tile.height() == 0));
+}
+
+#[test]
+#[should_panic]
+fn fill_rows_out_of_bounds() {
+    CyberGrindPattern::new().fill_rows(10..=16, Tile::with_height(1));
 }
@@ -0,0 +1,50 @@
+#![cfg(feature = "charts")]
+
+use tinycbg::chart::*;
+use tinycbg::*;
+
+#[test]
+fn pack_stats() {
+    let mut pat = CyberGrindPattern::new();
+    pat[0].set_prefab(Prefab::Melee);
+    pat[1].set_prefab(Prefab::JumpPad);
+    pat[2].set_height(-4);
+    let pack = [("a", pat), ("b <&>", CyberGrindPattern::new())];
+    let named = || pack.iter().map(|(name, pat)| (*name, pat));
+
+    let spawns = Chart::pack_stats(named(), Stat::EnemySpawns);
+    assert_eq!(spawns.title, "Enemy spawns");
+    assert_eq!(spawns.labels, ["a", "b <&>"]);
+    assert_eq!(spawns.values, [1.0, 0.0]);
+    assert_eq!(
+        Chart::pack_stats(named(), Stat::HeightRange).values,
+        [4.0, 0.0]
+    );
+    assert_eq!(
+        Chart::pack_stats(named(), Stat::JumpPads).values,
+        [1.0, 0.0]
+    );
+
+    let svg = spawns.to_svg();
+    assert!(svg.contains("b &lt;&amp;&gt;"));
+    assert_eq!(svg.matches("<rect").count(), 1 + 2);
+}
+
+#[test]
+fn rendering() {
+    let mut pat = CyberGrindPattern::new();
+    pat[(2, 0)].set_height(-10);
+    let path: Vec<(usize, usize)> = (0..16).map(|x| (x, 0)).collect();
+    for chart in [
+        Chart::height_histogram(&[pat]),
+        Chart::profile(&pat, &path),
+        Chart::pack_stats([], Stat::MaxSlope),
+    ] {
+        let svg = chart.to_svg();
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert!(!svg.contains("NaN"));
+        let png = chart.to_png();
+        assert!(png.starts_with(b"\x89PNG"));
+        assert_eq!(&png[16..24], [0, 0, 2, 128, 0, 0, 1, 104]);
+    }
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "crdt")]
+
+use tinycbg::crdt::{CrdtPattern, Update};
+use tinycbg::*;
+
+#[test]
+fn replicas_converge() {
+    let mut a = CrdtPattern::new(1);
+    let mut b = CrdtPattern::new(2);
+
+    let mut from_a = vec![
+        a.set(5, Tile::with_height(1)),
+        a.set(6, Tile::with_height(2)),
+    ];
+    let from_b = vec![
+        b.set(6, Tile::with_height(3)),
+        b.set(7, Tile::new(4, Prefab::Melee)),
+    ];
+    from_a.push(a.set(5, Tile::with_height(5)));
+
+    for update in &from_b {
+        a.apply(update);
+    }
+    for update in from_a.iter().rev() {
+        let bytes = update.to_bytes();
+        b.apply(&Update::from_bytes(&bytes).unwrap());
+    }
+
+    assert_eq!(a.pattern()[..], b.pattern()[..]);
+    assert_eq!(a.pattern()[5].height(), 5);
+
+    let mut c = CrdtPattern::new(3);
+    c.merge(&a);
+    assert_eq!(c.pattern()[..], a.pattern()[..]);
+}
@@ -0,0 +1,229 @@
+#![cfg(feature = "std")]
+
+use tinycbg::*;
+
+#[test]
+fn rng_is_deterministic() {
+    let a: Vec<u64> = (0..10)
+        .scan(Rng::new(42), |rng, _| Some(rng.next_u64()))
+        .collect();
+    let b: Vec<u64> = (0..10)
+        .scan(Rng::new(42), |rng, _| Some(rng.next_u64()))
+        .collect();
+    assert_eq!(a, b);
+    assert_ne!(a[0], Rng::new(43).next_u64());
+
+    let mut rng = Rng::new(0);
+    for _ in 0..1000 {
+        assert!((-3..3).contains(&rng.range(-3, 3)));
+        assert!((0.0..1.0).contains(&rng.next_f32()));
+    }
+}
+
+#[test]
+fn pillars_keep_their_distance() {
+    let config = PillarConfig {
+        min_height: 10,
+        max_height: 20,
+        footprint: 2,
+        min_spacing: 2,
+    };
+    let mut pat = CyberGrindPattern::new();
+    let pillars = pat.scatter_pillars(&mut Rng::new(3), 100, config);
+    assert!(!pillars.is_empty() && pillars.len() < 100);
+
+    for (i, a) in pillars.iter().enumerate() {
+        for b in &pillars[i + 1..] {
+            let gap_x = b.x.max(a.x) - b.x.min(a.x);
+            let gap_y = b.y.max(a.y) - b.y.min(a.y);
+            assert!(gap_x >= 4 || gap_y >= 4);
+        }
+        for pos in a.positions() {
+            assert!((10..=20).contains(&pat[pos].height()));
+        }
+    }
+
+    let covered = pat[..].iter().filter(|tile| tile.height() != 0).count();
+    assert_eq!(covered, pillars.len() * 4);
+}
+
+#[test]
+fn pillar_heights_are_clamped() {
+    let config = PillarConfig {
+        min_height: 60,
+        max_height: 70,
+        ..PillarConfig::default()
+    };
+    let mut pat = CyberGrindPattern::new();
+    let pillars = pat.scatter_pillars(&mut Rng::new(5), 4, config);
+    for pillar in pillars {
+        assert!(pillar.positions().all(|pos| pat[pos].height() == 50));
+    }
+}
+
+#[test]
+fn poisson_prefabs() {
+    let mut pat = CyberGrindPattern::new();
+    pat[(0, 0)].set_prefab(Prefab::Stairs);
+    let placed = pat.scatter_prefabs(&mut Rng::new(9), Prefab::Projectile, 256, 4.0, |(x, _)| {
+        x < 12
+    });
+    assert!(placed.len() > 3);
+
+    let mut all = placed.clone();
+    all.push((0, 0));
+    for (i, &(ax, ay)) in all.iter().enumerate() {
+        assert!(ax < 12);
+        for &(bx, by) in &all[i + 1..] {
+            let (dx, dy) = (ax as f32 - bx as f32, ay as f32 - by as f32);
+            assert!((dx * dx + dy * dy).sqrt() >= 4.0);
+        }
+    }
+    let projectiles = pat[..]
+        .iter()
+        .filter(|tile| tile.prefab() == Prefab::Projectile);
+    assert_eq!(projectiles.count(), placed.len());
+}
+
+#[test]
+fn symmetric_generation() {
+    let symmetries = [
+        Symmetry::MirrorX,
+        Symmetry::MirrorY,
+        Symmetry::MirrorBoth,
+        Symmetry::Rotate180,
+        Symmetry::Rotate90,
+    ];
+    for symmetry in symmetries {
+        let pat = CyberGrindPattern::generate_symmetric(&mut Rng::new(11), symmetry, |pat, rng| {
+            for i in 0..256 {
+                pat[i].set_height(rng.range(-50, 51) as i8);
+            }
+        });
+
+        let mut covered = 0;
+        for pos in symmetry.source().positions() {
+            covered += 1;
+            for image in symmetry.images(pos) {
+                assert!(!symmetry.source().contains(image));
+                assert_eq!(pat[image], pat[pos]);
+                covered += 1;
+            }
+        }
+        assert_eq!(covered, 256);
+    }
+}
+
+#[test]
+fn spawn_balance() {
+    let pat =
+        CyberGrindPattern::generate_symmetric(&mut Rng::new(2), Symmetry::Rotate90, |pat, rng| {
+            pat.scatter_prefabs(rng, Prefab::Melee, 4, 2.0, |(x, y)| x < 8 && y < 8);
+        });
+    let report = pat.check_spawn_balance(Symmetry::Rotate90, 0);
+    assert_eq!(report.counts, [4; 4]);
+    assert!(report.balanced);
+    assert!(report.unmatched.is_empty());
+
+    let mut pat = pat;
+    let (x, y) = (0..256)
+        .map(|i| (i % 16, i / 16))
+        .find(|&pos| pat[pos].prefab() == Prefab::Melee && pos.0 >= 8 && pos.1 >= 8)
+        .unwrap();
+    pat[(x, y)].set_prefab(Prefab::HideousMass);
+    pat[(0, 15)].set_prefab(Prefab::Projectile);
+
+    let report = pat.check_spawn_balance(Symmetry::Rotate90, 0);
+    assert_eq!(report.counts, [4, 4, 5, 4]);
+    assert!(!report.balanced);
+    assert!(pat.check_spawn_balance(Symmetry::Rotate90, 1).balanced);
+    // The changed spawn, the three it should match, and the extra one
+    assert_eq!(report.unmatched.len(), 5);
+    assert!(report.unmatched.contains(&(x, y)));
+    assert!(report.unmatched.contains(&(0, 15)));
+}
+
+#[test]
+fn jitter_stays_in_range() {
+    let mut pat = CyberGrindPattern::splat(Tile::with_height(49));
+    pat.jitter_heights(&mut Rng::new(6), 5, |_| true);
+    assert!(pat[..]
+        .iter()
+        .all(|tile| (44..=50).contains(&tile.height())));
+    assert!(pat[..].iter().any(|tile| tile.height() == 50));
+
+    let mut pat = CyberGrindPattern::splat(Tile::with_height(-50));
+    pat.jitter_heights(&mut Rng::new(6), 255, |_| true);
+    assert!(pat[..].iter().any(|tile| tile.height() > 0));
+}
+
+#[test]
+fn voronoi_plateaus() {
+    let config = VoronoiConfig {
+        sites: 5,
+        min_height: -20,
+        max_height: 20,
+        smoothing: 0,
+    };
+    let pat = CyberGrindPattern::generate_voronoi(&mut Rng::new(12), config);
+    let mut heights: Vec<i8> = pat[..].iter().map(|tile| tile.height()).collect();
+    heights.sort();
+    heights.dedup();
+    assert!(heights.len() <= 5);
+    assert!(heights.iter().all(|height| (-20..=20).contains(height)));
+
+    let smooth = CyberGrindPattern::generate_voronoi(
+        &mut Rng::new(12),
+        VoronoiConfig {
+            smoothing: 2,
+            ..config
+        },
+    );
+    let steepest = |pat: &CyberGrindPattern| {
+        (0..255)
+            .filter(|i| i % 16 != 15)
+            .map(|i| (pat[i].height() - pat[i + 1].height()).abs())
+            .max()
+            .unwrap()
+    };
+    assert!(steepest(&smooth) < steepest(&pat));
+}
+
+#[test]
+fn diamond_square() {
+    let a = CyberGrindPattern::generate_diamond_square(&mut Rng::new(21), 0.6, 30);
+    let b = CyberGrindPattern::generate_diamond_square(&mut Rng::new(21), 0.6, 30);
+    assert_eq!(a[..], b[..]);
+
+    let heights = a[..].iter().map(|tile| tile.height());
+    assert_eq!(heights.clone().min(), Some(-30));
+    assert_eq!(heights.max(), Some(30));
+
+    let flat = CyberGrindPattern::generate_diamond_square(&mut Rng::new(21), 0.6, 0);
+    assert!(flat[..].iter().all(|tile| tile.height() == 0));
+}
+
+#[test]
+fn archetypes() {
+    for archetype in Archetype::ALL {
+        assert_eq!(archetype.name().parse::<Archetype>(), Ok(archetype));
+        let a = CyberGrindPattern::generate_archetype(&mut Rng::new(5), archetype);
+        let b = CyberGrindPattern::generate_archetype(&mut Rng::new(5), archetype);
+        assert_eq!(a[..], b[..], "{}", archetype.name());
+    }
+    assert_eq!(" Hills ".parse(), Ok(Archetype::Hills { roughness: 0.5 }));
+    assert_eq!(
+        "volcano".parse::<Archetype>().unwrap_err().to_string(),
+        "Unknown archetype \"volcano\""
+    );
+
+    let canyon = Archetype::Canyon {
+        depth: 20,
+        width: 3,
+    };
+    let pat = CyberGrindPattern::generate_archetype(&mut Rng::new(5), canyon);
+    for y in 0..16 {
+        let deep = (0..16).filter(|&x| pat[(x, y)].height() <= -18).count();
+        assert_eq!(deep, 3);
+    }
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "http")]
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+use tinycbg::http::PreviewServer;
+
+fn get(server: &PreviewServer, path: &str) -> String {
+    let addr = server.local_addr().unwrap();
+    let path = path.to_string();
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        String::from_utf8_lossy(&response).into_owned()
+    });
+    server.handle_next().unwrap();
+    client.join().unwrap()
+}
+
+#[test]
+fn preview_server() {
+    let server = PreviewServer::bind("tests/patterns/valid", "127.0.0.1:0").unwrap();
+
+    let index = get(&server, "/");
+    assert!(index.starts_with("HTTP/1.1 200 OK"));
+    assert!(index.contains("/patterns/range.svg"));
+
+    let list = get(&server, "/patterns.json");
+    assert!(list.contains("\"name\":\"max_len\",\"min_height\":-50,\"max_height\":-50"));
+
+    let meta = get(&server, "/patterns/range.json");
+    assert!(meta.contains("\"jump pad\":1"));
+
+    assert!(get(&server, "/patterns/range.svg").contains("image/svg+xml"));
+    assert!(get(&server, "/patterns/range.png").contains("image/png"));
+    assert!(get(&server, "/patterns/../test.cgp").starts_with("HTTP/1.1 404"));
+}
+
+#[test]
+fn idle_clients_time_out() {
+    let mut server = PreviewServer::bind("tests/patterns/valid", "127.0.0.1:0").unwrap();
+    assert!(server.set_timeout(Duration::ZERO).is_err());
+    server.set_timeout(Duration::from_millis(100)).unwrap();
+
+    // Connects without ever sending a request
+    let idle = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    let start = Instant::now();
+    assert!(server.handle_next().is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+    drop(idle);
+
+    assert!(get(&server, "/").starts_with("HTTP/1.1 200 OK"));
+}
@@ -0,0 +1,90 @@
+use tinycbg::error::JsonError;
+use tinycbg::*;
+
+#[test]
+fn round_trip() {
+    for bytes in [
+        &include_bytes!("patterns/valid/range.cgp")[..],
+        &include_bytes!("patterns/valid/max_len.cgp")[..],
+    ] {
+        let mut pattern = CyberGrindPattern::parse(bytes).unwrap();
+        pattern[(4, 9)].set_prefab(Prefab::unknown(b'"').unwrap());
+        let json = pattern.to_json();
+        assert_eq!(json.lines().count(), 38);
+        let read = CyberGrindPattern::from_json(&json).unwrap();
+        assert_eq!(read[..], pattern[..]);
+    }
+}
+
+#[test]
+fn lenient_reading() {
+    let row = |value: &str| format!("[{}]", vec![value; 16].join(","));
+    let grid = |value: &str| format!("[{}]", vec![row(value); 16].join(","));
+    let json = format!(
+        "{{\"version\": 1, \"meta\": {{\"tags\": [\"a\\u00e9\\ud83d\\ude00\", null, true]}},\
+          \"prefabs\": {}, \"heights\": {}}}",
+        grid("\"Jump Pad\""),
+        grid("-7"),
+    );
+    let pattern = CyberGrindPattern::from_json(&json).unwrap();
+    assert!(pattern[..]
+        .iter()
+        .all(|&tile| tile == Tile::new(-7, Prefab::JumpPad)));
+
+    let short = json.replacen("-7,", "", 1);
+    assert_eq!(
+        CyberGrindPattern::from_json(&short).unwrap_err(),
+        JsonError::WrongShape { field: "heights" }
+    );
+    let missing = format!("{{\"heights\": {}}}", grid("0"));
+    assert_eq!(
+        CyberGrindPattern::from_json(&missing).unwrap_err(),
+        JsonError::MissingField { field: "prefabs" }
+    );
+}
+
+#[test]
+fn invalid_json() {
+    let json = CyberGrindPattern::new().to_json();
+    assert_eq!(
+        CyberGrindPattern::from_json(&json.replacen("0", "51", 1)).unwrap_err(),
+        JsonError::Height { x: 0, y: 0 }
+    );
+    assert_eq!(
+        CyberGrindPattern::from_json(&json.replacen("0, 0", "0, 1.5", 1)).unwrap_err(),
+        JsonError::Height { x: 1, y: 0 }
+    );
+    let prefabs = json.find("\"none\"").unwrap();
+    let mut bad = json.clone();
+    bad.replace_range(prefabs..prefabs + 6, "\"maurice\"");
+    assert_eq!(
+        CyberGrindPattern::from_json(&bad).unwrap_err(),
+        JsonError::Prefab { x: 0, y: 0 }
+    );
+
+    for (json, offset) in [("", 0), ("{\"heights\" 1}", 11), ("[1, 2", 5), ("{} x", 3)] {
+        assert_eq!(
+            CyberGrindPattern::from_json(json).unwrap_err(),
+            JsonError::Syntax { offset },
+            "{json}"
+        );
+    }
+    assert_eq!(
+        CyberGrindPattern::from_json("[]").unwrap_err().to_string(),
+        "Missing field \"heights\""
+    );
+}
+
+#[test]
+fn deep_nesting() {
+    let deep = "[".repeat(200_000);
+    assert_eq!(
+        CyberGrindPattern::from_json(&deep).unwrap_err(),
+        JsonError::Syntax { offset: 32 }
+    );
+
+    // Nesting in ignored fields is fine, up to a point
+    let json = CyberGrindPattern::new().to_json();
+    let nested = json.replacen('{', "{\"extra\": [[[[[1]]]]],", 1);
+    assert!(CyberGrindPattern::from_json(&nested).is_ok());
+}
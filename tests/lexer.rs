@@ -0,0 +1,57 @@
+use tinycbg::*;
+
+#[test]
+fn tokens() {
+    let tokens: Vec<_> = Lexer::new(b"4(-12)\n\nJ0\n")
+        .map(|token| token.unwrap())
+        .collect();
+    let kinds: Vec<_> = tokens.iter().map(|token| token.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            TokenKind::Height(4),
+            TokenKind::ParenHeight(-12),
+            TokenKind::Newline,
+            TokenKind::Newline,
+            TokenKind::Prefab(Prefab::JumpPad),
+            TokenKind::Prefab(Prefab::None),
+            TokenKind::Newline,
+        ]
+    );
+    assert_eq!(
+        tokens[1].span,
+        Span {
+            start: 1,
+            end: 6,
+            line: 1,
+            column: 2,
+        }
+    );
+    assert_eq!((tokens[5].span.line, tokens[5].span.column), (3, 2));
+}
+
+#[test]
+fn covers_whole_file() {
+    let bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let tokens: Vec<_> = CyberGrindPattern::tokenize(&bytes)
+        .map(|token| token.unwrap())
+        .collect();
+    assert_eq!(tokens.len(), 256 * 2 + 33);
+
+    let mut end = 0;
+    for token in &tokens {
+        assert_eq!(token.span.start, end);
+        end = token.span.end;
+    }
+    assert_eq!(end, bytes.len());
+}
+
+#[test]
+fn stops_after_error() {
+    let mut lexer = Lexer::new(b"1(--2)3");
+    assert!(lexer.next().unwrap().is_ok());
+    let err = lexer.next().unwrap().unwrap_err();
+    assert_eq!(err.kind, error::ParseErrorType::DuplicateNegative);
+    assert_eq!(err.column, 4);
+    assert!(lexer.next().is_none());
+}
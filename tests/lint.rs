@@ -0,0 +1,150 @@
+use tinycbg::error::ParseErrorType;
+use tinycbg::*;
+
+fn pattern_text(first_row: &str) -> String {
+    let mut text = format!("{first_row}\n");
+    for _ in 0..15 {
+        text.push_str(&"0".repeat(16));
+        text.push('\n');
+    }
+    text.push('\n');
+    for _ in 0..16 {
+        text.push_str(&"0".repeat(16));
+        text.push('\n');
+    }
+    text
+}
+
+#[test]
+fn lint_warnings() {
+    let text = pattern_text("(5)00000000000000()");
+    let diagnostics: Vec<Diagnostic> = CyberGrindPattern::lint(text.as_bytes()).collect();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnneededParentheses);
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+    assert_eq!(diagnostics[1].kind, DiagnosticKind::EmptyParentheses);
+    assert_eq!((diagnostics[1].line, diagnostics[1].column), (1, 18));
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+
+    let text = pattern_text(&"0".repeat(16));
+    let kinds: Vec<DiagnosticKind> = CyberGrindPattern::lint(text.as_bytes())
+        .map(|d| d.kind)
+        .collect();
+    assert_eq!(kinds, [DiagnosticKind::AllZero]);
+}
+
+#[test]
+fn whole_pattern_diagnostics_come_last() {
+    let mut text = pattern_text(&"0".repeat(16));
+    text.push_str("extra");
+    let kinds: Vec<DiagnosticKind> = CyberGrindPattern::lint(text.as_bytes())
+        .map(|d| d.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        [DiagnosticKind::AllZero, DiagnosticKind::TrailingData]
+    );
+
+    // Warnings before an error are handed back as they're found
+    let text = pattern_text("(5)000000000000000").replacen("\n\n", "\nx\n", 1);
+    let mut lint = CyberGrindPattern::lint(text.as_bytes());
+    assert_eq!(
+        lint.next().unwrap().kind,
+        DiagnosticKind::UnneededParentheses
+    );
+    assert_eq!(
+        lint.next().unwrap().kind,
+        DiagnosticKind::Parse(ParseErrorType::ExpectedNewline)
+    );
+    assert!(lint.next().is_none());
+}
+
+#[test]
+fn lint_errors() {
+    let text = pattern_text("(--5)000000000000000");
+    let diagnostics: Vec<Diagnostic> = CyberGrindPattern::lint(text.as_bytes()).collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::Parse(ParseErrorType::DuplicateNegative)
+    );
+}
+
+#[test]
+fn diagnostic_ranges_and_fixes() {
+    let mut text = pattern_text("(5)00000000000000()");
+    text.push_str("extra\nlines");
+    let diagnostics = CyberGrindPattern::diagnostics(text.as_bytes());
+    assert_eq!(diagnostics.len(), 3);
+
+    assert_eq!(diagnostics[0].range, 0..3);
+    assert_eq!((diagnostics[0].end_line, diagnostics[0].end_column), (1, 4));
+    assert_eq!(diagnostics[1].range, 17..19);
+
+    let trailing = &diagnostics[2];
+    assert_eq!(trailing.kind, DiagnosticKind::TrailingData);
+    // The leftover text starts on the line after the pattern
+    let pattern_lines = pattern_text("(5)00000000000000()").lines().count() as u32;
+    assert_eq!((trailing.line, trailing.column), (pattern_lines + 1, 1));
+    assert_eq!(
+        (trailing.end_line, trailing.end_column),
+        (pattern_lines + 2, "lines".len() as u32 + 1)
+    );
+
+    // Fixes are applied back to front so earlier ranges stay valid
+    let mut fixed = text.clone().into_bytes();
+    for diagnostic in diagnostics.iter().rev() {
+        fixed = diagnostic.fix.as_ref().unwrap().apply(&fixed);
+    }
+    assert_eq!(fixed, pattern_text("5000000000000000").into_bytes());
+}
+
+#[test]
+fn error_fixes() {
+    let text = pattern_text("(-05)000000000000000");
+    let diagnostics = CyberGrindPattern::diagnostics(text.as_bytes());
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::Parse(ParseErrorType::LeadingZero)
+    );
+    assert_eq!(diagnostics[0].range, 2..3);
+    let fixed = diagnostics[0].fix.as_ref().unwrap().apply(text.as_bytes());
+    assert!(CyberGrindPattern::parse(&fixed).is_ok());
+
+    let text = pattern_text("x000000000000000");
+    let diagnostics = CyberGrindPattern::diagnostics(text.as_bytes());
+    assert_eq!(
+        diagnostics[0].message(),
+        ParseErrorType::InvalidHeightChar.to_string()
+    );
+    assert!(diagnostics[0].fix.is_none());
+}
+
+#[test]
+fn byte_order_mark() {
+    let text = pattern_text("(5)00000000000000()");
+    let mut bytes = b"\xef\xbb\xbf".to_vec();
+    bytes.extend_from_slice(text.as_bytes());
+
+    let plain = CyberGrindPattern::diagnostics(text.as_bytes());
+    let with_bom = CyberGrindPattern::diagnostics(&bytes);
+    assert_eq!(with_bom.len(), plain.len());
+    for (a, b) in plain.iter().zip(&with_bom) {
+        assert_eq!((a.line, a.column), (b.line, b.column));
+        assert_eq!((a.end_line, a.end_column), (b.end_line, b.end_column));
+        assert_eq!(a.range.start + 3, b.range.start);
+    }
+
+    let mut fixed = bytes.clone();
+    for diagnostic in with_bom.iter().rev() {
+        fixed = diagnostic.fix.as_ref().unwrap().apply(&fixed);
+    }
+    assert_eq!(&fixed[3..], pattern_text("5000000000000000").as_bytes());
+
+    let mut bytes = b"\xef\xbb\xbf".to_vec();
+    bytes.extend_from_slice(pattern_text("(-05)000000000000000").as_bytes());
+    let diagnostics = CyberGrindPattern::diagnostics(&bytes);
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 3));
+    assert_eq!(diagnostics[0].range, 5..6);
+}
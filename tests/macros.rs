@@ -0,0 +1,75 @@
+use tinycbg::*;
+
+#[test]
+fn cgp_macro() {
+    let pattern = cgp! {
+        [-50 -49 -48 -47 -46 -45 -44 -43 -42 -41 -40 -39 -38 -37 -36 -35]
+        [-34 -33 -32 -31 -30 -29 -28 -27 -26 -25 -24 -23 -22 -21 -20 -19]
+        [-18 -17 -16 -15 -14 -13 -12 -11 -10 -9 -8 -7 -6 -5 -4 -3]
+        [-2 -1 0 1 2 3 4 5 6 7 8 9 10 11 12 13]
+        [14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29]
+        [30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45]
+        [46 47 48 49 50 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        ;
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0]
+        [0 0 0 0 0 0 0 0 0 0 0 n p H J s]
+    };
+    let parsed = CyberGrindPattern::parse(include_bytes!("patterns/valid/range.cgp")).unwrap();
+    assert_eq!(pattern[..], parsed[..]);
+
+    const FLAT: CyberGrindPattern = cgp! {
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+        [3 3 3 3 3 3 3 3 3 3 3 3 3 3 3 3]
+    };
+    assert!(FLAT[..].iter().all(|tile| tile.height() == 3));
+    assert!(FLAT[..].iter().all(|tile| tile.prefab() == Prefab::None));
+}
+
+#[test]
+fn include_cgp_macro() {
+    static RANGE: CyberGrindPattern = include_cgp!("patterns/valid/range.cgp");
+    let max_len = include_cgp!("patterns/valid/max_len.cgp");
+    for (pattern, bytes) in [
+        (RANGE, &include_bytes!("patterns/valid/range.cgp")[..]),
+        (max_len, &include_bytes!("patterns/valid/max_len.cgp")[..]),
+    ] {
+        assert_eq!(pattern[..], CyberGrindPattern::parse(bytes).unwrap()[..]);
+    }
+}
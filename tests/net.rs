@@ -0,0 +1,59 @@
+#![cfg(feature = "net")]
+
+use tinycbg::net::{Message, PatternClient, PatternServer};
+use tinycbg::*;
+
+#[test]
+fn broadcast_to_clients() {
+    let mut server = PatternServer::bind("127.0.0.1:0").unwrap();
+    let mut start = CyberGrindPattern::new();
+    start.copy_tile_to_row(Tile::new(-30, Prefab::JumpPad), 3);
+    server
+        .broadcast(&Message::Pattern(Box::new(start)))
+        .unwrap();
+
+    let addr = server.local_addr().unwrap();
+    let mut first = PatternClient::connect(addr).unwrap();
+    let mut second = PatternClient::connect(addr).unwrap();
+    assert_eq!(server.accept_pending().unwrap(), 2);
+
+    let ops = vec![Op::SetTile {
+        index: 200,
+        tile: Tile::new(12, Prefab::Melee),
+    }];
+    server.broadcast(&Message::Ops(ops)).unwrap();
+
+    for client in [&mut first, &mut second] {
+        assert!(matches!(client.recv().unwrap(), Message::Pattern(_)));
+        assert!(matches!(client.recv().unwrap(), Message::Ops(_)));
+        assert_eq!(client.pattern()[..], server.pattern()[..]);
+        assert_eq!(client.pattern()[48].prefab(), Prefab::JumpPad);
+        assert_eq!(client.pattern()[200].height(), 12);
+    }
+}
+
+#[test]
+fn invalid_ops_are_rejected() {
+    let mut server = PatternServer::bind("127.0.0.1:0").unwrap();
+    let bad = Message::Ops(vec![Op::SetHeight {
+        index: 999,
+        height: 0,
+    }]);
+    assert!(!bad.is_valid());
+    assert!(server.broadcast(&bad).is_err());
+    assert!(server.set_write_timeout(std::time::Duration::ZERO).is_err());
+    server
+        .set_write_timeout(std::time::Duration::from_millis(100))
+        .unwrap();
+
+    // A peer which sends an op outside of the pattern
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut client = PatternClient::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut peer, _) = listener.accept().unwrap();
+    let payload = b"set 999 0 0\n";
+    std::io::Write::write_all(&mut peer, &[1, payload.len() as u8, 0]).unwrap();
+    std::io::Write::write_all(&mut peer, payload).unwrap();
+
+    assert!(client.recv().is_err());
+    assert!(client.pattern()[..] == CyberGrindPattern::new()[..]);
+}
@@ -0,0 +1,73 @@
+use tinycbg::*;
+
+#[test]
+fn record_and_replay() {
+    let mut pattern = CyberGrindPattern::new();
+    let mut recorder = pattern.record();
+    recorder.fill(Tile::with_height(2));
+    recorder.copy_tile_to_column(Tile::new(30, Prefab::Stairs), 4);
+    recorder.set_tile(200, Tile::new(-50, Prefab::HideousMass));
+    recorder.set_height(3, 9);
+    recorder.set_prefab(3, Prefab::JumpPad);
+    recorder.set_prefab(4, Prefab::unknown(b'x').unwrap());
+    let ops = recorder.finish();
+    assert_eq!(ops.len(), 6);
+
+    let log = Op::write_log(&ops);
+    let parsed = Op::parse_log(&log).unwrap();
+    assert_eq!(parsed, ops);
+
+    let mut replayed = CyberGrindPattern::new();
+    replayed.replay(&parsed);
+    assert_eq!(replayed[..], pattern[..]);
+}
+
+#[test]
+fn invalid_log() {
+    let err = Op::parse_log("fill 0 0\n\nheight 3 51\n").unwrap_err();
+    assert_eq!(err.line, 3);
+    assert!("set 1 2 n extra".parse::<Op>().is_err());
+    assert!("prefab 1 xy".parse::<Op>().is_err());
+
+    // Out of bounds ops can't be applied
+    assert!("set 999 0 0".parse::<Op>().is_err());
+    assert!("height 256 0".parse::<Op>().is_err());
+    assert!("row 16 0 0".parse::<Op>().is_err());
+    assert!("column 16 0 0".parse::<Op>().is_err());
+    assert!("set 255 0 0".parse::<Op>().is_ok());
+    assert!("column 15 0 0".parse::<Op>().is_ok());
+    assert_eq!(Op::parse_log("fill 0 0\nset 999 0 0").unwrap_err().line, 2);
+}
+
+#[test]
+fn record_selections_and_regions() {
+    let mut other = CyberGrindPattern::new();
+    other.fill(Tile::new(7, Prefab::Stairs));
+    let sub = other.extract(Rect::new(0, 0, 3, 2));
+
+    let mut pattern = CyberGrindPattern::new();
+    let mut recorder = pattern.record();
+    recorder.fill_selection(&Selection::circle((7, 7), 5), Tile::with_height(20));
+    recorder.blit(&sub, (14, 0));
+    recorder.copy_region_from(&other, Rect::new(0, 0, 2, 2), (0, 14));
+    let ops = recorder.finish();
+
+    // Clipped tiles aren't recorded
+    assert_eq!(ops.len(), 1 + 4 + 4);
+    assert!(matches!(ops[0], Op::FillSelection { .. }));
+    assert!(ops[1..].iter().all(|op| matches!(op, Op::SetTile { .. })));
+
+    let parsed = Op::parse_log(&Op::write_log(&ops)).unwrap();
+    assert_eq!(parsed, ops);
+    let mut replayed = CyberGrindPattern::new();
+    replayed.replay(&parsed);
+    assert_eq!(replayed[..], pattern[..]);
+}
+
+#[test]
+fn op_error_lines() {
+    assert_eq!("height 3 51".parse::<Op>().unwrap_err().line, 1);
+    assert_eq!("fill 0 0\nfill 1 0".parse::<Op>().unwrap_err().line, 2);
+    assert!("fill 0 0\n".parse::<Op>().is_ok());
+    assert!("selection 0000 0 0".parse::<Op>().is_err());
+}
@@ -0,0 +1,164 @@
+use tinycbg::*;
+
+fn feed_bytewise(
+    bytes: &[u8],
+    options: ParseOptions,
+) -> Result<(CyberGrindPattern, usize), error::ParseError> {
+    let mut parser = Parser::with_options(options);
+    for (i, byte) in bytes.iter().enumerate() {
+        if let ParseStatus::Complete { pattern, consumed } = parser.feed(&[*byte])? {
+            assert_eq!(consumed, 1);
+            return Ok((*pattern, i + 1));
+        }
+    }
+    let pattern = parser.finish()?;
+    Ok((*pattern, bytes.len()))
+}
+
+#[test]
+fn matches_parse() {
+    let text = std::fs::read_to_string("tests/patterns/valid/range.cgp").unwrap();
+    let inputs = [
+        text.clone(),
+        text.replacen("(-12)", "(--12)", 1),
+        text.replacen("(30)", "(031)", 1),
+        text.replacen("(45)", "(99)", 1),
+        text.replacen("0123", "01x3", 1),
+        text.replacen('J', "q", 1),
+        text.replacen("(-12)", "(12-)", 1),
+        text.replacen("(30)", "()", 1),
+        text.replacen("(45)", "(-)", 1),
+        text.replacen("\n\n", "\n0\n", 1),
+        text.replace('\n', "\r\n"),
+        text.replacen('\n', "\r\r\n", 1),
+        text.replacen('\n', "\r", 1),
+        text.trim_end().to_string(),
+        text[..500].to_string(),
+    ];
+
+    for options in [ParseOptions::default(), ParseOptions::lenient()] {
+        for input in &inputs {
+            let expected = CyberGrindPattern::parse_prefix_with(input.as_bytes(), &options);
+            let streamed = feed_bytewise(input.as_bytes(), options);
+            match (expected, streamed) {
+                (Ok((a, a_len)), Ok((b, b_len))) => {
+                    assert_eq!(a[..], b[..]);
+                    assert_eq!(a_len, b_len);
+                }
+                (Err(a), Err(b)) => assert_eq!(a, b),
+                (a, b) => panic!("{a:?} != {b:?}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn stream_of_patterns() {
+    let mut bytes = std::fs::read("tests/patterns/valid/max_len.cgp").unwrap();
+    bytes.extend(std::fs::read("tests/patterns/valid/range.cgp").unwrap());
+
+    let mut parser = Parser::new();
+    let mut patterns = Vec::new();
+    for chunk in bytes.chunks(500) {
+        let mut chunk = chunk;
+        while let ParseStatus::Complete { pattern, consumed } = parser.feed(chunk).unwrap() {
+            patterns.push(pattern);
+            chunk = &chunk[consumed..];
+        }
+    }
+    assert_eq!(patterns.len(), 2);
+    assert_eq!(patterns[0][0].prefab(), Prefab::Melee);
+    assert_eq!(patterns[1][0].height(), -50);
+    assert!(!parser.in_progress());
+}
+
+#[test]
+fn all_errors() {
+    use error::ParseErrorType;
+
+    let text = std::fs::read_to_string("tests/patterns/valid/range.cgp").unwrap();
+    let inputs = [
+        text.clone(),
+        text.replacen("(-12)", "(--12)", 1),
+        text.replacen("(30)", "(031)", 1),
+        text.replacen("(45)", "(99)", 1),
+        text.replacen("0123", "01x3", 1),
+        text.replacen('J', "q", 1),
+        text.replacen("\n\n", "\n0\n", 1),
+        text.replacen("\n\n", "\n\n\n", 1),
+        text.replacen("(-50)", "", 1),
+        text.replacen("(-50)", "(-50)1", 1),
+        text.replacen("(-12)", "(12-)", 1),
+        text.replacen("(30)", "()", 1),
+        text.replace('\n', "\r\n"),
+        text.trim_end().to_string(),
+        text[..500].to_string(),
+        format!("{text}extra"),
+    ];
+
+    // The first error is always the one `parse_with()` finds
+    for options in [ParseOptions::strict(), ParseOptions::lenient()] {
+        for input in &inputs {
+            let expected = CyberGrindPattern::parse_with(input.as_bytes(), &options);
+            let all = CyberGrindPattern::parse_all_errors_with(input.as_bytes(), &options);
+            match (expected, all) {
+                (Ok(a), Ok(b)) => assert_eq!(a[..], b[..]),
+                (Err(a), Err(b)) => assert_eq!(a, b[0]),
+                (a, b) => panic!("{a:?} != {b:?}"),
+            }
+        }
+    }
+
+    let broken = text
+        .replacen("(-50)", "(-70)", 1)
+        .replacen("(-12)", "(--12)", 1)
+        .replacen("(-11)", "(-111)", 1)
+        .replacen("\n\n", "\nx\n", 1)
+        .replacen('n', "?", 1)
+        .replace('J', "q");
+    let errors = CyberGrindPattern::parse_all_errors(broken.as_bytes()).unwrap_err();
+    let found: Vec<_> = errors
+        .iter()
+        .map(|err| (err.line, err.kind.clone()))
+        .collect();
+    assert_eq!(found[0], (1, ParseErrorType::InvalidHeightValue));
+    assert_eq!(found[1], (3, ParseErrorType::DuplicateNegative));
+    assert_eq!(found[2], (17, ParseErrorType::ExpectedNewline));
+    assert!(found[3..]
+        .iter()
+        .all(|(line, kind)| *line > 17 && *kind == ParseErrorType::InvalidPrefab));
+    assert_eq!(
+        CyberGrindPattern::parse_all_errors(b"(-").unwrap_err(),
+        [CyberGrindPattern::parse(b"(-").unwrap_err()]
+    );
+}
+
+#[test]
+fn byte_order_mark() {
+    let text = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let with_bom = [b"\xef\xbb\xbf".as_slice(), &text].concat();
+
+    // The same file is accepted by both parsers
+    let parsed = CyberGrindPattern::parse(&with_bom).unwrap();
+    let (streamed, consumed) = feed_bytewise(&with_bom, ParseOptions::default()).unwrap();
+    assert_eq!(parsed[..], streamed[..]);
+    assert_eq!(consumed, with_bom.len());
+    match Parser::new().feed(&with_bom).unwrap() {
+        ParseStatus::Complete { pattern, .. } => assert_eq!(pattern[..], parsed[..]),
+        ParseStatus::Incomplete => panic!("BOM file wasn't completed"),
+    }
+
+    let mut bad_height = with_bom.clone();
+    bad_height[5] = b'x';
+    let inputs = [
+        bad_height,
+        [b"\xef\xbb".as_slice(), &text].concat(),
+        [b"\xef".as_slice(), &text].concat(),
+        [b"\xef\xbb\xbf\xef\xbb\xbf".as_slice(), &text].concat(),
+    ];
+    for input in inputs {
+        let expected = CyberGrindPattern::parse_prefix_with(&input, &ParseOptions::default());
+        let streamed = feed_bytewise(&input, ParseOptions::default());
+        assert_eq!(expected.unwrap_err(), streamed.unwrap_err());
+    }
+}
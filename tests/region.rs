@@ -0,0 +1,237 @@
+use tinycbg::*;
+
+fn numbered() -> CyberGrindPattern {
+    let mut pat = CyberGrindPattern::new();
+    for i in 0..256 {
+        pat[i] = Tile::new((i % 101) as i8 - 50, Prefab::Melee);
+    }
+    pat
+}
+
+#[test]
+fn copy_rows_and_columns() {
+    let other = numbered();
+    let mut pat = CyberGrindPattern::new();
+    pat.copy_row_from(&other, 15);
+    pat.copy_column_from(&other, 2);
+    for i in 0..256 {
+        let (x, y) = (i % 16, i / 16);
+        let expected = if y == 15 || x == 2 {
+            other[i]
+        } else {
+            Tile::default()
+        };
+        assert_eq!(pat[i], expected);
+    }
+}
+
+#[test]
+fn copy_region() {
+    let other = numbered();
+    let mut pat = CyberGrindPattern::new();
+    pat.copy_region_from(&other, Rect::new(1, 2, 3, 20), (14, 10));
+
+    assert_eq!(pat[(14, 10)], other[(1, 2)]);
+    assert_eq!(pat[(15, 15)], other[(2, 7)]);
+    // The third column and everything
+    // past the bottom are cut off
+    let copied = pat[..].iter().filter(|tile| **tile != Tile::default());
+    assert_eq!(copied.count(), 12);
+}
+
+#[test]
+fn swap_region() {
+    let original = numbered();
+    let mut a = original;
+    let mut b = CyberGrindPattern::new();
+    let rect = Rect::new(4, 4, 100, 2);
+
+    a.swap_region(&mut b, rect);
+    for (x, y) in Rect::FULL.positions() {
+        if rect.contains((x, y)) {
+            assert_eq!((a[(x, y)], b[(x, y)]), (Tile::default(), original[(x, y)]));
+        } else {
+            assert_eq!((a[(x, y)], b[(x, y)]), (original[(x, y)], Tile::default()));
+        }
+    }
+
+    a.swap_region(&mut b, rect);
+    assert_eq!(a[..], original[..]);
+}
+
+#[test]
+fn level_region() {
+    let original = numbered();
+    let mut pat = original;
+    let rect = Rect::new(10, 10, 10, 10);
+    let level = pat.level_region(rect);
+
+    let inside: Vec<i32> = rect
+        .positions()
+        .map(|pos| original[pos].height() as i32)
+        .collect();
+    let mean = inside.iter().sum::<i32>() as f32 / inside.len() as f32;
+    assert_eq!(level, mean.round() as i8);
+
+    for pos in Rect::FULL.positions() {
+        let expected = if rect.contains(pos) {
+            Tile::new(level, Prefab::Melee)
+        } else {
+            original[pos]
+        };
+        assert_eq!(pat[pos], expected);
+    }
+}
+
+#[test]
+fn level_selection() {
+    let original = numbered();
+    let mut pat = original;
+    let site = Selection::disc((5, 5), 3);
+    let level = pat.level_selection(&site);
+
+    let inside: Vec<i32> = site
+        .positions()
+        .map(|pos| original[pos].height() as i32)
+        .collect();
+    let mean = inside.iter().sum::<i32>() as f32 / inside.len() as f32;
+    assert_eq!(level, mean.round() as i8);
+
+    for pos in Rect::FULL.positions() {
+        let expected = if site.contains(pos) {
+            Tile::new(level, Prefab::Melee)
+        } else {
+            original[pos]
+        };
+        assert_eq!(pat[pos], expected);
+    }
+
+    let mut smoothed = original;
+    smoothed.level_selection_smoothed(&site, 2);
+    assert_eq!(smoothed[(5, 5)].height(), level);
+    assert_ne!(smoothed[(5, 9)], original[(5, 9)]);
+    assert_eq!(smoothed[(5, 11)], original[(5, 11)]);
+}
+
+#[test]
+fn border() {
+    let mut pat = numbered();
+    pat.build_border(-5, 100);
+    assert!(pat[..].iter().all(|&tile| tile == Tile::with_height(-5)));
+
+    let mut pat = CyberGrindPattern::new();
+    pat.build_border_profile(&[3, 2, 1]);
+    let ones = pat[..].iter().filter(|tile| tile.height() == 1).count();
+    let twos = pat[..].iter().filter(|tile| tile.height() == 2).count();
+    let threes = pat[..].iter().filter(|tile| tile.height() == 3).count();
+    assert_eq!((threes, twos, ones), (60, 52, 44));
+}
+
+#[test]
+fn rect_geometry() {
+    let rect = Rect::new(12, 12, 8, 8);
+    assert_eq!(rect.area(), 16);
+    assert!(!rect.is_empty());
+    assert!(Rect::new(16, 0, 4, 4).is_empty());
+    assert!(Rect::new(3, 3, 0, 5).is_empty());
+
+    assert_eq!(rect.intersect(&Rect::FULL), Some(Rect::new(12, 12, 4, 4)));
+    assert_eq!(Rect::FULL.intersect(&rect), Some(Rect::new(12, 12, 4, 4)));
+    assert_eq!(rect.intersect(&Rect::new(0, 0, 12, 16)), None);
+
+    assert_eq!(rect.translate(-4, -12), Rect::new(8, 0, 8, 8));
+    assert_eq!(rect.translate(-20, 0), Rect::new(0, 12, 0, 8));
+    assert_eq!(rect.translate(-20, 0).area(), 0);
+}
+
+#[test]
+fn fill_and_iterate_rect() {
+    let mut pat = numbered();
+    let rect = Rect::new(14, 2, 5, 2);
+    let tiles: Vec<_> = pat.tiles_in(rect).collect();
+    assert_eq!(tiles.len(), 4);
+    assert_eq!(tiles[0], ((14, 2), pat[(14, 2)]));
+    assert_eq!(tiles[3], ((15, 3), pat[(15, 3)]));
+
+    pat.fill_rect(rect, Tile::with_height(-20));
+    for (x, y) in Rect::FULL.positions() {
+        assert_eq!(pat[(x, y)] == Tile::with_height(-20), rect.contains((x, y)));
+    }
+}
+
+#[test]
+fn extract_and_blit() {
+    let source = numbered();
+    let sub = source.extract(Rect::new(13, 0, 5, 2));
+    assert_eq!((sub.width(), sub.height()), (3, 2));
+    assert_eq!(sub[(0, 0)], source[(13, 0)]);
+    assert_eq!(sub[(2, 1)], source[(15, 1)]);
+    assert_eq!(sub.get((3, 0)), None);
+    assert_eq!(source.extract(Rect::new(20, 0, 2, 2)).width(), 0);
+
+    let mut pat = CyberGrindPattern::new();
+    pat.blit(&sub, (14, 15));
+    assert_eq!(pat[(14, 15)], source[(13, 0)]);
+    assert_eq!(pat[(15, 15)], source[(14, 0)]);
+    let changed = pat[..]
+        .iter()
+        .filter(|&&tile| tile != Tile::default())
+        .count();
+    assert_eq!(changed, 2);
+}
+
+#[test]
+fn swaps() {
+    let original = numbered();
+    let mut pat = original;
+
+    pat.swap_rows(0, 15);
+    assert!(pat[0..16] == original[240..256]);
+    assert!(pat[240..256] == original[0..16]);
+    assert!(pat[16..240] == original[16..240]);
+    pat.swap_rows(3, 3);
+    pat.swap_rows(15, 0);
+    assert!(pat[..] == original[..]);
+
+    pat.swap_columns(1, 4);
+    for y in 0..16 {
+        assert_eq!(pat[(1, y)], original[(4, y)]);
+        assert_eq!(pat[(4, y)], original[(1, y)]);
+        assert_eq!(pat[(2, y)], original[(2, y)]);
+    }
+
+    let mut pat = original;
+    pat.swap_tiles((2, 3), (15, 15));
+    assert_eq!(pat[(2, 3)], original[(15, 15)]);
+    assert_eq!(pat[(15, 15)], original[(2, 3)]);
+}
+
+#[test]
+#[should_panic]
+fn swap_rows_out_of_bounds() {
+    CyberGrindPattern::new().swap_rows(0, 16);
+}
+
+#[test]
+#[should_panic(expected = "Row must be less than 16")]
+fn copy_row_out_of_bounds() {
+    CyberGrindPattern::new().copy_row_from(&numbered(), 16);
+}
+
+#[test]
+#[should_panic(expected = "Column must be less than 16")]
+fn copy_column_out_of_bounds() {
+    CyberGrindPattern::new().copy_column_from(&numbered(), 16);
+}
+
+#[test]
+fn copy_far_outside_of_the_grid() {
+    let source = numbered();
+    let mut pat = CyberGrindPattern::new();
+    let sub = source.extract(Rect::FULL);
+    pat.blit(&sub, (usize::MAX, 0));
+    pat.blit(&sub, (3, usize::MAX - 2));
+    pat.copy_region_from(&source, Rect::FULL, (usize::MAX, usize::MAX));
+    pat.copy_region_from(&source, Rect::new(4, 4, 4, 4), (16, 0));
+    assert!(pat[..].iter().all(|&tile| tile == Tile::default()));
+}
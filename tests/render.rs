@@ -0,0 +1,50 @@
+#![cfg(feature = "std")]
+
+use tinycbg::*;
+
+#[test]
+fn diff_heatmap() {
+    let old = CyberGrindPattern::new();
+    let mut new = old;
+    new[(2, 0)].set_height(50);
+    new[(3, 0)].set_prefab(Prefab::Melee);
+
+    let text = old.diff_heatmap_ansi(&new);
+    let first_row = text.lines().next().unwrap();
+    assert!(first_row.contains("\x1b[48;2;127;0;0m"));
+    assert!(first_row.contains("m\x1b[97mn"));
+    assert_eq!(text.matches('.').count(), 255);
+
+    let png = old.diff_heatmap_png(&new);
+    assert!(png.starts_with(b"\x89PNG"));
+    assert_eq!(png, old.diff_heatmap_png(&new));
+    assert_ne!(png, old.diff_heatmap_png(&old));
+}
+
+#[test]
+fn normal_map() {
+    let flat = CyberGrindPattern::new().to_normal_map_png();
+    let mut slope = CyberGrindPattern::new();
+    for x in 0..16 {
+        slope.copy_tile_to_column((x as i8).into(), x);
+    }
+    let slope = slope.to_normal_map_png();
+    assert_ne!(flat, slope);
+
+    // Uncompressed pixel data, so the flat
+    // normal color can be found directly
+    let flat_row: Vec<u8> = [128, 128, 255].repeat(16);
+    assert!(flat.windows(flat_row.len()).any(|w| w == flat_row));
+    let tilted_row: Vec<u8> = [37, 128, 218].repeat(16);
+    assert!(slope.windows(tilted_row.len()).any(|w| w == tilted_row));
+}
+
+#[test]
+fn escaped_prefab_characters() {
+    let mut pat = CyberGrindPattern::new();
+    pat[0].set_prefab(Prefab::unknown(b'<').unwrap());
+    pat[1].set_prefab(Prefab::unknown(b'&').unwrap());
+    let svg = pat.to_svg();
+    assert!(svg.contains(">&lt;</text>"));
+    assert!(svg.contains(">&amp;</text>"));
+}
@@ -0,0 +1,69 @@
+use tinycbg::*;
+
+#[test]
+fn valid_data_is_untouched() {
+    let bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let pattern = CyberGrindPattern::parse(&bytes).unwrap();
+    let raw = RawPattern {
+        heights: pattern[..]
+            .iter()
+            .map(|tile| tile.height() as i32)
+            .collect(),
+        prefabs: pattern[..]
+            .iter()
+            .map(|tile| tile.prefab().byte())
+            .collect(),
+    };
+    let (sanitized, corrections) = raw.sanitize(&SanitizeConfig::default());
+    assert!(corrections.is_empty());
+    assert_eq!(sanitized[..], pattern[..]);
+}
+
+#[test]
+fn every_fix_is_reported() {
+    let mut raw = RawPattern {
+        heights: vec![0; 300],
+        prefabs: vec![b'0'; 100],
+    };
+    raw.heights[5] = 51;
+    raw.heights[6] = i32::MIN;
+    raw.prefabs[7] = b'x';
+
+    let config = SanitizeConfig {
+        out_of_range: OutOfRange::Replace(7),
+        unknown_prefab: Prefab::Stairs,
+        missing_tile: Tile::new(1, Prefab::Melee),
+    };
+    let (pattern, corrections) = raw.sanitize(&config);
+    assert_eq!(pattern[5].height(), 7);
+    assert_eq!(pattern[6].height(), 7);
+    assert_eq!(pattern[7].prefab(), Prefab::Stairs);
+    assert_eq!(pattern[100], Tile::new(0, Prefab::Melee));
+
+    assert_eq!(
+        corrections[..3],
+        [
+            Correction::Height {
+                index: 5,
+                from: 51,
+                to: 7
+            },
+            Correction::Height {
+                index: 6,
+                from: i32::MIN,
+                to: 7
+            },
+            Correction::Prefab {
+                index: 7,
+                from: b'x',
+                to: Prefab::Stairs
+            },
+        ]
+    );
+    assert_eq!(corrections[3], Correction::MissingPrefab { index: 100 });
+    assert_eq!(
+        corrections.last(),
+        Some(&Correction::ExtraHeights { count: 44 })
+    );
+    assert_eq!(corrections.len(), 3 + 156 + 1);
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "schemars")]
+
+use schemars::schema_for;
+use tinycbg::*;
+
+#[test]
+fn pattern_schema() {
+    let schema = schema_for!(CyberGrindPattern);
+    let properties = schema.get("properties").unwrap();
+
+    let heights = &properties["heights"];
+    assert_eq!(heights["minItems"], 16);
+    assert_eq!(heights["items"]["maxItems"], 16);
+    assert_eq!(heights["items"]["items"]["minimum"], -50);
+    assert_eq!(heights["items"]["items"]["maximum"], 50);
+
+    let prefabs = &properties["prefabs"];
+    assert_eq!(prefabs["items"]["items"]["$ref"], "#/$defs/Prefab");
+    let names = &schema.get("$defs").unwrap()["Prefab"]["anyOf"][0]["enum"];
+    for prefab in Prefab::iter() {
+        assert!(names.as_array().unwrap().contains(&prefab.name().into()));
+    }
+}
+
+#[test]
+fn tile_schema() {
+    let schema = schema_for!(Tile);
+    let required = schema.get("required").unwrap();
+    assert_eq!(required[0], "height");
+    assert_eq!(required[1], "prefab");
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "serde")]
+
+use serde::de::{value::Error, value::StrDeserializer, IntoDeserializer};
+use serde::Deserialize;
+use tinycbg::*;
+
+fn prefab(name: &str) -> Result<Prefab, Error> {
+    let deserializer: StrDeserializer<Error> = name.into_deserializer();
+    Prefab::deserialize(deserializer)
+}
+
+#[test]
+fn prefab_names() {
+    for prefab_ in Prefab::iter() {
+        assert_eq!(prefab(prefab_.name()), Ok(prefab_));
+    }
+    assert_eq!(prefab("Jump Pad"), Ok(Prefab::JumpPad));
+    assert_eq!(prefab("T"), Ok(Prefab::unknown(b'T').unwrap()));
+    assert!(prefab("maurice").is_err());
+    assert!(prefab(" ").is_err());
+}
+
+#[test]
+fn prefabs_are_strings() {
+    let unknown = Prefab::unknown(b'T').unwrap();
+    assert_eq!(serde_json::to_string(&unknown).unwrap(), "\"T\"");
+    for prefab in Prefab::iter().chain([unknown]) {
+        let json = serde_json::to_value(prefab).unwrap();
+        assert!(json.is_string());
+        assert_eq!(serde_json::from_value::<Prefab>(json).unwrap(), prefab);
+    }
+}
@@ -1,5 +1,7 @@
+use tinycbg::error::BufferTooSmall;
 use tinycbg::*;
 
+#[cfg(feature = "std")]
 #[test]
 fn write() {
     let mut pattern = CyberGrindPattern::new();
@@ -17,3 +19,338 @@ fn write() {
 
     pattern.write_to_path("tests/patterns/test.cgp").unwrap();
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn write_to_any_writer() {
+    let mut pattern = CyberGrindPattern::new();
+    pattern[(3, 4)] = Tile::new(-12, Prefab::Stairs);
+
+    let mut vec = Vec::new();
+    pattern.write(&mut vec).unwrap();
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    pattern.write(&mut cursor).unwrap();
+    let path = "tests/patterns/writer.cgp";
+    pattern.write_to_path(path).unwrap();
+    let file = std::fs::read(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(vec, file);
+    assert_eq!(cursor.into_inner(), file);
+    assert_eq!(CyberGrindPattern::parse(&vec).unwrap()[..], pattern[..]);
+}
+
+#[test]
+fn to_bytes() {
+    let text = std::fs::read("tests/patterns/valid/max_len.cgp").unwrap();
+    let pattern = CyberGrindPattern::parse(&text).unwrap();
+    assert_eq!(pattern.to_bytes(), text);
+
+    let mut out = b"header\n".to_vec();
+    pattern.write_to_vec(&mut out);
+    pattern.write_to_vec(&mut out);
+    assert_eq!(&out[..7], b"header\n");
+    assert_eq!(&out[7..7 + text.len()], text);
+    assert_eq!(&out[7 + text.len()..], text);
+}
+
+#[test]
+fn display() {
+    let text = std::fs::read_to_string("tests/patterns/valid/max_len.cgp").unwrap();
+    let pattern: CyberGrindPattern = text.parse().unwrap();
+    assert_eq!(pattern.to_string(), text);
+    assert_eq!(
+        format!("{pattern}").parse::<CyberGrindPattern>().unwrap()[..],
+        pattern[..]
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn line_endings() {
+    let text = std::fs::read_to_string("tests/patterns/valid/max_len.cgp").unwrap();
+    let pattern: CyberGrindPattern = text.parse().unwrap();
+    let crlf = WriteOptions {
+        line_ending: LineEnding::CrLf,
+    };
+
+    let bytes = pattern.to_bytes_with(&crlf);
+    assert_eq!(bytes, text.replace('\n', "\r\n").into_bytes());
+    assert_eq!(
+        pattern.to_bytes_with(&WriteOptions::default()),
+        pattern.to_bytes()
+    );
+    assert_eq!(CyberGrindPattern::parse(&bytes).unwrap()[..], pattern[..]);
+    assert!(CyberGrindPattern::parse_with(&bytes, &ParseOptions::strict()).is_err());
+
+    let mut out = Vec::new();
+    pattern.write_with(&mut out, &crlf).unwrap();
+    assert_eq!(out, bytes);
+    assert_eq!(
+        LineEnding::NATIVE.as_bytes(),
+        if cfg!(windows) { &b"\r\n"[..] } else { b"\n" }
+    );
+}
+
+#[test]
+fn prefab_bytes_round_trip() {
+    for prefab in Prefab::iter() {
+        assert_eq!(Prefab::try_from(prefab.byte()), Ok(prefab));
+        assert_eq!(prefab.name().parse(), Ok(prefab));
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn jump_pads_parse_back() {
+    let mut pattern = CyberGrindPattern::new();
+    pattern[17].set_prefab(Prefab::JumpPad);
+    pattern
+        .write_to_path("tests/patterns/jump_pad.cgp")
+        .unwrap();
+    let parsed = CyberGrindPattern::parse_path("tests/patterns/jump_pad.cgp").unwrap();
+    std::fs::remove_file("tests/patterns/jump_pad.cgp").unwrap();
+    assert_eq!(parsed[17].prefab(), Prefab::JumpPad);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_atomic() {
+    let pattern = CyberGrindPattern::splat(Tile::new(-7, Prefab::Stairs));
+    pattern
+        .write_to_path_atomic("tests/patterns/atomic.cgp")
+        .unwrap();
+    let read = CyberGrindPattern::parse_path("tests/patterns/atomic.cgp").unwrap();
+    std::fs::remove_file("tests/patterns/atomic.cgp").unwrap();
+    assert_eq!(read[..], pattern[..]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn concurrent_atomic_writes() {
+    std::fs::create_dir_all("tests/patterns/concurrent").unwrap();
+    let path = "tests/patterns/concurrent/atomic.cgp";
+    std::thread::scope(|scope| {
+        for height in 0..8 {
+            scope.spawn(move || {
+                let pattern = CyberGrindPattern::splat(Tile::with_height(height));
+                for _ in 0..20 {
+                    pattern.write_to_path_atomic(path).unwrap();
+                }
+            });
+        }
+    });
+
+    let read = CyberGrindPattern::parse_path(path).unwrap();
+    assert!(read[..].iter().all(|tile| *tile == read[0]));
+    let files = std::fs::read_dir("tests/patterns/concurrent")
+        .unwrap()
+        .count();
+    std::fs::remove_dir_all("tests/patterns/concurrent").unwrap();
+    assert_eq!(files, 1);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_with_backups() {
+    let path = "tests/patterns/backup.cgp";
+    let policy = WritePolicy {
+        backup: true,
+        max_backups: 2,
+        ..Default::default()
+    };
+    for height in 1..=4 {
+        CyberGrindPattern::splat(Tile::with_height(height))
+            .write_to_path_with(path, policy)
+            .unwrap();
+    }
+
+    let current = CyberGrindPattern::parse_path(path).unwrap();
+    let newest = CyberGrindPattern::parse_path("tests/patterns/backup.cgp.bak").unwrap();
+    let oldest = CyberGrindPattern::parse_path("tests/patterns/backup.cgp.bak.1").unwrap();
+    let extra = std::path::Path::new("tests/patterns/backup.cgp.bak.2").exists();
+    for file in [
+        path,
+        "tests/patterns/backup.cgp.bak",
+        "tests/patterns/backup.cgp.bak.1",
+    ] {
+        std::fs::remove_file(file).unwrap();
+    }
+
+    assert_eq!(current[0].height(), 4);
+    assert_eq!(newest[0].height(), 3);
+    assert_eq!(oldest[0].height(), 2);
+    assert!(!extra);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_locked() {
+    let path = "tests/patterns/locked.cgp";
+    let policy = WritePolicy {
+        lock: true,
+        ..Default::default()
+    };
+    let pattern = CyberGrindPattern::splat(Tile::new(50, Prefab::Projectile));
+    pattern.write_to_path_with(path, policy).unwrap();
+    let read = CyberGrindPattern::parse_path_locked(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(read[..], pattern[..]);
+
+    // Readers and writers share the lock file, which stays
+    let lock = "tests/patterns/locked.cgp.lock";
+    assert!(std::path::Path::new(lock).exists());
+    std::fs::remove_file(lock).unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_locked_with_backups() {
+    let path = "tests/patterns/locked_backup.cgp";
+    let backup = "tests/patterns/locked_backup.cgp.bak";
+    let policy = WritePolicy {
+        lock: true,
+        backup: true,
+        ..Default::default()
+    };
+    let first = CyberGrindPattern::splat(Tile::with_height(1));
+    let second = CyberGrindPattern::splat(Tile::with_height(2));
+
+    // Nothing to back up yet
+    first.write_to_path_with(path, policy).unwrap();
+    assert!(!std::path::Path::new(backup).exists());
+
+    // Overwriting a longer file leaves nothing behind
+    std::fs::write(path, [b'0'; 2000]).unwrap();
+    second.write_to_path_with(path, policy).unwrap();
+    assert_eq!(std::fs::read(backup).unwrap(), [b'0'; 2000]);
+    let read = CyberGrindPattern::parse_path_locked(path).unwrap();
+    assert_eq!(read[..], second[..]);
+
+    let atomic = WritePolicy {
+        atomic: true,
+        ..policy
+    };
+    first.write_to_path_with(path, atomic).unwrap();
+    let lock = "tests/patterns/locked_backup.cgp.lock";
+    assert_eq!(
+        CyberGrindPattern::parse_path(backup).unwrap()[..],
+        second[..]
+    );
+    assert_eq!(CyberGrindPattern::parse_path(path).unwrap()[..], first[..]);
+
+    for file in [path, backup, lock] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn single_digits_without_parentheses() {
+    let mut pattern = CyberGrindPattern::new();
+    for i in 0..10 {
+        pattern[i].set_height(i as i8);
+    }
+    pattern[10].set_height(10);
+    pattern.write_to_path("tests/patterns/digits.cgp").unwrap();
+    let bytes = std::fs::read("tests/patterns/digits.cgp").unwrap();
+    std::fs::remove_file("tests/patterns/digits.cgp").unwrap();
+
+    assert!(bytes.starts_with(b"0123456789(10)00000\n"));
+    assert_eq!(CyberGrindPattern::lint(&bytes).collect::<Vec<_>>(), []);
+}
+
+#[test]
+fn unknown_prefabs_round_trip() {
+    let text = std::fs::read_to_string("tests/patterns/valid/max_len.cgp").unwrap();
+    let (heights, prefabs) = text.split_once("\n\n").unwrap();
+    let prefabs = prefabs.replacen('n', "T", 1).replacen('n', "~", 30);
+    let modded = format!("{heights}\n\n{prefabs}");
+    let keep = ParseOptions {
+        unknown_prefab: UnknownPrefab::Keep,
+        ..Default::default()
+    };
+
+    assert!(CyberGrindPattern::parse_str(&modded).is_err());
+    let pattern = CyberGrindPattern::parse_with(modded.as_bytes(), &keep).unwrap();
+    assert_eq!(pattern.to_string(), modded);
+    assert_eq!(pattern[0].prefab(), Prefab::unknown(b'T').unwrap());
+    assert!(!Prefab::unknown(b'T').unwrap().spawns_enemy());
+    assert_eq!(Prefab::unknown(b'T').unwrap().name(), "unknown");
+    assert_eq!(Prefab::unknown(b'T').unwrap().to_string(), "T");
+
+    // Known characters never become unknown prefabs
+    assert_eq!(Prefab::unknown(b'n'), None);
+    assert_eq!(Prefab::unknown(b'\n'), None);
+    let unknown = Prefab::unknown(b'~').unwrap();
+    assert_eq!(Tile::with_prefab(unknown).prefab(), unknown);
+    assert_eq!(std::mem::size_of::<Tile>(), 2);
+
+    // Whitespace and control characters are still errors
+    let spaced = format!("{heights}\n\n {}", &prefabs[1..]);
+    assert!(CyberGrindPattern::parse_with(spaced.as_bytes(), &keep).is_err());
+}
+
+#[test]
+fn write_to_buf() {
+    let text = std::fs::read("tests/patterns/valid/max_len.cgp").unwrap();
+    let pattern = CyberGrindPattern::parse(&text).unwrap();
+    assert_eq!(pattern.serialized_len(), MAX_FILE_SIZE);
+
+    let mut buf = [0; MAX_FILE_SIZE + 10];
+    assert_eq!(pattern.write_to_buf(&mut buf), Ok(MAX_FILE_SIZE));
+    assert_eq!(&buf[..MAX_FILE_SIZE], &text[..]);
+
+    let mut small = [0; 100];
+    assert_eq!(
+        pattern.write_to_buf(&mut small),
+        Err(BufferTooSmall {
+            needed: MAX_FILE_SIZE,
+            len: 100
+        })
+    );
+    assert_eq!(small, [0; 100]);
+
+    for height in -50..=50 {
+        let pattern = CyberGrindPattern::splat(Tile::with_height(height));
+        assert_eq!(pattern.serialized_len(), pattern.to_bytes().len());
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn locked_readers_wait_for_writers() {
+    let path = "tests/patterns/locked_readers.cgp";
+    let policy = WritePolicy {
+        lock: true,
+        atomic: true,
+        backup: true,
+        ..Default::default()
+    };
+    CyberGrindPattern::new()
+        .write_to_path_with(path, policy)
+        .unwrap();
+    std::thread::scope(|scope| {
+        for height in 1..5 {
+            scope.spawn(move || {
+                let pattern = CyberGrindPattern::splat(Tile::with_height(height));
+                for _ in 0..20 {
+                    pattern.write_to_path_with(path, policy).unwrap();
+                }
+            });
+        }
+        scope.spawn(|| {
+            for _ in 0..50 {
+                let read = CyberGrindPattern::parse_path_locked(path).unwrap();
+                assert!(read[..].iter().all(|tile| *tile == read[0]));
+            }
+        });
+    });
+
+    // Backups are only taken under the lock, so they're never partial
+    let backup = "tests/patterns/locked_readers.cgp.bak";
+    CyberGrindPattern::parse_path(backup).unwrap();
+    for file in [path, backup, "tests/patterns/locked_readers.cgp.lock"] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
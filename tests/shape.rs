@@ -0,0 +1,258 @@
+use tinycbg::*;
+
+// Checks that every selected tile touches another
+// one, diagonals included, and that both ends are set.
+fn assert_connected(selection: &Selection, a: (usize, usize), b: (usize, usize)) {
+    assert!(selection.contains(a) && selection.contains(b));
+    if selection.len() == 1 {
+        return;
+    }
+    for (x, y) in selection.positions() {
+        let neighbours = Rect::around((x, y), 1)
+            .positions()
+            .filter(|&pos| pos != (x, y) && selection.contains(pos))
+            .count();
+        assert!(neighbours > 0, "({x}, {y}) isn't connected");
+    }
+}
+
+#[test]
+fn lines_in_every_direction() {
+    let ends = [
+        (0, 0),
+        (15, 15),
+        (15, 0),
+        (0, 15),
+        (7, 3),
+        (2, 11),
+        (9, 9),
+        (13, 6),
+    ];
+    for a in ends {
+        for b in ends {
+            let line = Selection::line(a, b);
+            let dx = a.0.abs_diff(b.0);
+            let dy = a.1.abs_diff(b.1);
+            assert_eq!(line.len(), dx.max(dy) + 1, "{a:?} to {b:?}");
+            assert_connected(&line, a, b);
+        }
+    }
+}
+
+#[test]
+fn selection_basics() {
+    let mut selection = Selection::new();
+    assert!(selection.is_empty());
+    selection.insert((3, 4));
+    selection.insert((16, 0));
+    selection.insert((0, 0));
+    assert_eq!(selection.len(), 2);
+    assert_eq!(selection.positions().collect::<Vec<_>>(), [(0, 0), (3, 4)]);
+    selection.remove((0, 0));
+    assert!(!selection.contains((0, 0)));
+    assert!(!selection.contains((16, 0)));
+
+    let mut pat = CyberGrindPattern::new();
+    pat.fill_selection(&selection, Tile::with_height(7));
+    assert_eq!(pat[(3, 4)].height(), 7);
+    assert_eq!(pat[..].iter().filter(|tile| tile.height() == 7).count(), 1);
+}
+
+#[test]
+fn thick_lines() {
+    for (a, b) in [((3, 3), (12, 9)), ((14, 1), (2, 13)), ((5, 5), (5, 5))] {
+        let line = Selection::line(a, b);
+        assert_eq!(Selection::thick_line(a, b, 0), line);
+        assert_eq!(Selection::thick_line(a, b, 1), line);
+
+        let mut last = line;
+        for width in 2..6 {
+            let thick = Selection::thick_line(a, b, width);
+            assert!(last.positions().all(|pos| thick.contains(pos)));
+            assert!(thick.len() > last.len());
+            last = thick;
+        }
+    }
+
+    let vertical = Selection::thick_line((8, 15), (8, 0), 5);
+    assert_eq!(vertical.len(), 16 * 5);
+    assert!(vertical.contains((6, 0)) && vertical.contains((10, 15)));
+}
+
+#[test]
+fn paths() {
+    assert!(Selection::path(&[]).is_empty());
+    assert_eq!(
+        Selection::path(&[(4, 4)]).positions().collect::<Vec<_>>(),
+        [(4, 4)]
+    );
+
+    let points = [(1, 1), (14, 1), (14, 14), (1, 14), (1, 1)];
+    let path = Selection::path(&points);
+    assert_eq!(path.len(), 4 * 13);
+    for segment in points.windows(2) {
+        let line = Selection::line(segment[0], segment[1]);
+        assert!(line.positions().all(|pos| path.contains(pos)));
+    }
+    assert!(!path.contains((2, 2)));
+}
+
+#[test]
+fn circles_and_ellipses() {
+    assert_eq!(
+        Selection::circle((3, 3), 0).positions().collect::<Vec<_>>(),
+        [(3, 3)]
+    );
+
+    for radius in 1..8 {
+        let circle = Selection::circle((7, 7), radius);
+        // Every tile is about `radius` away from the center
+        for (x, y) in circle.positions() {
+            let (dx, dy) = (x as f32 - 7.0, y as f32 - 7.0);
+            let distance = (dx * dx + dy * dy).sqrt();
+            assert!(
+                (distance - radius as f32).abs() < 0.75,
+                "{radius}: ({x}, {y})"
+            );
+        }
+        for (x, y) in circle.positions() {
+            assert!(circle.contains((14 - x, y)) && circle.contains((y, x)));
+        }
+        assert_connected(&circle, (7 + radius, 7), (7, 7 - radius));
+    }
+
+    let ellipse = Selection::ellipse((8, 8), 7, 2);
+    assert!(ellipse.contains((1, 8)) && ellipse.contains((15, 8)));
+    assert!(ellipse.contains((8, 6)) && ellipse.contains((8, 10)));
+    assert!(ellipse.positions().all(|(_, y)| (6..=10).contains(&y)));
+    assert_connected(&ellipse, (1, 8), (8, 6));
+
+    // Flat ellipses are lines
+    assert_eq!(
+        Selection::ellipse((8, 8), 0, 3),
+        Selection::line((8, 5), (8, 11))
+    );
+    assert_eq!(
+        Selection::ellipse((8, 8), 4, 0),
+        Selection::line((4, 8), (12, 8))
+    );
+
+    // Clipped to the grid
+    let corner = Selection::circle((0, 0), 4);
+    assert_eq!(corner, Selection::ellipse((0, 0), 4, 4));
+    assert!(corner.contains((4, 0)) && corner.contains((0, 4)));
+}
+
+#[test]
+fn discs() {
+    for (rx, ry) in [(0, 0), (3, 3), (6, 2), (1, 7), (20, 20)] {
+        let outline = Selection::ellipse((7, 8), rx, ry);
+        let filled = Selection::filled_ellipse((7, 8), rx, ry);
+        assert!(outline.positions().all(|pos| filled.contains(pos)));
+        // Rows have no gaps between the edges
+        for y in 0..16 {
+            let xs: Vec<_> = (0..16).filter(|&x| filled.contains((x, y))).collect();
+            if let (Some(first), Some(last)) = (xs.first(), xs.last()) {
+                assert_eq!(xs.len(), last - first + 1);
+            }
+        }
+    }
+    assert_eq!(
+        Selection::disc((7, 8), 3),
+        Selection::filled_ellipse((7, 8), 3, 3)
+    );
+    assert_eq!(Selection::disc((7, 8), 30).len(), 256);
+
+    // Flat ellipses fill a line
+    assert_eq!(Selection::disc((4, 4), 0).len(), 1);
+    let column = Selection::filled_ellipse((7, 8), 0, 4);
+    assert_eq!(column, Selection::rect_filled(Rect::new(7, 4, 1, 9)));
+    let row = Selection::filled_ellipse((7, 8), 5, 0);
+    assert_eq!(row, Selection::rect_filled(Rect::new(2, 8, 11, 1)));
+}
+
+#[test]
+fn rects() {
+    let rect = Rect::new(3, 4, 6, 5);
+    let outline = Selection::rect_outline(rect);
+    let filled = Selection::rect_filled(rect);
+    assert_eq!(outline.len(), 2 * 6 + 2 * 3);
+    assert_eq!(filled.len(), rect.area());
+    for pos in Rect::FULL.positions() {
+        assert_eq!(filled.contains(pos), rect.contains(pos));
+        let inner = Rect::new(4, 5, 4, 3).contains(pos);
+        assert_eq!(outline.contains(pos), rect.contains(pos) && !inner);
+    }
+
+    assert_eq!(Selection::rect_outline(Rect::new(5, 5, 1, 1)).len(), 1);
+    assert!(Selection::rect_outline(Rect::new(5, 5, 0, 3)).is_empty());
+    // The right edge is off the grid
+    let clipped = Selection::rect_outline(Rect::new(12, 0, 8, 3));
+    assert_eq!(clipped.len(), 4 + 4 + 1);
+    assert_eq!(Selection::rect_filled(Rect::FULL).len(), 256);
+}
+
+#[test]
+fn polygons() {
+    assert!(Selection::polygon(&[]).is_empty());
+    assert_eq!(Selection::polygon(&[(3, 3)]).len(), 1);
+    assert_eq!(
+        Selection::polygon(&[(3, 3), (6, 3)]),
+        Selection::line((3, 3), (6, 3))
+    );
+
+    let square = Selection::polygon(&[(2, 2), (9, 2), (9, 9), (2, 9)]);
+    assert_eq!(square, Selection::rect_filled(Rect::new(2, 2, 8, 8)));
+
+    let hexagon = [(5, 1), (10, 1), (14, 7), (10, 13), (5, 13), (1, 7)];
+    let filled = Selection::polygon(&hexagon);
+    let mut outline = Selection::path(&hexagon);
+    outline.insert(hexagon[0]);
+    for pos in Selection::line(hexagon[5], hexagon[0]).positions() {
+        outline.insert(pos);
+    }
+    assert!(outline.positions().all(|pos| filled.contains(pos)));
+    assert!(filled.contains((7, 7)) && filled.contains((3, 7)));
+    assert!(!filled.contains((1, 1)) && !filled.contains((15, 7)));
+    // Rows have no gaps between the edges
+    for y in 0..16 {
+        let xs: Vec<_> = (0..16).filter(|&x| filled.contains((x, y))).collect();
+        if let (Some(first), Some(last)) = (xs.first(), xs.last()) {
+            assert_eq!(xs.len(), last - first + 1);
+        }
+    }
+}
+
+#[test]
+fn bezier_curves() {
+    assert_eq!(Selection::bezier((3, 3), (3, 3), (3, 3)).len(), 1);
+    // Straight when the control point is on the line
+    assert_eq!(
+        Selection::bezier((0, 4), (6, 4), (12, 4)),
+        Selection::line((0, 4), (12, 4))
+    );
+
+    let curve = Selection::bezier((1, 14), (8, 0), (14, 14));
+    assert_connected(&curve, (1, 14), (14, 14));
+    assert!(curve.contains((7, 7)) || curve.contains((8, 7)));
+    assert!(!curve.contains((8, 0)));
+    for (x, y) in curve.positions() {
+        assert!(y >= 6, "({x}, {y}) is past the peak");
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn arcs() {
+    let circle = Selection::circle((8, 8), 6);
+    let full = Selection::arc((8, 8), 6, 0.0, 359.9);
+    assert_eq!(full, circle);
+
+    let right = Selection::arc((8, 8), 6, -90.0, 90.0);
+    let left = Selection::arc((8, 8), 6, 90.0, 270.0);
+    for pos in circle.positions() {
+        assert!(right.contains(pos) || left.contains(pos));
+        assert_eq!(right.contains(pos), pos.0 >= 8);
+        assert_eq!(left.contains(pos), pos.0 <= 8);
+    }
+}
@@ -0,0 +1,41 @@
+use tinycbg::*;
+
+#[test]
+fn spans_match_source() {
+    let bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let (pattern, map) = CyberGrindPattern::parse_mapped(&bytes).unwrap();
+    assert_eq!(pattern[..], CyberGrindPattern::parse(&bytes).unwrap()[..]);
+
+    for index in 0..256 {
+        let span = map.prefab_span(index);
+        assert_eq!(bytes[span.start], pattern[index].prefab().byte());
+        assert_eq!(span.line as usize, index / 16 + 18);
+        assert_eq!(span.column as usize, index % 16 + 1);
+
+        let span = map.height_span(index);
+        let text = std::str::from_utf8(&bytes[span.start..span.end]).unwrap();
+        let height: i8 = text.trim_matches(['(', ')']).parse().unwrap();
+        assert_eq!(height, pattern[index].height());
+    }
+}
+
+#[test]
+fn find() {
+    let bytes = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let (_, map) = CyberGrindPattern::parse_mapped(&bytes).unwrap();
+
+    for index in 0..256 {
+        for layer in [Layer::Height, Layer::Prefab] {
+            let span = map.span(index, layer);
+            for offset in span.start..span.end {
+                assert_eq!(map.find(offset), Some((index, layer)));
+            }
+        }
+    }
+
+    // Newlines don't belong to any tile
+    let newline = map.height_span(15).end;
+    assert_eq!(bytes[newline], b'\n');
+    assert_eq!(map.find(newline), None);
+    assert_eq!(map.find(bytes.len()), None);
+}
@@ -0,0 +1,177 @@
+use tinycbg::error::HeightError;
+use tinycbg::*;
+
+#[test]
+fn try_new() {
+    assert_eq!(
+        Tile::try_new(-50, Prefab::Stairs),
+        Ok(Tile::new(-50, Prefab::Stairs))
+    );
+    assert_eq!(
+        Tile::try_new(-51, Prefab::None),
+        Err(HeightError { height: -51 })
+    );
+
+    let mut tile = Tile::with_height(7);
+    assert_eq!(tile.try_set_height(51), Err(HeightError { height: 51 }));
+    assert_eq!(tile.height(), 7);
+    assert_eq!(tile.try_set_height(-12), Ok(()));
+    assert_eq!(tile.height(), -12);
+}
+
+#[test]
+fn saturating() {
+    let tile = Tile::new(40, Prefab::JumpPad);
+    assert_eq!(tile.saturating_add(5), Tile::new(45, Prefab::JumpPad));
+    assert_eq!(tile.saturating_add(i8::MAX), Tile::new(50, Prefab::JumpPad));
+    assert_eq!(tile.saturating_sub(i8::MAX).height(), -50);
+    assert_eq!(tile.saturating_sub(i8::MIN).height(), 50);
+    assert_eq!(tile.saturating_add(i8::MIN).height(), -50);
+    assert_eq!(Tile::with_height(-50).saturating_sub(1).height(), -50);
+}
+
+#[test]
+fn checked() {
+    let tile = Tile::new(-20, Prefab::Projectile);
+    assert_eq!(
+        tile.checked_add(70),
+        Some(Tile::new(50, Prefab::Projectile))
+    );
+    assert_eq!(tile.checked_add(71), None);
+    assert_eq!(
+        tile.checked_sub(30),
+        Some(Tile::new(-50, Prefab::Projectile))
+    );
+    assert_eq!(tile.checked_sub(31), None);
+    assert_eq!(tile.checked_sub(i8::MIN), None);
+    assert_eq!(tile.checked_add(i8::MAX), None);
+}
+
+#[test]
+fn invert() {
+    let text = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let pattern = CyberGrindPattern::parse(&text).unwrap();
+    let mut inverted = pattern;
+    inverted.invert_heights();
+    for (tile, flipped) in pattern[..].iter().zip(&inverted[..]) {
+        assert_eq!(flipped.height(), -tile.height());
+        assert_eq!(flipped.prefab(), tile.prefab());
+        assert_eq!(-*flipped, *tile);
+    }
+}
+
+#[test]
+fn assign_ops() {
+    let mut tile = Tile::new(10, Prefab::Stairs);
+    tile += 15;
+    assert_eq!(tile, Tile::new(25, Prefab::Stairs));
+    tile -= 40;
+    assert_eq!(tile, Tile::new(-15, Prefab::Stairs));
+
+    let overflow = std::panic::catch_unwind(|| {
+        let mut tile = Tile::with_height(45);
+        tile += 10;
+    });
+    assert!(overflow.is_err());
+}
+
+#[test]
+fn raise_and_lower() {
+    let text = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let pattern = CyberGrindPattern::parse(&text).unwrap();
+
+    let mut raised = pattern;
+    raised.raise_all(10, OutOfRange::Clamp);
+    let mut lowered = pattern;
+    lowered.lower_all(i8::MIN, OutOfRange::Replace(-7));
+    for index in 0..256 {
+        let height = pattern[index].height();
+        assert_eq!(raised[index].height(), (height + 10).min(50));
+        assert_eq!(raised[index].prefab(), pattern[index].prefab());
+        assert_eq!(lowered[index].height(), -7);
+    }
+
+    let mut round_trip = pattern;
+    round_trip.raise_all(-20, OutOfRange::Clamp);
+    round_trip.lower_all(-20, OutOfRange::Clamp);
+    assert_eq!(round_trip[20..256], pattern[20..256]);
+    assert_eq!(round_trip[0].height(), -30);
+}
+
+#[test]
+fn combine_patterns() {
+    let text = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let range = CyberGrindPattern::parse(&text).unwrap();
+    let mut detail = CyberGrindPattern::splat(Tile::new(5, Prefab::Projectile));
+    detail[255] = Tile::with_height(-5);
+
+    let sum = range + &detail;
+    let difference = range - &detail;
+    for index in 0..256 {
+        let height = range[index].height();
+        let offset = detail[index].height();
+        assert_eq!(sum[index].height(), (height + offset).clamp(-50, 50));
+        assert_eq!(difference[index].height(), (height - offset).clamp(-50, 50));
+        assert_eq!(difference[index].prefab(), range[index].prefab());
+    }
+    assert_eq!(sum[0].prefab(), Prefab::Projectile);
+    assert_eq!(sum[251].prefab(), Prefab::Melee);
+    assert_eq!(sum[255].prefab(), Prefab::Stairs);
+
+    let unchanged = range.combine_with(&detail, |a, _| a);
+    assert_eq!(unchanged[..], range[..]);
+}
+
+#[test]
+fn combine_max_min() {
+    let text = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let range = CyberGrindPattern::parse(&text).unwrap();
+    let flat = CyberGrindPattern::splat(Tile::new(3, Prefab::Stairs));
+
+    let max = range.combine_max(&flat, PrefabSide::Left);
+    let min = range.combine_min(&flat, PrefabSide::Right);
+    for index in 0..256 {
+        let height = range[index].height();
+        assert_eq!(max[index].height(), height.max(3));
+        assert_eq!(max[index].prefab(), range[index].prefab());
+        assert_eq!(min[index].height(), height.min(3));
+        assert_eq!(min[index].prefab(), Prefab::Stairs);
+    }
+}
+
+#[test]
+fn map_heights() {
+    let text = std::fs::read("tests/patterns/valid/range.cgp").unwrap();
+    let range = CyberGrindPattern::parse(&text).unwrap();
+
+    let mut curved = range;
+    curved.map_heights(
+        |height| i32::from(height.signum() * (height / 5).pow(2)),
+        OutOfRange::Replace(50),
+    );
+    let mut negated = range;
+    negated.try_map_heights(|height| -height as i32).unwrap();
+    for index in 0..256 {
+        let height = range[index].height();
+        let expected = height.signum() * (height / 5).pow(2);
+        let expected = if expected.abs() > 50 { 50 } else { expected };
+        assert_eq!(curved[index].height(), expected);
+        assert_eq!(negated[index], -range[index]);
+    }
+
+    let mut failed = range;
+    assert_eq!(
+        failed.try_map_heights(|height| height as i32 + 1),
+        Err(HeightError { height: 51 })
+    );
+    assert_eq!(failed[..], range[..]);
+
+    // Steep curves are clamped rather than overflowing
+    let mut steep = CyberGrindPattern::splat(Tile::with_height(50));
+    steep.map_heights(|height| height as i32 * 3, OutOfRange::Clamp);
+    assert!(steep[..].iter().all(|tile| tile.height() == 50));
+    assert_eq!(
+        steep.try_map_heights(|height| height as i32 * 300),
+        Err(HeightError { height: i8::MAX })
+    );
+}
@@ -0,0 +1,120 @@
+use tinycbg::*;
+
+fn numbered() -> CyberGrindPattern {
+    let mut pat = CyberGrindPattern::new();
+    for i in 0..256 {
+        pat[i] = Tile::new(
+            (i % 101) as i8 - 50,
+            Prefab::from_byte(b"0nprJsH"[i % 7]).unwrap(),
+        );
+    }
+    pat
+}
+
+#[test]
+fn rotations() {
+    let original = numbered();
+
+    let mut pat = original;
+    pat.rotate_cw();
+    for (x, y) in Rect::FULL.positions() {
+        assert_eq!(pat[(15 - y, x)], original[(x, y)]);
+    }
+    pat.rotate_ccw();
+    assert!(pat[..] == original[..]);
+
+    let mut twice = original;
+    twice.rotate_cw();
+    twice.rotate_cw();
+    let mut half = original;
+    half.rotate_180();
+    assert!(twice[..] == half[..]);
+
+    for _ in 0..2 {
+        half.rotate_ccw();
+    }
+    assert!(half[..] == original[..]);
+}
+
+#[test]
+fn flips() {
+    let original = numbered();
+
+    let mut pat = original;
+    pat.flip_horizontal();
+    for (x, y) in Rect::FULL.positions() {
+        assert_eq!(pat[(15 - x, y)], original[(x, y)]);
+    }
+    pat.flip_vertical();
+    let mut half = original;
+    half.rotate_180();
+    assert!(pat[..] == half[..]);
+
+    let mut pat = original;
+    pat.transpose();
+    for (x, y) in Rect::FULL.positions() {
+        assert_eq!(pat[(y, x)], original[(x, y)]);
+    }
+    // Transposing and flipping is the same as rotating
+    pat.flip_horizontal();
+    let mut rotated = original;
+    rotated.rotate_cw();
+    assert!(pat[..] == rotated[..]);
+}
+
+#[test]
+fn shifts() {
+    let original = numbered();
+
+    let mut pat = original;
+    pat.shift_x(5);
+    pat.shift_y(-7);
+    for (x, y) in Rect::FULL.positions() {
+        assert_eq!(pat[((x + 5) % 16, (y + 9) % 16)], original[(x, y)]);
+    }
+
+    pat.shift_x(-5 + 32);
+    pat.shift_y(7 - 48);
+    assert!(pat[..] == original[..]);
+}
+
+#[test]
+fn mirror_quadrants() {
+    let original = numbered();
+    let quadrants = [
+        Quadrant::TopLeft,
+        Quadrant::TopRight,
+        Quadrant::BottomLeft,
+        Quadrant::BottomRight,
+    ];
+    for quadrant in quadrants {
+        let mut pat = original;
+        pat.mirror_quadrant(quadrant);
+        for pos in quadrant.rect().positions() {
+            assert_eq!(pat[pos], original[pos]);
+        }
+        for (x, y) in Rect::FULL.positions() {
+            assert_eq!(pat[(x, y)], pat[(15 - x, y)]);
+            assert_eq!(pat[(x, y)], pat[(x, 15 - y)]);
+        }
+    }
+}
+
+#[test]
+fn rotational_symmetry() {
+    let original = numbered();
+
+    let mut pat = original;
+    pat.apply_rotational_symmetry(SymmetryOrder::Two);
+    assert!(pat[0..8] == original[0..8]);
+    let mut rotated = pat;
+    rotated.rotate_180();
+    assert!(pat[..] == rotated[..]);
+
+    let mut pat = original;
+    pat.apply_rotational_symmetry(SymmetryOrder::Four);
+    assert!(pat[0..8] == original[0..8]);
+    let mut rotated = pat;
+    rotated.rotate_cw();
+    assert!(pat[..] == rotated[..]);
+}